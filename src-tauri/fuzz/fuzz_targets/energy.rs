@@ -0,0 +1,26 @@
+// energy.rs - Fuzz the RMS energy used by the wake word energy gate and calibration
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Both halves of the pipeline: raw i16 frames, and frames converted from f32
+    let frames: Vec<i16> = data
+        .chunks_exact(2)
+        .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect();
+    let floats: Vec<f32> = data
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+
+    for samples in [frames, app_lib::convert_samples(&floats)] {
+        let energy = app_lib::rms(&samples);
+        assert!(energy.is_finite());
+        // i16::MIN is one step past full scale, so allow a hair over 1.0
+        assert!((0.0..=1.001).contains(&energy), "rms out of range: {}", energy);
+        if samples.iter().all(|&sample| sample == 0) {
+            assert_eq!(energy, 0.0);
+        }
+    }
+});
@@ -0,0 +1,25 @@
+// sample_conversion.rs - Fuzz the f32 -> i16 conversion AudioCapture applies to device buffers
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Reinterpret the input as little-endian f32 samples, like a float device buffer
+    let samples: Vec<f32> = data
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+
+    let converted = app_lib::convert_samples(&samples);
+
+    assert_eq!(converted.len(), samples.len());
+    for (&sample, &output) in samples.iter().zip(&converted) {
+        if sample.is_nan() {
+            assert_eq!(output, 0, "NaN must convert to silence");
+        } else if sample >= 1.0 {
+            assert_eq!(output, i16::MAX);
+        } else if sample <= -1.0 {
+            assert_eq!(output, i16::MIN);
+        }
+    }
+});
@@ -0,0 +1,142 @@
+// agc.rs - Automatic gain control, so quiet microphones are boosted and loud
+// ones are kept from clipping
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::pipeline::AudioProcessor;
+
+// Loudness is measured over this much of the most recent input
+const RMS_WINDOW: Duration = Duration::from_millis(500);
+// How fast the gain falls when the input gets louder, and rises when it gets quieter
+const ATTACK: Duration = Duration::from_millis(10);
+const RELEASE: Duration = Duration::from_millis(100);
+// Never boost by more than 26 dB, or cut by more than 20 dB
+const MAX_GAIN: f32 = 20.0;
+const MIN_GAIN: f32 = 0.1;
+// Below this the input is treated as silence and the gain is left alone,
+// rather than amplifying the noise floor
+const SILENCE_RMS: f64 = 0.001;
+
+pub struct AutoGainControl {
+    target_rms: f32,
+    gain: f32,
+    // Sum of squares and sample count of each recent frame
+    window: VecDeque<(f64, usize)>,
+    window_sum: f64,
+    window_samples: usize,
+}
+
+impl AutoGainControl {
+    pub fn new(target_rms: f32) -> Self {
+        Self {
+            target_rms,
+            gain: 1.0,
+            window: VecDeque::new(),
+            window_sum: 0.0,
+            window_samples: 0,
+        }
+    }
+
+    fn measure(&mut self, frame: &[i16], sample_rate: u32) -> f64 {
+        let sum: f64 = frame
+            .iter()
+            .map(|&sample| {
+                let normalized = sample as f64 / i16::MAX as f64;
+                normalized * normalized
+            })
+            .sum();
+        self.window.push_back((sum, frame.len()));
+        self.window_sum += sum;
+        self.window_samples += frame.len();
+
+        let max_samples = (RMS_WINDOW.as_secs_f64() * sample_rate as f64) as usize;
+        while self.window_samples > max_samples && self.window.len() > 1 {
+            if let Some((sum, count)) = self.window.pop_front() {
+                self.window_sum -= sum;
+                self.window_samples -= count;
+            }
+        }
+        (self.window_sum.max(0.0) / self.window_samples.max(1) as f64).sqrt()
+    }
+}
+
+// Per-sample smoothing coefficient for a one-pole ramp taking `time`
+fn ramp_coefficient(time: Duration, sample_rate: u32) -> f32 {
+    1.0 - (-1.0 / (time.as_secs_f64() * sample_rate as f64)).exp() as f32
+}
+
+impl AudioProcessor for AutoGainControl {
+    fn process(&mut self, frame: &mut [i16], sample_rate: u32) {
+        let rms = self.measure(frame, sample_rate);
+        let desired = if rms < SILENCE_RMS {
+            self.gain
+        } else {
+            (self.target_rms / rms as f32).clamp(MIN_GAIN, MAX_GAIN)
+        };
+        let coefficient = if desired < self.gain {
+            ramp_coefficient(ATTACK, sample_rate)
+        } else {
+            ramp_coefficient(RELEASE, sample_rate)
+        };
+
+        for sample in frame.iter_mut() {
+            self.gain += (desired - self.gain) * coefficient;
+            *sample = (*sample as f32 * self.gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+        self.window.clear();
+        self.window_sum = 0.0;
+        self.window_samples = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio;
+
+    const RATE: u32 = 16_000;
+    const TARGET: f32 = 0.2;
+
+    // `duration_ms` of a 440 Hz sine with the given RMS, processed in 10 ms
+    // frames; returns the RMS of the last 100 ms of output
+    fn run_sine(agc: &mut AutoGainControl, rms: f32, duration_ms: usize) -> f32 {
+        let amplitude = rms * std::f32::consts::SQRT_2 * i16::MAX as f32;
+        let samples: Vec<i16> = (0..duration_ms * RATE as usize / 1000)
+            .map(|i| (amplitude * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / RATE as f32).sin()) as i16)
+            .collect();
+        let mut output = Vec::with_capacity(samples.len());
+        for chunk in samples.chunks(160) {
+            let mut frame = chunk.to_vec();
+            agc.process(&mut frame, RATE);
+            output.extend(frame);
+        }
+        audio::rms(&output[output.len() - 1_600..])
+    }
+
+    fn db(value: f32, reference: f32) -> f32 {
+        20.0 * (value / reference).log10()
+    }
+
+    #[test]
+    fn boosts_a_quiet_signal_to_near_the_target() {
+        let mut agc = AutoGainControl::new(TARGET);
+        // 20 dB below target
+        let output = run_sine(&mut agc, TARGET / 10.0, 600);
+        assert!(db(output, TARGET).abs() <= 3.0, "output {:.3} is {:.1} dB off", output, db(output, TARGET));
+    }
+
+    #[test]
+    fn cuts_a_loud_signal_and_recovers_after_reset() {
+        let mut agc = AutoGainControl::new(TARGET);
+        let output = run_sine(&mut agc, 0.6, 600);
+        assert!(db(output, TARGET).abs() <= 3.0);
+
+        agc.reset();
+        let output = run_sine(&mut agc, TARGET / 10.0, 600);
+        assert!(db(output, TARGET).abs() <= 3.0);
+    }
+}
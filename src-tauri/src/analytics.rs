@@ -0,0 +1,148 @@
+// analytics.rs - Usage summary of each speech session, kept for the last few sessions
+use serde::Serialize;
+use std::collections::VecDeque;
+
+pub const DEFAULT_SESSION_HISTORY: usize = 50;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SessionAnalytics {
+    pub session_id: u64,
+    // Milliseconds since the UNIX epoch
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub phrase_count: u32,
+    // Phrases that matched an intent
+    pub commands_executed: u32,
+    // Over the phrases that had a confidence; 0 if none did
+    pub average_confidence: f32,
+    // Speech that reached the recognizer
+    pub total_audio_ms: u64,
+}
+
+struct InProgress {
+    session_id: u64,
+    started_at: u64,
+    phrase_count: u32,
+    commands_executed: u32,
+    confidence_sum: f32,
+    confidence_count: u32,
+}
+
+pub struct SessionAnalyticsTracker {
+    current: Option<InProgress>,
+    // Newest last
+    history: VecDeque<SessionAnalytics>,
+    capacity: usize,
+}
+
+impl SessionAnalyticsTracker {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            current: None,
+            history: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn start(&mut self, session_id: u64, now_ms: u64) {
+        self.current = Some(InProgress {
+            session_id,
+            started_at: now_ms,
+            phrase_count: 0,
+            commands_executed: 0,
+            confidence_sum: 0.0,
+            confidence_count: 0,
+        });
+    }
+
+    fn current_for(&mut self, session_id: u64) -> Option<&mut InProgress> {
+        self.current.as_mut().filter(|current| current.session_id == session_id)
+    }
+
+    pub fn record_phrase(&mut self, session_id: u64, confidence: Option<f32>) {
+        if let Some(current) = self.current_for(session_id) {
+            current.phrase_count += 1;
+            if let Some(confidence) = confidence {
+                current.confidence_sum += confidence;
+                current.confidence_count += 1;
+            }
+        }
+    }
+
+    pub fn record_command(&mut self, session_id: u64) {
+        if let Some(current) = self.current_for(session_id) {
+            current.commands_executed += 1;
+        }
+    }
+
+    // Close the session and keep its summary; None if it was never started
+    pub fn finish(&mut self, session_id: u64, now_ms: u64, total_audio_ms: u64) -> Option<SessionAnalytics> {
+        self.current_for(session_id)?;
+        let current = self.current.take()?;
+        let summary = SessionAnalytics {
+            session_id,
+            started_at: current.started_at,
+            ended_at: now_ms,
+            phrase_count: current.phrase_count,
+            commands_executed: current.commands_executed,
+            average_confidence: if current.confidence_count > 0 {
+                current.confidence_sum / current.confidence_count as f32
+            } else {
+                0.0
+            },
+            total_audio_ms,
+        };
+        self.history.push_back(summary.clone());
+        while self.history.len() > self.capacity {
+            self.history.pop_front();
+        }
+        Some(summary)
+    }
+
+    // Up to `limit` sessions, newest first
+    pub fn recent(&self, limit: usize) -> Vec<SessionAnalytics> {
+        self.history.iter().rev().take(limit).cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_a_session_and_keeps_the_newest() {
+        let mut tracker = SessionAnalyticsTracker::new(2);
+        tracker.start(1, 1_000);
+        tracker.record_phrase(1, Some(0.8));
+        tracker.record_phrase(1, None);
+        tracker.record_phrase(1, Some(0.6));
+        tracker.record_command(1);
+        // Stragglers from another session don't count
+        tracker.record_phrase(7, Some(0.1));
+
+        let summary = tracker.finish(1, 4_000, 1_500).unwrap();
+        assert_eq!(summary.phrase_count, 3);
+        assert_eq!(summary.commands_executed, 1);
+        assert!((summary.average_confidence - 0.7).abs() < 1e-6);
+        assert_eq!((summary.started_at, summary.ended_at, summary.total_audio_ms), (1_000, 4_000, 1_500));
+        assert!(tracker.finish(1, 5_000, 0).is_none());
+
+        for id in 2..=3 {
+            tracker.start(id, 0);
+            tracker.finish(id, 0, 0);
+        }
+        let ids: Vec<u64> = tracker.recent(10).iter().map(|summary| summary.session_id).collect();
+        assert_eq!(ids, vec![3, 2]);
+    }
+}
@@ -4,12 +4,49 @@ use cpal::traits::HostTrait;
 use cpal::traits::StreamTrait;
 use cpal::{Device, Sample, SampleFormat, Stream, StreamConfig};
 use crossbeam_channel::{unbounded, Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Rate the devices of a multi-device capture are resampled to before mixing
+pub const MIX_SAMPLE_RATE: u32 = 16_000;
+
+// How much audio is mixed at a time across devices
+const MIX_WINDOW: Duration = Duration::from_millis(50);
+
+// Audio held per device while waiting for the others; a device that runs ahead
+// by more than this loses its oldest samples
+const MAX_MIX_BACKLOG: Duration = Duration::from_secs(1);
+
+type DeviceErrorHandler = Arc<dyn Fn(String) + Send + Sync>;
+
+// Which channels of a multi-channel device (e.g. a conference room array)
+// make up the mono audio. Channels are numbered from 0.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelRouting {
+    // Average every channel
+    #[default]
+    MixAll,
+    // Only this channel
+    SelectChannel(u16),
+    // Average only these channels
+    BeamformChannels(Vec<u16>),
+}
 
 pub struct AudioCapture {
     is_capturing: Arc<Mutex<bool>>,
     shutdown_sender: Option<Sender<()>>,
     _stream: Option<Stream>, // Keep the stream alive
+    _streams: Vec<Stream>,   // ...or streams, when mixing several devices
+    device_error_handler: Option<DeviceErrorHandler>,
+    channel_routing: ChannelRouting,
+    // Rate to convert to when the device runs at another one
+    resample_to: Option<u32>,
 }
 
 impl AudioCapture {
@@ -18,9 +55,123 @@ impl AudioCapture {
             is_capturing: Arc::new(Mutex::new(false)),
             shutdown_sender: None,
             _stream: None,
+            _streams: Vec::new(),
+            device_error_handler: None,
+            channel_routing: ChannelRouting::default(),
+            resample_to: None,
         }
     }
 
+    // Applies to streams started after this
+    pub fn with_channel_routing(mut self, routing: ChannelRouting) -> Self {
+        self.channel_routing = routing;
+        self
+    }
+
+    // Deliver audio at `target_hz` whatever the device's native rate, e.g. a
+    // virtual cable running at 44.1 or 96 kHz. Applies to streams started
+    // with start_capture_with_sample_rate after this.
+    pub fn auto_resample_to(mut self, target_hz: u32) -> Self {
+        self.resample_to = Some(target_hz);
+        self
+    }
+
+    // Called with the device name when one stream of a multi-device capture
+    // fails; the other devices keep capturing
+    pub fn on_device_error(&mut self, handler: impl Fn(String) + Send + Sync + 'static) {
+        self.device_error_handler = Some(Arc::new(handler));
+    }
+
+    // Capture from several devices at once (None is the default device) and
+    // pass their average to `callback` as mono audio at MIX_SAMPLE_RATE, as if
+    // it came from a single device
+    pub fn start_multi_capture<F>(&mut self, device_ids: Vec<Option<String>>, callback: F) -> Result<()>
+    where
+        F: Fn(Vec<i16>) + Send + 'static,
+    {
+        if device_ids.is_empty() {
+            return Err(anyhow::anyhow!("No capture devices given"));
+        }
+        let mut is_capturing_guard = self.is_capturing.lock().unwrap();
+        if *is_capturing_guard {
+            return Ok(());
+        }
+        *is_capturing_guard = true;
+        drop(is_capturing_guard);
+
+        // One queue per device that opened, filled by its stream and emptied by the mixer
+        let queues: Arc<Mutex<Vec<Option<VecDeque<i16>>>>> = Arc::new(Mutex::new(vec![None; device_ids.len()]));
+        let max_backlog = samples_for(MAX_MIX_BACKLOG, MIX_SAMPLE_RATE);
+        let mut streams = Vec::new();
+
+        for (index, device_id) in device_ids.iter().enumerate() {
+            let device_name = device_id.clone().unwrap_or_else(|| "default".to_string());
+            let report_error = {
+                let handler = self.device_error_handler.clone();
+                let device_name = device_name.clone();
+                move |message: String| {
+                    eprintln!("⚠️ Audio device {} failed: {}", device_name, message);
+                    if let Some(handler) = &handler {
+                        handler(device_name.clone());
+                    }
+                }
+            };
+
+            let queue_writer = Arc::clone(&queues);
+            queues.lock().unwrap()[index] = Some(VecDeque::new());
+            let opened = find_input_device(device_id.as_deref()).and_then(|device| {
+                let stream_error = report_error.clone();
+                Self::build_stream_with_sample_rate(
+                    &device,
+                    move |frame, _| {
+                        let mut queues = queue_writer.lock().unwrap();
+                        if let Some(queue) = queues[index].as_mut() {
+                            queue.extend(frame);
+                            let overflow = queue.len().saturating_sub(max_backlog);
+                            queue.drain(..overflow);
+                        }
+                    },
+                    Arc::clone(&self.is_capturing),
+                    self.channel_routing.clone(),
+                    Some(MIX_SAMPLE_RATE),
+                    move |err| stream_error(err.to_string()),
+                )
+            });
+            match opened {
+                Ok(stream) => {
+                    println!("🎤 Mixing audio device: {}", device_name);
+                    streams.push(stream);
+                }
+                Err(e) => {
+                    queues.lock().unwrap()[index] = None;
+                    report_error(e.to_string());
+                }
+            }
+        }
+
+        if streams.is_empty() {
+            *self.is_capturing.lock().unwrap() = false;
+            return Err(anyhow::anyhow!("None of the capture devices could be opened"));
+        }
+
+        let (shutdown_sender, shutdown_receiver) = unbounded();
+        self.shutdown_sender = Some(shutdown_sender);
+        let window = samples_for(MIX_WINDOW, MIX_SAMPLE_RATE);
+        thread::spawn(move || {
+            // Runs until stop_capture drops or signals the shutdown sender
+            while let Err(crossbeam_channel::RecvTimeoutError::Timeout) = shutdown_receiver.recv_timeout(MIX_WINDOW) {
+                let mixed = mix_window(&mut queues.lock().unwrap(), window);
+                if !mixed.is_empty() {
+                    callback(mixed);
+                }
+            }
+        });
+
+        self._streams = streams;
+        println!("🎙️ Multi-device audio capture started");
+        Ok(())
+    }
+
     // Updated to match the wake_word.rs usage with single parameter
     pub fn start_capture<F>(&mut self, callback: F) -> Result<()>
     where
@@ -48,7 +199,6 @@ impl AudioCapture {
     }
 
     // Alternative method if you need both audio_frame and sample_rate
-    #[allow(dead_code)]
     pub fn start_capture_with_sample_rate<F>(&mut self, callback: F) -> Result<()>
     where
         F: Fn(Vec<i16>, u32) + Send + 'static,
@@ -65,7 +215,8 @@ impl AudioCapture {
         self.shutdown_sender = Some(shutdown_sender);
 
         // Start audio capture in the current thread (don't spawn another thread)
-        let stream = Self::capture_audio_stream_with_sample_rate(callback, is_capturing, shutdown_receiver)?;
+        let routing = self.channel_routing.clone();
+        let stream = Self::capture_audio_stream_with_sample_rate(callback, is_capturing, routing, self.resample_to, shutdown_receiver)?;
         
         // Store the stream to keep it alive
         self._stream = Some(stream);
@@ -116,6 +267,25 @@ impl AudioCapture {
                 callback,
                 is_capturing,
             )?,
+            // Pro audio interfaces often use these natively; FromSample scales them to i16
+            SampleFormat::F64 => Self::create_stream::<f64>(
+                &device,
+                &config.into(),
+                callback,
+                is_capturing,
+            )?,
+            SampleFormat::I32 => Self::create_stream::<i32>(
+                &device,
+                &config.into(),
+                callback,
+                is_capturing,
+            )?,
+            SampleFormat::U32 => Self::create_stream::<u32>(
+                &device,
+                &config.into(),
+                callback,
+                is_capturing,
+            )?,
             sample_format => {
                 return Err(anyhow::anyhow!(
                     "Unsupported sample format: {:?}",
@@ -132,6 +302,8 @@ impl AudioCapture {
     fn capture_audio_stream_with_sample_rate<F>(
         callback: F,
         is_capturing: Arc<Mutex<bool>>,
+        routing: ChannelRouting,
+        resample_to: Option<u32>,
         _shutdown_receiver: Receiver<()>,
     ) -> Result<Stream>
     where
@@ -147,33 +319,85 @@ impl AudioCapture {
             device.name().unwrap_or_else(|_| "Unknown".to_string())
         );
 
+        let err_fn = |err| eprintln!("An error occurred on the audio stream: {}", err);
+        Self::build_stream_with_sample_rate(&device, callback, is_capturing, routing, resample_to, err_fn)
+    }
+
+    // Open and start a stream on `device` that delivers mono audio and its
+    // sample rate, converted to `resample_to` if given
+    fn build_stream_with_sample_rate<F>(
+        device: &Device,
+        callback: F,
+        is_capturing: Arc<Mutex<bool>>,
+        routing: ChannelRouting,
+        resample_to: Option<u32>,
+        err_fn: impl FnMut(cpal::StreamError) + Send + 'static,
+    ) -> Result<Stream>
+    where
+        F: Fn(Vec<i16>, u32) + Send + 'static,
+    {
         // Get the default config for the device
         let config = device.default_input_config()?;
         println!("📊 Default audio config: {:?}", config);
         let sample_rate = config.sample_rate().0;
+        let callback = resampling(callback, sample_rate, resample_to);
 
         // Create the stream based on the sample format
         let stream = match config.sample_format() {
             SampleFormat::I16 => Self::create_stream_with_sample_rate::<i16>(
-                &device,
+                device,
                 &config.into(),
                 callback,
                 is_capturing,
+                routing,
                 sample_rate,
+                err_fn,
             )?,
             SampleFormat::U16 => Self::create_stream_with_sample_rate::<u16>(
-                &device,
+                device,
                 &config.into(),
                 callback,
                 is_capturing,
+                routing,
                 sample_rate,
+                err_fn,
             )?,
             SampleFormat::F32 => Self::create_stream_with_sample_rate::<f32>(
-                &device,
+                device,
+                &config.into(),
+                callback,
+                is_capturing,
+                routing,
+                sample_rate,
+                err_fn,
+            )?,
+            // Pro audio interfaces often use these natively; FromSample scales them to i16
+            SampleFormat::F64 => Self::create_stream_with_sample_rate::<f64>(
+                device,
                 &config.into(),
                 callback,
                 is_capturing,
+                routing,
                 sample_rate,
+                err_fn,
+            )?,
+            SampleFormat::I32 => Self::create_stream_with_sample_rate::<i32>(
+                device,
+                &config.into(),
+                callback,
+                is_capturing,
+                routing,
+                sample_rate,
+                err_fn,
+            )?,
+            SampleFormat::U32 => Self::create_stream_with_sample_rate::<u32>(
+                device,
+                &config.into(),
+                callback,
+                is_capturing,
+                routing,
+                sample_rate,
+                err_fn,
             )?,
             sample_format => {
                 return Err(anyhow::anyhow!(
@@ -208,11 +432,7 @@ impl AudioCapture {
                     return;
                 }
                 
-                // Convert samples to i16
-                let audio_frame: Vec<i16> = data
-                    .iter()
-                    .map(|&sample| i16::from_sample(sample))
-                    .collect();
+                let audio_frame = convert_samples(data);
                 
                 if !audio_frame.is_empty() {
                     callback(audio_frame);
@@ -230,13 +450,15 @@ impl AudioCapture {
         config: &StreamConfig,
         callback: impl Fn(Vec<i16>, u32) + Send + 'static,
         is_capturing: Arc<Mutex<bool>>,
+        routing: ChannelRouting,
         sample_rate: u32,
+        err_fn: impl FnMut(cpal::StreamError) + Send + 'static,
     ) -> Result<Stream>
     where
         T: Sample + Send + 'static + cpal::SizedSample,
         i16: cpal::FromSample<T>,
     {
-        let err_fn = |err| eprintln!("An error occurred on the audio stream: {}", err);
+        let channels = config.channels as usize;
         
         let stream = device.build_input_stream(
             config,
@@ -246,11 +468,8 @@ impl AudioCapture {
                     return;
                 }
                 
-                // Convert samples to i16
-                let audio_frame: Vec<i16> = data
-                    .iter()
-                    .map(|&sample| i16::from_sample(sample))
-                    .collect();
+                // Mixed down to mono so sample counts line up with the sample rate
+                let audio_frame = route_channels(&convert_samples(data), channels, &routing);
                 
                 if !audio_frame.is_empty() {
                     callback(audio_frame, sample_rate);
@@ -263,6 +482,28 @@ impl AudioCapture {
         Ok(stream)
     }
 
+    // Drop frames until resume() while keeping the stream open, so the device
+    // doesn't have to be reopened. False if nothing is being captured.
+    pub fn pause(&self) -> bool {
+        if !self.has_stream() {
+            return false;
+        }
+        *self.is_capturing.lock().unwrap() = false;
+        true
+    }
+
+    pub fn resume(&self) -> bool {
+        if !self.has_stream() {
+            return false;
+        }
+        *self.is_capturing.lock().unwrap() = true;
+        true
+    }
+
+    fn has_stream(&self) -> bool {
+        self._stream.is_some() || !self._streams.is_empty()
+    }
+
     pub fn stop_capture(&mut self) {
         *self.is_capturing.lock().unwrap() = false;
 
@@ -271,13 +512,550 @@ impl AudioCapture {
             let _ = sender.send(());
         }
 
-        // Drop the stream to stop capture
+        // Drop the stream(s) to stop capture
         self._stream = None;
+        self._streams.clear();
 
         println!("🛑 Audio capture stopped");
     }
 }
 
+// Built-in sounds for audio feedback
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Earcon {
+    // Rising beep when the wake word opens a session
+    Wake,
+    // Short blip when an utterance has been recognized
+    Confirm,
+    // Low double tone when recognition couldn't start
+    Error,
+    // Falling beep when a session ends from silence
+    Timeout,
+}
+
+const EARCONS: &[(Earcon, &str, &[u8])] = &[
+    (Earcon::Wake, "wake", include_bytes!("../resources/earcons/wake.wav")),
+    (Earcon::Confirm, "confirm", include_bytes!("../resources/earcons/confirm.wav")),
+    (Earcon::Error, "error", include_bytes!("../resources/earcons/error.wav")),
+    (Earcon::Timeout, "timeout", include_bytes!("../resources/earcons/timeout.wav")),
+];
+
+impl Earcon {
+    pub fn from_name(name: &str) -> Option<Self> {
+        EARCONS.iter().find(|(_, earcon_name, _)| *earcon_name == name).map(|(earcon, _, _)| *earcon)
+    }
+}
+
+// The earcons, decoded once, and the volume they play at
+pub struct EarconManager {
+    clips: HashMap<Earcon, (Vec<i16>, u32)>,
+    volume: f32,
+}
+
+impl EarconManager {
+    pub fn new(volume: f32) -> Self {
+        let mut clips = HashMap::new();
+        for &(earcon, name, wav) in EARCONS {
+            match hound::WavReader::new(wav).map_err(anyhow::Error::from).and_then(decode_wav) {
+                Ok(clip) => {
+                    clips.insert(earcon, clip);
+                }
+                Err(e) => eprintln!("⚠️ Failed to decode the {} earcon: {}", name, e),
+            }
+        }
+        Self {
+            clips,
+            volume: volume.clamp(0.0, 1.0),
+        }
+    }
+
+    // 0.0 (silent) to 1.0 (as recorded)
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn play(&self, playback: &AudioPlayback, earcon: Earcon) {
+        if let Some((samples, sample_rate)) = self.clips.get(&earcon) {
+            let scaled = samples.iter().map(|&sample| (sample as f32 * self.volume) as i16).collect();
+            playback.play_samples(scaled, *sample_rate);
+        }
+    }
+}
+
+type OutputTap = Box<dyn Fn(&[i16], u32) + Send>;
+
+// Plays audio on the default output device. Samples are queued and the
+// stream plays silence while the queue is empty.
+pub struct AudioPlayback {
+    queue: Arc<Mutex<VecDeque<i16>>>,
+    sample_rate: u32,
+    // Gets every block as it goes out, silence included
+    output_tap: Arc<Mutex<Option<OutputTap>>>,
+    _stream: Stream, // Keep the stream alive
+}
+
+impl AudioPlayback {
+    pub fn new() -> Result<Self> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| anyhow::anyhow!("No default output device available"))?;
+        println!(
+            "🔈 Using output device: {}",
+            device.name().unwrap_or_else(|_| "Unknown".to_string())
+        );
+
+        let config = device.default_output_config()?;
+        let sample_rate = config.sample_rate().0;
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let output_tap = Arc::new(Mutex::new(None));
+        let stream = match config.sample_format() {
+            SampleFormat::I16 => Self::create_stream::<i16>(&device, &config.into(), Arc::clone(&queue), Arc::clone(&output_tap))?,
+            SampleFormat::U16 => Self::create_stream::<u16>(&device, &config.into(), Arc::clone(&queue), Arc::clone(&output_tap))?,
+            SampleFormat::F32 => Self::create_stream::<f32>(&device, &config.into(), Arc::clone(&queue), Arc::clone(&output_tap))?,
+            SampleFormat::F64 => Self::create_stream::<f64>(&device, &config.into(), Arc::clone(&queue), Arc::clone(&output_tap))?,
+            SampleFormat::I32 => Self::create_stream::<i32>(&device, &config.into(), Arc::clone(&queue), Arc::clone(&output_tap))?,
+            SampleFormat::U32 => Self::create_stream::<u32>(&device, &config.into(), Arc::clone(&queue), Arc::clone(&output_tap))?,
+            sample_format => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported sample format: {:?}",
+                    sample_format
+                ));
+            }
+        };
+        stream.play()?;
+
+        Ok(Self {
+            queue,
+            sample_rate,
+            output_tap,
+            _stream: stream,
+        })
+    }
+
+    fn create_stream<T>(
+        device: &Device,
+        config: &StreamConfig,
+        queue: Arc<Mutex<VecDeque<i16>>>,
+        output_tap: Arc<Mutex<Option<OutputTap>>>,
+    ) -> Result<Stream>
+    where
+        T: Sample + Send + 'static + cpal::SizedSample + cpal::FromSample<i16>,
+    {
+        let channels = config.channels as usize;
+        let sample_rate = config.sample_rate.0;
+        let err_fn = |err| eprintln!("An error occurred on the output stream: {}", err);
+
+        let stream = device.build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let mut queue = queue.lock().unwrap();
+                let mut played = Vec::with_capacity(data.len() / channels);
+                // The queue is mono; every channel of a frame gets the same sample
+                for frame in data.chunks_mut(channels) {
+                    let sample = queue.pop_front().unwrap_or(0);
+                    played.push(sample);
+                    frame.fill(T::from_sample(sample));
+                }
+                drop(queue);
+                if let Some(tap) = output_tap.lock().unwrap().as_ref() {
+                    tap(&played, sample_rate);
+                }
+            },
+            err_fn,
+            None,
+        )?;
+
+        Ok(stream)
+    }
+
+    // Replaces the tap that sees the mono audio as it is played
+    pub fn on_output(&self, tap: impl Fn(&[i16], u32) + Send + 'static) {
+        *self.output_tap.lock().unwrap() = Some(Box::new(tap));
+    }
+
+    // Queue mono samples for playback, resampled to the device rate
+    pub fn play_samples(&self, samples: Vec<i16>, sample_rate: u32) {
+        let samples = resample(&samples, sample_rate, self.sample_rate);
+        self.queue.lock().unwrap().extend(samples);
+    }
+
+    pub fn play_file(&self, path: &Path) -> Result<()> {
+        let reader = hound::WavReader::open(path)?;
+        let (samples, sample_rate) = decode_wav(reader)?;
+        self.play_samples(samples, sample_rate);
+        Ok(())
+    }
+}
+
+// Mono i16 samples and the sample rate of a WAV file in any PCM or float format
+fn decode_wav<R: Read>(mut reader: hound::WavReader<R>) -> Result<(Vec<i16>, u32)> {
+    let spec = reader.spec();
+    let samples: Vec<i16> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|sample| sample.map(i16::from_sample))
+            .collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let bits = spec.bits_per_sample as u32;
+            reader
+                .samples::<i32>()
+                .map(|sample| {
+                    sample.map(|sample| match bits {
+                        16 => sample as i16,
+                        bits if bits > 16 => (sample >> (bits - 16)) as i16,
+                        bits => (sample << (16 - bits)) as i16,
+                    })
+                })
+                .collect::<Result<_, _>>()?
+        }
+    };
+    Ok((downmix(&samples, spec.channels as usize), spec.sample_rate))
+}
+
+pub fn input_device_available() -> bool {
+    cpal::default_host().default_input_device().is_some()
+}
+
+pub fn default_input_device_name() -> Option<String> {
+    cpal::default_host().default_input_device()?.name().ok()
+}
+
+pub fn input_device_names() -> Vec<String> {
+    cpal::default_host()
+        .input_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+// An input device by name, or the default one
+fn find_input_device(device_id: Option<&str>) -> Result<Device> {
+    let host = cpal::default_host();
+    match device_id {
+        None => host
+            .default_input_device()
+            .ok_or_else(|| anyhow::anyhow!("No default input device available")),
+        Some(name) => host
+            .input_devices()?
+            .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+            .ok_or_else(|| anyhow::anyhow!("Input device not found: {}", name)),
+    }
+}
+
+// Take up to `window` samples from each device queue and average them frame by
+// frame. Devices that delivered less are padded with silence, and devices that
+// failed to open (None) are left out; returns nothing until some device has
+// audio.
+fn mix_window(queues: &mut [Option<VecDeque<i16>>], window: usize) -> Vec<i16> {
+    let len = queues.iter().flatten().map(|queue| queue.len().min(window)).max().unwrap_or(0);
+    let mut sums = vec![0i32; len];
+    for queue in queues.iter_mut().flatten() {
+        let take = queue.len().min(len);
+        for (sum, sample) in sums.iter_mut().zip(queue.drain(..take)) {
+            *sum += sample as i32;
+        }
+    }
+    let devices = queues.iter().flatten().count().max(1) as i32;
+    sums.into_iter().map(|sum| (sum / devices) as i16).collect()
+}
+
+// Rolling window of the most recently captured audio, so the moments before a
+// detection can be inspected after the fact
+pub struct PreTriggerBuffer {
+    samples: VecDeque<i16>,
+    window: Duration,
+    sample_rate: u32,
+}
+
+impl PreTriggerBuffer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            window,
+            sample_rate: 0,
+        }
+    }
+
+    pub fn push(&mut self, frame: &[i16], sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.samples.extend(frame);
+        let capacity = samples_for(self.window, sample_rate);
+        while self.samples.len() > capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    // The last `duration` of audio (or less if not that much has been captured)
+    pub fn recent(&self, duration: Duration) -> Vec<i16> {
+        let count = samples_for(duration, self.sample_rate).min(self.samples.len());
+        self.samples.iter().skip(self.samples.len() - count).copied().collect()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+fn samples_for(duration: Duration, sample_rate: u32) -> usize {
+    (duration.as_secs_f64() * sample_rate as f64) as usize
+}
+
+// Convert a device buffer to i16. Float samples outside [-1.0, 1.0] saturate
+// and NaN becomes silence.
+pub fn convert_samples<T>(data: &[T]) -> Vec<i16>
+where
+    T: Sample,
+    i16: cpal::FromSample<T>,
+{
+    data.iter().map(|&sample| i16::from_sample(sample)).collect()
+}
+
+// Average interleaved channels into one
+pub fn downmix(samples: &[i16], channels: usize) -> Vec<i16> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(channels)
+        .map(|frame| (frame.iter().map(|&sample| sample as i32).sum::<i32>() / frame.len() as i32) as i16)
+        .collect()
+}
+
+// Mono audio from interleaved channels as the routing picks them. Channels
+// the device doesn't have are ignored, and if none are left every channel is
+// mixed.
+pub fn route_channels(samples: &[i16], channels: usize, routing: &ChannelRouting) -> Vec<i16> {
+    let selected: Vec<usize> = match routing {
+        ChannelRouting::MixAll => Vec::new(),
+        ChannelRouting::SelectChannel(channel) => vec![*channel as usize],
+        ChannelRouting::BeamformChannels(list) => list.iter().map(|&channel| channel as usize).collect(),
+    };
+    let selected: Vec<usize> = selected.into_iter().filter(|&channel| channel < channels).collect();
+    if selected.is_empty() || channels <= 1 {
+        return downmix(samples, channels);
+    }
+    samples
+        .chunks_exact(channels)
+        .map(|frame| (selected.iter().map(|&channel| frame[channel] as i32).sum::<i32>() / selected.len() as i32) as i16)
+        .collect()
+}
+
+// Wraps `callback` so it gets audio at `resample_to` when the device runs at
+// another rate
+fn resampling<F>(callback: F, device_rate: u32, resample_to: Option<u32>) -> Box<dyn Fn(Vec<i16>, u32) + Send>
+where
+    F: Fn(Vec<i16>, u32) + Send + 'static,
+{
+    match resample_to.filter(|&rate| rate != device_rate) {
+        Some(rate) => {
+            println!("🔁 Resampling {} Hz audio to {} Hz", device_rate, rate);
+            let resampler = Mutex::new(Resampler::new(device_rate, rate));
+            Box::new(move |frame, _| {
+                let frame = resampler.lock().unwrap().process(&frame);
+                if !frame.is_empty() {
+                    callback(frame, rate);
+                }
+            })
+        }
+        None => Box::new(callback),
+    }
+}
+
+// Linear resampling of a mono stream that arrives in frames. Unlike
+// resample(), the position between input samples carries over from one
+// frame to the next, so frame boundaries don't add clicks or drift.
+pub struct Resampler {
+    from_rate: u64,
+    to_rate: u64,
+    // Where the next output sample falls after `last`, in input samples
+    // times to_rate, so it never drifts
+    position: u64,
+    // The final sample of the previous frame
+    last: Option<i16>,
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            from_rate: from_rate.max(1) as u64,
+            to_rate: to_rate.max(1) as u64,
+            position: 0,
+            last: None,
+        }
+    }
+
+    pub fn process(&mut self, frame: &[i16]) -> Vec<i16> {
+        let samples: Vec<i16> = self.last.into_iter().chain(frame.iter().copied()).collect();
+        let Some(&final_sample) = samples.last() else {
+            return Vec::new();
+        };
+        let span = (samples.len() as u64 - 1) * self.to_rate;
+        let mut output = Vec::with_capacity((frame.len() as u64 * self.to_rate / self.from_rate) as usize + 1);
+        while self.position < span {
+            let index = (self.position / self.to_rate) as usize;
+            let fraction = (self.position % self.to_rate) as f64 / self.to_rate as f64;
+            let current = samples[index] as f64;
+            let next = samples[index + 1] as f64;
+            output.push((current + (next - current) * fraction).round() as i16);
+            self.position += self.from_rate;
+        }
+        // The next frame starts from this one's final sample
+        self.position -= span;
+        self.last = Some(final_sample);
+        output
+    }
+}
+
+// Linear resampling of mono audio, e.g. from the device rate to 16 kHz
+pub fn resample(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
+    if from_rate == to_rate || from_rate == 0 || to_rate == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let step = from_rate as f64 / to_rate as f64;
+    let len = (samples.len() as f64 / step) as usize;
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let current = samples[index] as f64;
+            let next = samples.get(index + 1).copied().unwrap_or(samples[index]) as f64;
+            (current + (next - current) * (position - index as f64)).round() as i16
+        })
+        .collect()
+}
+
+// Root mean square of the samples, normalized so full scale is 1.0
+pub fn rms(samples: &[i16]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_of_squares: f64 = samples
+        .iter()
+        .map(|&sample| {
+            let normalized = sample as f64 / i16::MAX as f64;
+            normalized * normalized
+        })
+        .sum();
+    (sum_of_squares / samples.len() as f64).sqrt() as f32
+}
+
 // Make AudioCapture thread-safe
 unsafe impl Send for AudioCapture {}
-unsafe impl Sync for AudioCapture {}
\ No newline at end of file
+unsafe impl Sync for AudioCapture {}
+
+// ...and AudioPlayback, whose queue is the only thing shared with the stream
+unsafe impl Send for AudioPlayback {}
+unsafe impl Sync for AudioPlayback {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f64_samples_convert_to_i16() {
+        assert_eq!(convert_samples(&[1.0f64, -1.0, 0.0, 0.5, -0.5]), vec![i16::MAX, i16::MIN, 0, 16_384, -16_384]);
+        // Out of range saturates, NaN is silence
+        assert_eq!(convert_samples(&[2.0f64, -2.0, f64::NAN]), vec![i16::MAX, i16::MIN, 0]);
+    }
+
+    #[test]
+    fn i32_samples_convert_to_i16() {
+        assert_eq!(convert_samples(&[i32::MAX, i32::MIN, 0]), vec![i16::MAX, i16::MIN, 0]);
+        // Only the top 16 bits survive
+        assert_eq!(convert_samples(&[65_536i32, -65_536, -1]), vec![1, -1, -1]);
+    }
+
+    #[test]
+    fn u32_samples_convert_to_i16() {
+        // Unsigned samples are centred on 2^31
+        let centre = 1u32 << 31;
+        assert_eq!(convert_samples(&[u32::MAX, 0, centre]), vec![i16::MAX, i16::MIN, 0]);
+        assert_eq!(convert_samples(&[centre + 65_536, centre - 65_536]), vec![1, -1]);
+    }
+
+    fn queue(samples: &[i16]) -> Option<VecDeque<i16>> {
+        Some(samples.iter().copied().collect())
+    }
+
+    #[test]
+    fn mix_pads_a_late_device_with_silence() {
+        let mut queues = [queue(&[1_000, 1_000, 1_000, 1_000]), queue(&[3_000, 3_000])];
+        assert_eq!(mix_window(&mut queues, 4), vec![2_000, 2_000, 500, 500]);
+        assert!(queues.iter().flatten().all(VecDeque::is_empty));
+        assert!(mix_window(&mut queues, 4).is_empty());
+    }
+
+    #[test]
+    fn mix_takes_at_most_a_window() {
+        let mut queues = [queue(&[100; 6]), queue(&[300; 3])];
+        assert_eq!(mix_window(&mut queues, 4), vec![200, 200, 200, 50]);
+        assert_eq!(queues[0].as_ref().unwrap().len(), 2);
+        assert_eq!(mix_window(&mut queues, 4), vec![50, 50]);
+    }
+
+    #[test]
+    fn mix_leaves_out_a_device_that_failed_to_open() {
+        let mut queues = [queue(&[1_000, -1_000]), None];
+        assert_eq!(mix_window(&mut queues, 4), vec![1_000, -1_000]);
+        assert!(mix_window(&mut [None, None], 4).is_empty());
+    }
+
+    // Four channels whose samples are 10, 20, 30 and 40 times the frame number
+    fn four_channel_frames() -> Vec<i16> {
+        (1..=3).flat_map(|frame| [10 * frame, 20 * frame, 30 * frame, 40 * frame]).collect()
+    }
+
+    #[test]
+    fn channels_are_routed_to_mono() {
+        let frames = four_channel_frames();
+        assert_eq!(route_channels(&frames, 4, &ChannelRouting::MixAll), vec![25, 50, 75]);
+        assert_eq!(route_channels(&frames, 4, &ChannelRouting::SelectChannel(2)), vec![30, 60, 90]);
+        assert_eq!(route_channels(&frames, 4, &ChannelRouting::BeamformChannels(vec![0, 1])), vec![15, 30, 45]);
+        // Channels the device doesn't have are left out, or everything is mixed
+        assert_eq!(route_channels(&frames, 4, &ChannelRouting::BeamformChannels(vec![3, 7])), vec![40, 80, 120]);
+        assert_eq!(route_channels(&frames, 4, &ChannelRouting::SelectChannel(4)), vec![25, 50, 75]);
+    }
+
+    // One second of a 440 Hz tone at 44.1 kHz, like a virtual cable delivers
+    fn tone_44k() -> Vec<i16> {
+        (0..44_100)
+            .map(|i| ((i as f64 * 440.0 * std::f64::consts::TAU / 44_100.0).sin() * 10_000.0) as i16)
+            .collect()
+    }
+
+    fn rising_zero_crossings(samples: &[i16]) -> usize {
+        samples.windows(2).filter(|pair| pair[0] < 0 && pair[1] >= 0).count()
+    }
+
+    #[test]
+    fn resampler_converts_44k_to_16k() {
+        let mut resampler = Resampler::new(44_100, 16_000);
+        let output = resampler.process(&tone_44k());
+        assert!((15_999..=16_000).contains(&output.len()), "{} samples", output.len());
+        // Still a 440 Hz tone
+        assert!((439..=441).contains(&rising_zero_crossings(&output)));
+    }
+
+    #[test]
+    fn resampler_output_doesnt_depend_on_frame_size() {
+        let input = tone_44k();
+        let whole = Resampler::new(44_100, 16_000).process(&input);
+
+        // Uneven frames, as devices deliver them
+        let mut resampler = Resampler::new(44_100, 16_000);
+        let mut framed = Vec::new();
+        let mut start = 0;
+        for size in [441, 1, 0, 480, 1024, 7].iter().cycle() {
+            if start >= input.len() {
+                break;
+            }
+            let end = (start + size).min(input.len());
+            framed.extend(resampler.process(&input[start..end]));
+            start = end;
+        }
+        assert_eq!(framed, whole);
+    }
+}
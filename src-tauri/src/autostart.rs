@@ -0,0 +1,100 @@
+// autostart.rs - Starting Jackson when the user logs in, through the
+// per-user Run key so no elevation is needed
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+use winreg::enums::{HKEY_CURRENT_USER, KEY_SET_VALUE};
+use winreg::RegKey;
+
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+const VALUE_NAME: &str = "Jackson";
+// Passed by the Run entry, so a launch at login can be told apart
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+// Returned to the frontend as { kind, message }; access_denied means policy
+// or a restricted account keeps us out of the Run key
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AutostartError {
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl AutostartError {
+    fn from_io(action: &str, error: io::Error) -> Self {
+        let kind = match error.kind() {
+            io::ErrorKind::PermissionDenied => "access_denied",
+            _ => "failed",
+        };
+        Self { kind, message: format!("Failed to {} autostart: {}", action, error) }
+    }
+}
+
+// The Run entry for this executable, quoted for paths with spaces
+fn command_line(exe: &Path) -> String {
+    format!("\"{}\" {}", exe.display(), AUTOSTART_ARG)
+}
+
+// An entry left behind by an install elsewhere doesn't start this one
+fn is_ours(entry: &str, exe: &Path) -> bool {
+    entry.trim().eq_ignore_ascii_case(&command_line(exe))
+}
+
+pub fn is_enabled() -> Result<bool, AutostartError> {
+    let exe = std::env::current_exe().map_err(|e| AutostartError::from_io("check", e))?;
+    let key = match RegKey::predef(HKEY_CURRENT_USER).open_subkey(RUN_KEY) {
+        Ok(key) => key,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(AutostartError::from_io("check", e)),
+    };
+    match key.get_value::<String, _>(VALUE_NAME) {
+        Ok(entry) => Ok(is_ours(&entry, &exe)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(AutostartError::from_io("check", e)),
+    }
+}
+
+pub fn set_enabled(enabled: bool) -> Result<(), AutostartError> {
+    let action = if enabled { "enable" } else { "disable" };
+    let user = RegKey::predef(HKEY_CURRENT_USER);
+    if enabled {
+        let exe = std::env::current_exe().map_err(|e| AutostartError::from_io(action, e))?;
+        let (key, _) = user.create_subkey(RUN_KEY).map_err(|e| AutostartError::from_io(action, e))?;
+        key.set_value(VALUE_NAME, &command_line(&exe)).map_err(|e| AutostartError::from_io(action, e))
+    } else {
+        let key = match user.open_subkey_with_flags(RUN_KEY, KEY_SET_VALUE) {
+            Ok(key) => key,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(AutostartError::from_io(action, e)),
+        };
+        match key.delete_value(VALUE_NAME) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(AutostartError::from_io(action, e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Whether this process was started by the Run entry
+pub fn launched_at_login() -> bool {
+    std::env::args().skip(1).any(|arg| arg == AUTOSTART_ARG)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_quotes_the_path() {
+        let exe = Path::new(r"C:\Program Files\Jackson\jackson.exe");
+        assert_eq!(command_line(exe), r#""C:\Program Files\Jackson\jackson.exe" --autostart"#);
+        assert!(is_ours(r#""c:\program files\jackson\JACKSON.exe" --autostart "#, exe));
+        assert!(!is_ours(r#""D:\Old\jackson.exe" --autostart"#, exe));
+    }
+
+    #[test]
+    fn denied_writes_are_told_apart() {
+        let denied = AutostartError::from_io("enable", io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(denied.kind, "access_denied");
+        let other = AutostartError::from_io("enable", io::Error::from(io::ErrorKind::Other));
+        assert_eq!(other.kind, "failed");
+    }
+}
@@ -0,0 +1,67 @@
+// bug_report.rs - The pre-filled email opened by open_bug_report
+use std::fmt::Write;
+
+// What goes into the report besides the user's own description
+pub struct BugReport {
+    pub app_version: String,
+    pub health: String,
+    pub log_path: String,
+    pub screenshot_path: Option<String>,
+}
+
+impl BugReport {
+    pub fn body(&self) -> String {
+        let mut body = String::from(
+            "What happened:\n\n\nWhat you expected to happen:\n\n\nSteps to reproduce:\n1. \n\n---\n",
+        );
+        let _ = writeln!(body, "Version: {}", self.app_version);
+        let _ = writeln!(body, "Health: {}", self.health);
+        let _ = writeln!(body, "Logs: {}", self.log_path);
+        match &self.screenshot_path {
+            Some(path) => {
+                let _ = writeln!(body, "Screenshot (please attach): {}", path);
+            }
+            None => body.push_str("Screenshot: not available\n"),
+        }
+        body
+    }
+
+    // mailto: with no recipient, so the user picks where it goes
+    pub fn mailto_uri(&self, subject: &str) -> String {
+        format!("mailto:?subject={}&body={}", percent_encode(subject), percent_encode(&self.body()))
+    }
+}
+
+// Everything but RFC 3986 unreserved characters is escaped, spaces and
+// newlines included, which mail clients expect in mailto: fields
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{:02X}", byte);
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mailto_fields_are_escaped() {
+        let report = BugReport {
+            app_version: "0.1.0".to_string(),
+            health: "{\"sapi_initialized\":true}".to_string(),
+            log_path: r"C:\logs".to_string(),
+            screenshot_path: None,
+        };
+        let uri = report.mailto_uri("Jackson bug & report");
+        assert!(uri.starts_with("mailto:?subject=Jackson%20bug%20%26%20report&body=What%20happened%3A%0A"));
+        assert!(uri.contains("C%3A%5Clogs"));
+        assert!(!uri.contains([' ', '\n', '"']));
+        assert_eq!(uri.matches('&').count(), 1);
+    }
+}
@@ -0,0 +1,154 @@
+// commands.rs - Registry describing every Tauri command, for list_commands
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CommandInfo {
+    pub name: String,
+    pub description: String,
+}
+
+// Builds the COMMANDS table from `command => "description"` pairs. Taking the
+// names as identifiers keeps them spelled like the functions themselves.
+macro_rules! register_command_info {
+    ($($name:ident => $description:expr),* $(,)?) => {
+        const COMMANDS: &[(&str, &str)] = &[$((stringify!($name), $description)),*];
+    };
+}
+
+// Keep this in sync with invoke_handler() in lib.rs
+register_command_info! {
+    start_wake_word_detection => "Start listening for \"Hey Jackson\"; resolves once the detector is ready",
+    stop_wake_word_detection => "Stop wake word detection and end any speech session",
+    get_wake_word_state => "Current wake word detector state, or null if it failed to initialize",
+    report_transcript => "Submit a final transcript (text, confidence, alternatives) from the frontend recognizer",
+    get_recognized_alternatives => "The last final result with up to 5 alternative hypotheses",
+    get_transcripts => "Transcript history, optionally limited and filtered by timestamp",
+    clear_transcripts => "Clear the transcript history (and any stored utterance audio)",
+    set_store_utterance_audio => "Opt in to keeping recent utterance audio in memory for playback",
+    set_save_utterance_audio => "Opt in to saving each utterance as a WAV clip next to its transcript",
+    get_utterance_audio => "Path of the saved WAV clip for a transcript id",
+    play_utterance_audio => "Play a transcript's audio, from memory if stored or else its saved WAV clip",
+    set_earcons => "Turn earcons on or off and optionally set their volume",
+    test_earcon => "Play an earcon (wake, confirm, error, timeout) for preview",
+    get_backend_chain => "Recognition backends utterance audio falls back through, in order",
+    set_backend_chain => "Replace the backend fallback chain; rejects unknown or repeated backends and ones that can't transcribe stored audio",
+    get_speech_metrics => "Words per minute, word count and pauses for the current session",
+    check_sapi_health => "Whether SAPI is installed and a recognizer can be created",
+    get_sapi_stats => "Counts of phrases SAPI recognized, accepted and rejected, plus the last HRESULT failure",
+    reset_sapi_stats => "Clear the SAPI recognition counts and last error",
+    open_speech_training => "Open the Windows speech training wizard to improve recognition accuracy",
+    record_training_phrase => "Record the user reading prompt out and save it as a WAV (output_path or app data)",
+    get_training_phrases => "List the training phrases recorded since the app started",
+    submit_training_phrases => "Train the SAPI speech profile on recorded training phrases by path and report how many it adapted to",
+    get_session_history => "Summaries of the last n speech sessions, newest first",
+    clear_session_history => "Forget all stored session summaries",
+    set_session_history_size => "How many session summaries to keep",
+    get_speech_timeout => "Seconds without recognized speech before a session closes (0 = never)",
+    set_speech_timeout => "Set the speech timeout in seconds, up to an hour (0 = never)",
+    set_pause_threshold => "Set the silence (threshold_ms) that counts as a pause",
+    set_recognition_backend => "Switch the continuous speech backend by name",
+    set_speech_backend => "Switch the continuous speech backend by type (web_speech, sapi; whisper reports what it's missing)",
+    list_available_models => "List the Whisper models that can be downloaded, with their sizes",
+    download_model => "Download a Whisper model, emitting model-download-progress as it goes",
+    set_active_model => "Choose the downloaded Whisper model the whisper backend is to load",
+    start_number_dictation => "Recognize spoken numbers as digits; emits number-dictated",
+    stop_number_dictation => "Go back from number dictation to normal recognition",
+    set_window_anchor => "Dock the window at the top, bottom, a corner or the center, with a margin",
+    ensure_window_visible => "Move the window back on screen if it's mostly off every monitor",
+    reset_window_position => "Forget where the window was dragged and snap it back to the anchor (also in the tray menu)",
+    set_window_monitor => "Show the window on the primary monitor, the cursor's monitor or a monitor index",
+    list_monitors => "The connected displays with their index, name, position, size, scale factor and whether it's primary",
+    set_display => "Pin the window to a display by list_monitors index or name; the primary one is used while it's missing",
+    set_preferred_monitor => "Place the window on the display with this list_monitors name, or the primary one for null",
+    list_input_devices => "Names of the available microphones",
+    set_input_devices => "Capture from these microphones, mixing them if there are several",
+    set_channel_routing => "Listen to all, one, or a set of a multi-channel microphone's channels",
+    set_endpointing_config => "Set trailing silence, maximum utterance and minimum speech durations",
+    get_app_status => "Detector state, backend, session and settings in one snapshot",
+    get_app_health => "Whether SAPI initialized and an audio input device is available",
+    capture_window_screenshot => "Save a PNG of the window as it appears on screen to output_path",
+    open_bug_report => "Open the mail client with a bug report, health details and a window screenshot",
+    calibrate_noise_floor => "Sample background audio for duration_ms and store its RMS",
+    set_agc => "Turn automatic gain control on or off, optionally with a new target RMS",
+    set_energy_gate_ratio => "Only poll SAPI while audio is ratio times the noise floor (0 turns the gate off)",
+    get_noise_floor => "Noise floor RMS from the last calibration",
+    set_output_playing => "Mark assistant audio as playing so its speech isn't recognized",
+    get_echo_suppression_status => "Whether the microphone picks up our playback and wake words are ignored while it plays",
+    get_postprocess_config => "Current transcript post-processing settings",
+    set_postprocess_config => "Replace the transcript post-processing settings",
+    set_profanity_filter => "Enable or disable profanity masking",
+    set_custom_profanity => "Set the words masked in addition to the built-in list",
+    list_vocabulary => "Custom vocabulary phrases recognition favors",
+    add_vocabulary_phrase => "Add a phrase (names, jargon) to the custom vocabulary",
+    remove_vocabulary_phrase => "Remove a phrase from the custom vocabulary",
+    register_intent => "Add or replace an intent (name, patterns with {slot} placeholders)",
+    test_command_match => "Dry-run a phrase against every intent pattern and report which would fire",
+    set_recognition_constraints => "Limit recognition to the given phrases",
+    clear_recognition_constraints => "Remove recognition constraints",
+    hide_window => "Hide the main window and end the speech session",
+    show_window => "Show and focus the main window",
+    quit_app => "Exit the application",
+    resize_window => "Resize the main window (width, height), applying the last of a burst once the debounce interval passes",
+    resize_and_position_window => "Resize the main window, animating it to its anchored position",
+    set_window_mode => "Animate to a window preset: compact, expanded or fullscreen-overlay (sizes in set_window_config)",
+    set_display_mode => "Switch between the compact and full window sizes, animated (the compact and expanded presets)",
+    enter_overlay_mode => "Cover the monitor's work area, on top and click-through outside interactive regions",
+    exit_overlay_mode => "Restore the window's geometry and flags from before enter_overlay_mode",
+    set_resize_animation => "Set how long resize_and_position_window animates for (0 = instant)",
+    set_resize_debounce => "Set the minimum time between resize_window resizes (0 = no debounce)",
+    set_window_config => "Set the window margin, minimum, default and preset sizes, re-applied to a visible window",
+    set_ignore_cursor_events => "Let mouse events pass through the window",
+    get_window_info => "Get the main window's size, position, visibility, focus, monitor and click-through state",
+    enable_hover_interaction => "Make the window click-through except while hovered",
+    disable_hover_interaction => "Turn hover interaction off, leaving the window interactive",
+    set_interactive_regions => "Only take mouse input over these rectangles while the window is visible",
+    set_do_not_disturb => "Enable or disable do not disturb",
+    get_do_not_disturb => "Whether do not disturb is enabled",
+    set_always_on_top => "Keep the window above other windows (also in the tray menu)",
+    set_show_without_focus => "Show the always-on-top window without taking keyboard focus",
+    set_tray_adjacent => "Open the window next to the tray when it's opened from the tray icon or menu",
+    get_autostart => "Whether Jackson starts when the user logs in",
+    set_autostart => "Start Jackson at login (also in the tray menu); access_denied errors mean the account may not",
+    get_listen_on_startup => "Whether wake word detection starts as soon as the app has loaded",
+    set_listen_on_startup => "Start wake word detection as soon as the app has loaded, at login included",
+    set_window_opacity => "Set the window opacity now (0.2 - 1.0)",
+    set_window_corner_style => "Round or square the window corners on Windows 11 (no-op on older versions)",
+    set_window_shadow => "Turn the window's drop shadow on or off (no effect on a fully opaque window or without DWM composition)",
+    set_window_fade => "Set how long showing and hiding the window fade for (0 = instant)",
+    set_opacity_levels => "Set the window opacity used between sessions (idle) and after the wake word (active)",
+    set_auto_hide_on_blur => "Hide the window delay_ms after it loses focus, unless a session is running or it's pinned",
+    set_hotkeys => "Change the global hotkeys that toggle the window, force it clickable and toggle listening; errors if one is taken",
+    set_window_pinned => "Keep the window up and in place: no auto-hide, and resizes don't move it",
+    set_idle_hide => "Hide the window after timeout_ms without activity (0 = never)",
+    frontend_heartbeat => "Tell the backend the user is interacting, postponing the idle hide",
+    get_focused_app => "Executable, window title and process ID of the foreground app",
+    get_system_theme => "The system light/dark theme",
+    get_system_volume => "The default output device's volume (0.0 - 1.0) and mute state",
+    set_system_mute => "Mute or unmute the default output device",
+    pause_microphone => "Mute the microphone: the wake word and speech results are ignored and capture pauses, keeping the device open",
+    resume_microphone => "Undo pause_microphone",
+    list_commands => "This list",
+    frontend_ready => "Signal that event listeners are attached; flushes queued events",
+    open_settings_window => "Open the settings window, or focus it if it's open (also in the tray menu)",
+    close_settings_window => "Close the settings window; settings it changed stay applied",
+}
+
+// Only built in debug builds, for driving the pipeline from tests
+#[cfg(debug_assertions)]
+const DEBUG_COMMANDS: &[(&str, &str)] = &[
+    ("simulate_wake_word", "Act as if the wake word was heard (debug builds only)"),
+    ("simulate_speech", "Deliver text to the session as if it was recognized (debug builds only)"),
+];
+#[cfg(not(debug_assertions))]
+const DEBUG_COMMANDS: &[(&str, &str)] = &[];
+
+pub fn list() -> Vec<CommandInfo> {
+    COMMANDS
+        .iter()
+        .chain(DEBUG_COMMANDS)
+        .map(|(name, description)| CommandInfo {
+            name: name.to_string(),
+            description: description.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,493 @@
+// config.rs - Persisted application settings
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::analytics::DEFAULT_SESSION_HISTORY;
+use crate::audio::ChannelRouting;
+use crate::corners::CornerStyle;
+use crate::fallback::{self, ChainStep};
+use crate::hotkeys::Hotkeys;
+use crate::session::DEFAULT_SESSION_TIMEOUT;
+use crate::speech_recognition::{EndpointingConfig, DEFAULT_BACKEND, TRANSCRIBING_BACKENDS};
+
+// Bump this and add a migration to MIGRATIONS whenever the schema changes in
+// a way that needs existing files rewritten
+pub const CURRENT_CONFIG_VERSION: u32 = 4;
+
+// Upgrades the raw JSON of a config file by one version
+pub type MigrationFn = fn(Value) -> Value;
+
+// MIGRATIONS[n] upgrades a version n + 1 file to version n + 2
+const MIGRATIONS: &[MigrationFn] = &[migrate_v1_to_v2, migrate_v2_to_v3, migrate_v3_to_v4];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub config_version: u32,
+    pub recognition_backend: String,
+    pub endpointing: EndpointingConfig,
+    // Background RMS measured by the last calibration; 0 if never calibrated
+    pub noise_floor_rms: f32,
+    // Minimum RMS (0.0 - 1.0) of the audio behind a wake word; 0 disables the check
+    pub min_wake_energy_rms: f32,
+    // Boost quiet microphones and tame loud ones towards agc_target_rms (0.0 - 1.0)
+    pub agc_enabled: bool,
+    pub agc_target_rms: f32,
+    // SAPI is only polled while audio is this many times louder than
+    // noise_floor_rms; 0 (or no calibration) turns the gate off
+    pub energy_gate_ratio: f32,
+    // Start wake word detection as soon as the app starts, at login included
+    pub listen_on_startup: bool,
+    pub profanity_filter: bool,
+    pub custom_profanity: Vec<String>,
+    // Keep the unfiltered text in transcript history
+    pub store_raw_transcripts: bool,
+    // Longest the output-playing flag stays set without being cleared
+    pub max_output_playing_ms: u64,
+    // Names and jargon recognition should favor
+    pub vocabulary: Vec<String>,
+    // Keep the audio of recent utterances for re-transcription; off for privacy
+    pub store_utterance_audio: bool,
+    // Also write each utterance to {app_data}/utterances as a WAV file
+    pub save_utterance_audio: bool,
+    // Oldest clips are deleted once the saved ones take more than this
+    pub max_utterance_audio_bytes: u64,
+    // Click-through window that becomes interactive while the cursor is over it
+    pub hover_interaction: bool,
+    // Backends utterance audio is run through in order; empty uses only the
+    // live result
+    pub backend_chain: Vec<ChainStep>,
+    // Microphones to capture from by name (null is the default device);
+    // empty uses the default device alone
+    pub input_devices: Vec<Option<String>>,
+    // Which channels of a multi-channel microphone are listened to
+    pub channel_routing: ChannelRouting,
+    pub window_monitor: MonitorPlacement,
+    // Where on the monitor the window sits
+    pub window_anchor: WindowAnchor,
+    pub window: WindowConfig,
+    // Last size the window had before it was hidden; None until the window
+    // has been sized once. Showing uses the compact preset.
+    pub window_size: Option<WindowSize>,
+    // Where the user dragged the window; it stays there instead of at the
+    // anchor until reset_window_position
+    pub window_position: Option<WindowPosition>,
+    // How long resize_and_position_window animates for; 0 is instant
+    pub resize_animation_ms: u64,
+    // Minimum time between resize_window resizes; later requests wait for it
+    pub resize_debounce_ms: u64,
+    // Keep the window above other windows, full-screen apps included
+    pub always_on_top: bool,
+    // While always on top, show the window without taking keyboard focus
+    pub show_without_focus: bool,
+    // Show next to the tray when opened from the tray icon or menu, instead
+    // of at the anchor
+    pub tray_adjacent: bool,
+    // Window opacity between sessions and while one runs (0.2 - 1.0)
+    pub idle_opacity: f64,
+    pub active_opacity: f64,
+    // How long showing and hiding the window fade for; 0 is instant
+    pub fade_ms: u64,
+    // Windows 11 corner rounding; ignored on older versions
+    pub corner_style: CornerStyle,
+    // The DWM drop shadow around the window
+    pub window_shadow: bool,
+    // Global hotkeys to toggle the window and to make it clickable again
+    pub hotkeys: Hotkeys,
+    // Hide the window this long after it loses focus, unless a session is
+    // running or it's pinned
+    pub auto_hide_on_blur: bool,
+    pub auto_hide_delay_ms: u64,
+    // Hide the window after this long without transcripts, resizes or
+    // frontend heartbeats; 0 disables it
+    pub idle_hide_ms: u64,
+    // How many session summaries get_session_history can return
+    pub session_history_size: usize,
+    // Close a session after this many seconds without recognized speech; 0
+    // never closes it
+    pub speech_timeout_secs: u64,
+    // Short sounds on wake, recognition, errors and timeouts
+    #[serde(alias = "earcons")]
+    pub earcons_enabled: bool,
+    // 0.0 to 1.0
+    pub earcon_volume: f32,
+    // Downloaded model the whisper backend loads, e.g. "base"
+    pub whisper_model: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowAnchor {
+    #[default]
+    TopCenter,
+    BottomCenter,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+    // Next to the notification area, at the bottom right of the work area
+    Tray,
+}
+
+// Window presets: the listening bar the window opens as, the answer card it
+// grows into once a transcript arrives, and an overlay over the whole work area
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowMode {
+    #[default]
+    Compact,
+    #[serde(alias = "full")]
+    Expanded,
+    FullscreenOverlay,
+}
+
+// The older two-mode API: just the input (compact) or the whole
+// conversation (full), which are the Compact and Expanded presets
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    #[default]
+    Compact,
+    Full,
+}
+
+impl From<DisplayMode> for WindowMode {
+    fn from(mode: DisplayMode) -> Self {
+        match mode {
+            DisplayMode::Compact => WindowMode::Compact,
+            DisplayMode::Full => WindowMode::Expanded,
+        }
+    }
+}
+
+impl DisplayMode {
+    // None for the overlay, which has no display mode
+    pub fn from_window_mode(mode: WindowMode) -> Option<Self> {
+        match mode {
+            WindowMode::Compact => Some(DisplayMode::Compact),
+            WindowMode::Expanded => Some(DisplayMode::Full),
+            WindowMode::FullscreenOverlay => None,
+        }
+    }
+}
+
+// Logical pixels
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+// Physical pixels, of the window's top left corner
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+// The one source of window margins and sizes for every function that sizes
+// or positions the window. Sizes are logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    // Distance from the edges the window is anchored to (the top one by default)
+    pub margin: u32,
+    pub min_width: f64,
+    pub min_height: f64,
+    // Used until there's a remembered size
+    pub default_width: f64,
+    pub default_height: f64,
+    // Sizes of the compact and expanded window modes
+    pub compact: WindowSize,
+    pub expanded: WindowSize,
+}
+
+impl WindowConfig {
+    pub fn min_size(&self) -> WindowSize {
+        WindowSize { width: self.min_width, height: self.min_height }
+    }
+
+    pub fn default_size(&self) -> WindowSize {
+        WindowSize { width: self.default_width, height: self.default_height }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            margin: 50,
+            min_width: 350.0,
+            min_height: 200.0,
+            default_width: 480.0,
+            default_height: 320.0,
+            compact: WindowSize { width: 480.0, height: 200.0 },
+            expanded: WindowSize { width: 480.0, height: 520.0 },
+        }
+    }
+}
+
+// Which monitor the window is shown on: "primary", "cursor" (the one under
+// the mouse), or {"index": n} or {"name": "..."} as listed by list_monitors.
+// The primary monitor is used whenever the choice can't be resolved.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorPlacement {
+    Primary,
+    #[default]
+    Cursor,
+    Index(usize),
+    Name(String),
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            config_version: CURRENT_CONFIG_VERSION,
+            recognition_backend: DEFAULT_BACKEND.to_string(),
+            endpointing: EndpointingConfig::default(),
+            noise_floor_rms: 0.0,
+            min_wake_energy_rms: 0.0,
+            agc_enabled: false,
+            agc_target_rms: 0.2,
+            energy_gate_ratio: 2.0,
+            listen_on_startup: true,
+            profanity_filter: false,
+            custom_profanity: Vec::new(),
+            store_raw_transcripts: false,
+            max_output_playing_ms: 30_000,
+            vocabulary: Vec::new(),
+            store_utterance_audio: false,
+            save_utterance_audio: false,
+            max_utterance_audio_bytes: 500 * 1024 * 1024,
+            hover_interaction: false,
+            backend_chain: Vec::new(),
+            input_devices: Vec::new(),
+            channel_routing: ChannelRouting::default(),
+            window_monitor: MonitorPlacement::default(),
+            window_anchor: WindowAnchor::default(),
+            window: WindowConfig::default(),
+            window_size: None,
+            window_position: None,
+            resize_animation_ms: 150,
+            resize_debounce_ms: 100,
+            always_on_top: true,
+            show_without_focus: false,
+            tray_adjacent: true,
+            idle_opacity: 0.85,
+            active_opacity: 1.0,
+            fade_ms: 120,
+            corner_style: CornerStyle::default(),
+            window_shadow: true,
+            hotkeys: Hotkeys::default(),
+            auto_hide_on_blur: false,
+            auto_hide_delay_ms: 2_000,
+            idle_hide_ms: 15_000,
+            session_history_size: DEFAULT_SESSION_HISTORY,
+            speech_timeout_secs: DEFAULT_SESSION_TIMEOUT.as_secs(),
+            earcons_enabled: false,
+            earcon_volume: 0.6,
+            whisper_model: None,
+        }
+    }
+}
+
+impl AppConfig {
+    // Whether sessions need their audio captured for something
+    pub fn records_utterance_audio(&self) -> bool {
+        self.store_utterance_audio || self.save_utterance_audio || !self.runnable_backend_chain().is_empty()
+    }
+
+    // The backend chain without steps that can't transcribe stored audio
+    pub fn runnable_backend_chain(&self) -> Vec<ChainStep> {
+        fallback::runnable(&self.backend_chain, TRANSCRIBING_BACKENDS)
+    }
+    
+    // Level the energy gate opens at, if it's on
+    pub fn energy_gate_rms(&self) -> Option<f32> {
+        let threshold = self.noise_floor_rms * self.energy_gate_ratio;
+        (threshold > 0.0).then_some(threshold)
+    }
+}
+
+// Version 2 added endpointing settings
+fn migrate_v1_to_v2(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        object
+            .entry("endpointing")
+            .or_insert_with(|| serde_json::to_value(EndpointingConfig::default()).unwrap_or(Value::Null));
+    }
+    value
+}
+
+// Version 3 moved window_margin into the window settings
+fn migrate_v2_to_v3(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        if let Some(margin) = object.remove("window_margin") {
+            let window = object.entry("window").or_insert_with(|| Value::Object(Default::default()));
+            if let Some(window) = window.as_object_mut() {
+                window.insert("margin".to_string(), margin);
+            }
+        }
+    }
+    value
+}
+
+// Version 4 moved the display mode sizes into the window settings as presets
+fn migrate_v3_to_v4(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        for (old, new) in [("compact_size", "compact"), ("full_size", "expanded")] {
+            if let Some(size) = object.remove(old) {
+                let window = object.entry("window").or_insert_with(|| Value::Object(Default::default()));
+                if let Some(window) = window.as_object_mut() {
+                    window.insert(new.to_string(), size);
+                }
+            }
+        }
+    }
+    value
+}
+
+// Bring a raw config up to the current version. Files written before
+// versioning was introduced have no `config_version` and count as version 1,
+// as does a version of 0.
+pub fn migrate(mut value: Value) -> Result<Value> {
+    let version = value
+        .get("config_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+        .max(1);
+    if version > CURRENT_CONFIG_VERSION as u64 {
+        return Err(anyhow::anyhow!(
+            "Config version {} is newer than this build supports ({})",
+            version,
+            CURRENT_CONFIG_VERSION
+        ));
+    }
+
+    let mut version = version as u32;
+    while version < CURRENT_CONFIG_VERSION {
+        let migration = MIGRATIONS
+            .get(version as usize - 1)
+            .ok_or_else(|| anyhow::anyhow!("No migration from config version {}", version))?;
+        value = migration(value);
+        version += 1;
+        println!("🔧 Migrated config to version {}", version);
+    }
+
+    if let Some(object) = value.as_object_mut() {
+        object.insert("config_version".to_string(), Value::from(version));
+    }
+    Ok(value)
+}
+
+// Parse a config file's contents, migrating older schemas first. Also returns
+// whether a migration was applied.
+pub fn parse(contents: &str) -> Result<(AppConfig, bool)> {
+    let raw: Value = serde_json::from_str(contents)?;
+    let original_version = raw.get("config_version").and_then(Value::as_u64);
+    let migrated = migrate(raw)?;
+    let config: AppConfig = serde_json::from_value(migrated)?;
+    let was_migrated = original_version != Some(config.config_version as u64);
+    Ok((config, was_migrated))
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| anyhow::anyhow!("Failed to resolve config directory: {}", e))?;
+    Ok(dir.join("config.json"))
+}
+
+// Load the settings from disk, falling back to defaults if the file is missing
+// or unreadable
+pub fn load<R: Runtime>(app: &AppHandle<R>) -> AppConfig {
+    let path = match config_path(app) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("⚠️ {}", e);
+            return AppConfig::default();
+        }
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return AppConfig::default(),
+    };
+
+    match parse(&contents) {
+        Ok((config, migrated)) => {
+            // Write migrated files back so the upgrade only happens once
+            if migrated {
+                if let Err(e) = save(app, &config) {
+                    eprintln!("⚠️ Failed to save migrated config: {}", e);
+                }
+            }
+            config
+        }
+        Err(e) => {
+            eprintln!("⚠️ Failed to load {}: {}", path.display(), e);
+            AppConfig::default()
+        }
+    }
+}
+
+pub fn save<R: Runtime>(app: &AppHandle<R>, config: &AppConfig) -> Result<()> {
+    let path = config_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(config)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn migrates_version_1_to_current() {
+        let v1 = json!({
+            "window_margin": 24,
+            "compact_size": { "width": 400.0, "height": 90.0 },
+            "full_size": { "width": 500.0, "height": 600.0 },
+        });
+        let (config, migrated) = parse(&v1.to_string()).unwrap();
+        assert!(migrated);
+        assert_eq!(config.config_version, CURRENT_CONFIG_VERSION);
+        assert_eq!(config.endpointing, EndpointingConfig::default());
+        assert_eq!(config.window.margin, 24);
+        assert_eq!(config.window.compact, WindowSize { width: 400.0, height: 90.0 });
+        assert_eq!(config.window.expanded, WindowSize { width: 500.0, height: 600.0 });
+    }
+
+    #[test]
+    fn unversioned_and_version_0_count_as_version_1() {
+        for file in [json!({}), json!({ "config_version": 0 })] {
+            let migrated = migrate(file).unwrap();
+            assert_eq!(migrated["config_version"], json!(CURRENT_CONFIG_VERSION));
+            assert!(migrated.get("endpointing").is_some());
+        }
+    }
+
+    #[test]
+    fn current_config_is_left_alone() {
+        let current = serde_json::to_string(&AppConfig::default()).unwrap();
+        let (config, migrated) = parse(&current).unwrap();
+        assert!(!migrated);
+        assert_eq!(serde_json::to_string(&config).unwrap(), current);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let newer = json!({ "config_version": CURRENT_CONFIG_VERSION + 1 });
+        assert!(migrate(newer.clone()).is_err());
+        assert!(parse(&newer.to_string()).is_err());
+    }
+}
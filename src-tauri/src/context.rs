@@ -0,0 +1,257 @@
+// context.rs - Track the foreground window so recognition can follow the active app
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use winapi::shared::minwindef::FALSE;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::winbase::QueryFullProcessImageNameW;
+use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+use winapi::um::winuser::{GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId};
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Executables (lowercase) that put recognition into each context
+const CONTEXT_APPS: &[(&str, &[&str])] = &[
+    ("browser", &["chrome.exe", "msedge.exe", "firefox.exe", "brave.exe", "opera.exe"]),
+    ("editor", &["code.exe", "devenv.exe", "idea64.exe", "sublime_text.exe", "notepad++.exe"]),
+];
+
+// Phrases recognized during a session while each context is active
+const DEFAULT_CONTEXT_GRAMMARS: &[(&str, &[&str])] = &[
+    ("browser", &["new tab", "close tab", "go back", "reload page"]),
+    ("editor", &["save file", "go to definition", "run tests", "open terminal"]),
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WindowContext {
+    // Executable name, e.g. "chrome.exe"
+    pub app_name: String,
+    pub window_title: String,
+    pub process_id: u32,
+}
+
+// The foreground app as get_focused_app and "focused-app-changed" report it
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FocusedAppInfo {
+    pub exe_name: String,
+    pub window_title: String,
+    pub pid: u32,
+}
+
+impl From<WindowContext> for FocusedAppInfo {
+    fn from(window: WindowContext) -> Self {
+        Self {
+            exe_name: window.app_name,
+            window_title: window.window_title,
+            pid: window.process_id,
+        }
+    }
+}
+
+// Where the foreground window comes from; tests substitute their own
+pub trait ForegroundWindowQuery {
+    fn foreground_window(&self) -> Option<WindowContext>;
+}
+
+pub struct Win32ForegroundWindow;
+
+impl ForegroundWindowQuery for Win32ForegroundWindow {
+    fn foreground_window(&self) -> Option<WindowContext> {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() {
+                return None;
+            }
+
+            let mut title = [0u16; 512];
+            let len = GetWindowTextW(hwnd, title.as_mut_ptr(), title.len() as i32);
+            let window_title = String::from_utf16_lossy(&title[..len.max(0) as usize]);
+
+            let mut process_id = 0;
+            GetWindowThreadProcessId(hwnd, &mut process_id);
+            Some(WindowContext {
+                app_name: process_name(process_id).unwrap_or_default(),
+                window_title,
+                process_id,
+            })
+        }
+    }
+}
+
+// File name of a process's executable
+unsafe fn process_name(process_id: u32) -> Option<String> {
+    let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id);
+    if process.is_null() {
+        return None;
+    }
+    let mut path = [0u16; 1024];
+    let mut size = path.len() as u32;
+    let succeeded = QueryFullProcessImageNameW(process, 0, path.as_mut_ptr(), &mut size) != 0;
+    CloseHandle(process);
+    if !succeeded {
+        return None;
+    }
+
+    let path = String::from_utf16_lossy(&path[..size as usize]);
+    path.rsplit('\\').next().map(str::to_string)
+}
+
+// The context an app belongs to, if any
+pub fn classify(app_name: &str) -> Option<&'static str> {
+    let app_name = app_name.to_lowercase();
+    CONTEXT_APPS
+        .iter()
+        .find(|(_, apps)| apps.contains(&app_name.as_str()))
+        .map(|(context, _)| *context)
+}
+
+// The foreground app right now; only asks Windows, so it's cheap enough to
+// call from a command on every request
+pub fn focused_app() -> Option<FocusedAppInfo> {
+    Win32ForegroundWindow.foreground_window().map(FocusedAppInfo::from)
+}
+
+// Whether `current` is still the app behind `previous`, whatever its title
+fn same_app(previous: Option<&WindowContext>, current: &WindowContext) -> bool {
+    previous.is_some_and(|previous| {
+        previous.process_id == current.process_id && previous.app_name.eq_ignore_ascii_case(&current.app_name)
+    })
+}
+
+pub fn default_grammars() -> HashMap<String, Vec<String>> {
+    DEFAULT_CONTEXT_GRAMMARS
+        .iter()
+        .map(|(context, phrases)| {
+            let phrases = phrases.iter().map(|phrase| phrase.to_string()).collect();
+            (context.to_string(), phrases)
+        })
+        .collect()
+}
+
+pub struct ContextDetector<Q: ForegroundWindowQuery> {
+    query: Q,
+    window: Option<WindowContext>,
+    context: Option<&'static str>,
+}
+
+impl<Q: ForegroundWindowQuery> ContextDetector<Q> {
+    pub fn new(query: Q) -> Self {
+        Self {
+            query,
+            window: None,
+            context: None,
+        }
+    }
+
+    // The foreground window as of the last poll
+    pub fn window(&self) -> Option<&WindowContext> {
+        self.window.as_ref()
+    }
+
+    // Check the foreground window once. Returns it if it changed, and calls
+    // `on_context` if that moved recognition into a different context.
+    pub fn poll(&mut self, on_context: &mut impl FnMut(Option<&'static str>)) -> Option<WindowContext> {
+        let window = self.query.foreground_window();
+        if window == self.window {
+            return None;
+        }
+        self.window = window.clone();
+
+        let context = window.as_ref().and_then(|window| classify(&window.app_name));
+        if context != self.context {
+            self.context = context;
+            on_context(context);
+        }
+        window
+    }
+}
+
+// Poll the foreground window every POLL_INTERVAL, emitting "context-changed"
+// when it changes, "focused-app-changed" when another app takes the
+// foreground, and calling `on_context` when the recognition context changes
+pub fn start_context_watcher<R: Runtime>(app: AppHandle<R>, mut on_context: impl FnMut(Option<&'static str>) + Send + 'static) {
+    tauri::async_runtime::spawn(async move {
+        let mut detector = ContextDetector::new(Win32ForegroundWindow);
+        loop {
+            let previous = detector.window().cloned();
+            if let Some(window) = detector.poll(&mut on_context) {
+                if !same_app(previous.as_ref(), &window) {
+                    let _ = app.emit("focused-app-changed", FocusedAppInfo::from(window.clone()));
+                }
+                let _ = app.emit("context-changed", window);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // Returns the scripted windows in order, then keeps returning the last one
+    struct ScriptedWindows(RefCell<Vec<Option<WindowContext>>>);
+
+    impl ForegroundWindowQuery for ScriptedWindows {
+        fn foreground_window(&self) -> Option<WindowContext> {
+            let mut windows = self.0.borrow_mut();
+            if windows.len() > 1 {
+                windows.remove(0)
+            } else {
+                windows.first().cloned().flatten()
+            }
+        }
+    }
+
+    fn window(process_id: u32, app_name: &str, window_title: &str) -> Option<WindowContext> {
+        Some(WindowContext {
+            app_name: app_name.to_string(),
+            window_title: window_title.to_string(),
+            process_id,
+        })
+    }
+
+    #[test]
+    fn reports_window_changes_and_context_switches() {
+        let query = ScriptedWindows(RefCell::new(vec![
+            window(10, "chrome.exe", "Inbox"),
+            window(10, "chrome.exe", "Inbox"),
+            window(10, "Chrome.exe", "News"),
+            window(20, "Code.exe", "main.rs"),
+            None,
+        ]));
+        let mut detector = ContextDetector::new(query);
+        let mut contexts = Vec::new();
+        let mut on_context = |context| contexts.push(context);
+
+        assert_eq!(detector.poll(&mut on_context), window(10, "chrome.exe", "Inbox"));
+        assert_eq!(detector.poll(&mut on_context), None);
+        // A new title in the same app is reported, but the context stays put
+        assert_eq!(detector.poll(&mut on_context), window(10, "Chrome.exe", "News"));
+        assert_eq!(detector.poll(&mut on_context), window(20, "Code.exe", "main.rs"));
+        assert_eq!(detector.poll(&mut on_context), None);
+
+        assert_eq!(contexts, vec![Some("browser"), Some("editor"), None]);
+    }
+
+    #[test]
+    fn a_new_title_is_not_a_new_app() {
+        let inbox = window(10, "chrome.exe", "Inbox").unwrap();
+        assert!(same_app(Some(&inbox), &window(10, "Chrome.exe", "News").unwrap()));
+        assert!(!same_app(Some(&inbox), &window(11, "chrome.exe", "Inbox").unwrap()));
+        assert!(!same_app(Some(&inbox), &window(10, "code.exe", "Inbox").unwrap()));
+        assert!(!same_app(None, &inbox));
+
+        let info = FocusedAppInfo::from(inbox);
+        assert_eq!((info.exe_name.as_str(), info.window_title.as_str(), info.pid), ("chrome.exe", "Inbox", 10));
+    }
+
+    #[test]
+    fn unknown_apps_have_no_context() {
+        assert_eq!(classify("explorer.exe"), None);
+        assert_eq!(classify("FIREFOX.EXE"), Some("browser"));
+    }
+}
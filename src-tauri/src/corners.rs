@@ -0,0 +1,85 @@
+// corners.rs - Windows 11 rounded window corners through DWM
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use tauri::{Runtime, WebviewWindow};
+use winapi::shared::windef::HWND;
+use winapi::shared::winerror::FAILED;
+use winapi::um::dwmapi::DwmSetWindowAttribute;
+use winreg::enums::HKEY_LOCAL_MACHINE;
+use winreg::RegKey;
+
+// DWMWA_WINDOW_CORNER_PREFERENCE, newer than the winapi headers
+const DWMWA_WINDOW_CORNER_PREFERENCE: u32 = 33;
+// Windows 11 is build 22000 and up, and the first to know the attribute
+const FIRST_WINDOWS_11_BUILD: u32 = 22000;
+
+const CURRENT_VERSION_KEY: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CornerStyle {
+    // Whatever Windows picks for this kind of window
+    #[default]
+    Default,
+    DoNotRound,
+    Round,
+    RoundSmall,
+}
+
+// The DWM_WINDOW_CORNER_PREFERENCE value for a style, or None where the
+// build doesn't support it
+fn corner_preference(style: CornerStyle, build: u32) -> Option<u32> {
+    if build < FIRST_WINDOWS_11_BUILD {
+        return None;
+    }
+    Some(match style {
+        CornerStyle::Default => 0,
+        CornerStyle::DoNotRound => 1,
+        CornerStyle::Round => 2,
+        CornerStyle::RoundSmall => 3,
+    })
+}
+
+fn windows_build() -> u32 {
+    RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey(CURRENT_VERSION_KEY)
+        .and_then(|key| key.get_value::<String, _>("CurrentBuildNumber"))
+        .ok()
+        .and_then(|build| build.parse().ok())
+        .unwrap_or(0)
+}
+
+// Does nothing before Windows 11, whose windows always have square corners
+pub fn set_corner_style<R: Runtime>(window: &WebviewWindow<R>, style: CornerStyle) -> Result<()> {
+    let Some(preference) = corner_preference(style, windows_build()) else {
+        return Ok(());
+    };
+    let hwnd = window.hwnd()?.0 as HWND;
+    let hr = unsafe {
+        DwmSetWindowAttribute(
+            hwnd,
+            DWMWA_WINDOW_CORNER_PREFERENCE,
+            &preference as *const u32 as *const _,
+            mem::size_of::<u32>() as u32,
+        )
+    };
+    if FAILED(hr) {
+        return Err(anyhow::anyhow!("Failed to set the window corner style (HRESULT {:#010x})", hr));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn older_builds_are_left_alone() {
+        // Windows 10 22H2
+        assert_eq!(corner_preference(CornerStyle::Round, 19045), None);
+        assert_eq!(corner_preference(CornerStyle::Round, 0), None);
+        assert_eq!(corner_preference(CornerStyle::Round, 22000), Some(2));
+        assert_eq!(corner_preference(CornerStyle::DoNotRound, 22631), Some(1));
+    }
+}
@@ -0,0 +1,101 @@
+// debounce.rs - Trailing-edge debounce, so the last of a burst of requests is
+// applied late rather than dropped
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq)]
+pub enum Debounced<T> {
+    // Apply this now
+    Apply(T),
+    // Held back; call flush() after this long to get the latest value
+    Schedule(Duration),
+    // Replaced the value already waiting for a flush
+    Queued,
+}
+
+pub struct Debouncer<T> {
+    last_applied: Option<Instant>,
+    pending: Option<T>,
+}
+
+impl<T> Debouncer<T> {
+    pub fn new() -> Self {
+        Self {
+            last_applied: None,
+            pending: None,
+        }
+    }
+
+    // Times are passed in so the sequencing can be tested without sleeping
+    pub fn request(&mut self, value: T, now: Instant, interval: Duration) -> Debounced<T> {
+        if let Some(pending) = self.pending.as_mut() {
+            *pending = value;
+            return Debounced::Queued;
+        }
+        match self.last_applied {
+            Some(last) if now.saturating_duration_since(last) < interval => {
+                self.pending = Some(value);
+                Debounced::Schedule(interval - now.saturating_duration_since(last))
+            }
+            _ => {
+                self.last_applied = Some(now);
+                Debounced::Apply(value)
+            }
+        }
+    }
+
+    // The latest held-back value, counted as applied at `now`
+    pub fn flush(&mut self, now: Instant) -> Option<T> {
+        let value = self.pending.take()?;
+        self.last_applied = Some(now);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn at(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn applies_immediately_outside_the_interval() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new();
+
+        assert_eq!(debouncer.request(1, at(start, 0), INTERVAL), Debounced::Apply(1));
+        assert_eq!(debouncer.request(2, at(start, 150), INTERVAL), Debounced::Apply(2));
+        assert_eq!(debouncer.flush(at(start, 300)), None);
+    }
+
+    #[test]
+    fn burst_ends_with_the_last_request() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new();
+
+        assert_eq!(debouncer.request(1, at(start, 0), INTERVAL), Debounced::Apply(1));
+        assert_eq!(debouncer.request(2, at(start, 30), INTERVAL), Debounced::Schedule(Duration::from_millis(70)));
+        assert_eq!(debouncer.request(3, at(start, 60), INTERVAL), Debounced::Queued);
+        assert_eq!(debouncer.request(4, at(start, 90), INTERVAL), Debounced::Queued);
+
+        assert_eq!(debouncer.flush(at(start, 100)), Some(4));
+        assert_eq!(debouncer.flush(at(start, 110)), None);
+    }
+
+    #[test]
+    fn flush_restarts_the_interval() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new();
+
+        debouncer.request(1, at(start, 0), INTERVAL);
+        debouncer.request(2, at(start, 50), INTERVAL);
+        assert_eq!(debouncer.flush(at(start, 100)), Some(2));
+
+        assert_eq!(debouncer.request(3, at(start, 150), INTERVAL), Debounced::Schedule(Duration::from_millis(50)));
+        assert_eq!(debouncer.flush(at(start, 200)), Some(3));
+        assert_eq!(debouncer.request(4, at(start, 300), INTERVAL), Debounced::Apply(4));
+    }
+}
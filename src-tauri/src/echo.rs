@@ -0,0 +1,288 @@
+// echo.rs - Telling whether the microphone hears our own playback, by
+// correlating what was captured with what the output device just played
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::audio;
+
+// Both signals are brought down to this rate; speech correlates fine at it
+// and the lag search stays cheap
+const ANALYSIS_RATE: u32 = 4_000;
+// Capture audio compared per estimate
+const WINDOW: usize = ANALYSIS_RATE as usize / 2;
+// Longest output-to-microphone delay searched for
+const MAX_LAG: usize = ANALYSIS_RATE as usize * 3 / 10;
+// Capture audio between estimates
+const ESTIMATE_EVERY: usize = ANALYSIS_RATE as usize / 4;
+// Correlation above this is echo
+pub const ECHO_THRESHOLD: f32 = 0.5;
+// Playback quieter than this is silence, and nothing to correlate with
+const REFERENCE_RMS: f32 = 0.005;
+// Playback counts as ongoing this long after it was last heard
+const PLAYBACK_HOLD: Duration = Duration::from_millis(500);
+// At most one detection reported per this long
+const EVENT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EchoStatus {
+    // Wake words are being ignored because output is playing into the microphone
+    pub suppression_active: bool,
+    // Echo was found since the capture started
+    pub echo_detected: bool,
+    // The latest estimate, from 0 (none) to 1; None until playback was heard
+    pub level: Option<f32>,
+    pub threshold: f32,
+}
+
+pub struct AcousticEchoDetector {
+    threshold: f32,
+    // Playback, WINDOW + MAX_LAG samples at the analysis rate
+    reference: VecDeque<f32>,
+    // Capture, WINDOW samples at the analysis rate
+    capture: VecDeque<f32>,
+    since_estimate: usize,
+    level: Option<f32>,
+    echo_detected: bool,
+    last_playback: Option<Instant>,
+    last_event: Option<Instant>,
+}
+
+impl AcousticEchoDetector {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            reference: VecDeque::with_capacity(WINDOW + MAX_LAG),
+            capture: VecDeque::with_capacity(WINDOW),
+            since_estimate: 0,
+            level: None,
+            echo_detected: false,
+            last_playback: None,
+            last_event: None,
+        }
+    }
+
+    // Samples as they go out to the output device, silence included, so the
+    // reference stays in step with the capture
+    pub fn push_playback(&mut self, samples: &[i16], sample_rate: u32, now: Instant) {
+        if samples.is_empty() || sample_rate == 0 {
+            return;
+        }
+        if audio::rms(samples) >= REFERENCE_RMS {
+            self.last_playback = Some(now);
+        }
+        push_limited(&mut self.reference, samples, sample_rate, WINDOW + MAX_LAG);
+    }
+
+    // Returns the level when this frame newly reveals echo, at most once per
+    // EVENT_INTERVAL
+    pub fn push_capture(&mut self, frame: &[i16], sample_rate: u32, now: Instant) -> Option<f32> {
+        if frame.is_empty() || sample_rate == 0 {
+            return None;
+        }
+        self.since_estimate += push_limited(&mut self.capture, frame, sample_rate, WINDOW);
+        if self.since_estimate < ESTIMATE_EVERY || !self.playback_recent(now) {
+            return None;
+        }
+        self.since_estimate = 0;
+        let level = self.estimate()?;
+        self.level = Some(level);
+        if level < self.threshold {
+            return None;
+        }
+        self.echo_detected = true;
+        let report = self.last_event.map_or(true, |last| now.saturating_duration_since(last) >= EVENT_INTERVAL);
+        if report {
+            self.last_event = Some(now);
+        }
+        report.then_some(level)
+    }
+
+    // Highest normalized correlation between the capture window and the
+    // playback at any delay; None until both buffers are full
+    fn estimate(&self) -> Option<f32> {
+        if self.capture.len() < WINDOW || self.reference.len() < WINDOW + MAX_LAG {
+            return None;
+        }
+        let capture: Vec<f32> = self.capture.iter().copied().collect();
+        let reference: Vec<f32> = self.reference.iter().copied().collect();
+        let capture_energy: f32 = capture.iter().map(|s| s * s).sum();
+        if capture_energy <= f32::EPSILON {
+            return Some(0.0);
+        }
+
+        // Delay `lag` lines the capture up with reference[MAX_LAG - lag..]
+        let mut reference_energy: f32 = reference[MAX_LAG..].iter().map(|s| s * s).sum();
+        let mut best = 0.0f32;
+        for lag in 0..MAX_LAG {
+            let start = MAX_LAG - lag;
+            if lag > 0 {
+                // Slide the window one sample earlier
+                reference_energy += reference[start] * reference[start] - reference[start + WINDOW] * reference[start + WINDOW];
+            }
+            if reference_energy <= f32::EPSILON {
+                continue;
+            }
+            let product: f32 = capture.iter().zip(&reference[start..start + WINDOW]).map(|(c, r)| c * r).sum();
+            best = best.max(product.abs() / (capture_energy * reference_energy).sqrt());
+        }
+        Some(best.min(1.0))
+    }
+
+    fn playback_recent(&self, now: Instant) -> bool {
+        self.last_playback.is_some_and(|last| now.saturating_duration_since(last) < PLAYBACK_HOLD)
+    }
+
+    // Once echo has been found, wake words are ignored whenever something is
+    // playing: our own audio, or replies the frontend marks as playing
+    pub fn is_suppressing(&self, output_playing: bool, now: Instant) -> bool {
+        self.echo_detected && (output_playing || self.playback_recent(now))
+    }
+
+    pub fn status(&self, output_playing: bool, now: Instant) -> EchoStatus {
+        EchoStatus {
+            suppression_active: self.is_suppressing(output_playing, now),
+            echo_detected: self.echo_detected,
+            level: self.level,
+            threshold: self.threshold,
+        }
+    }
+
+    // Forget what was learned, e.g. when the capture restarts on another device
+    pub fn reset(&mut self) {
+        *self = Self::new(self.threshold);
+    }
+}
+
+// Appends the frame at the analysis rate, dropping the oldest samples past
+// `limit`; returns how many samples were added
+fn push_limited(buffer: &mut VecDeque<f32>, samples: &[i16], sample_rate: u32, limit: usize) -> usize {
+    let resampled = audio::resample(samples, sample_rate, ANALYSIS_RATE);
+    buffer.extend(resampled.iter().map(|&s| s as f32 / i16::MAX as f32));
+    let excess = buffer.len().saturating_sub(limit);
+    buffer.drain(..excess);
+    resampled.len()
+}
+
+// Shared between the output stream, the capture and the recognition loop
+#[derive(Clone)]
+pub struct EchoMonitor {
+    detector: Arc<Mutex<AcousticEchoDetector>>,
+}
+
+impl Default for EchoMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EchoMonitor {
+    pub fn new() -> Self {
+        Self { detector: Arc::new(Mutex::new(AcousticEchoDetector::new(ECHO_THRESHOLD))) }
+    }
+
+    pub fn push_playback(&self, samples: &[i16], sample_rate: u32) {
+        self.detector.lock().unwrap().push_playback(samples, sample_rate, Instant::now());
+    }
+
+    pub fn push_capture(&self, frame: &[i16], sample_rate: u32) -> Option<f32> {
+        self.detector.lock().unwrap().push_capture(frame, sample_rate, Instant::now())
+    }
+
+    pub fn is_suppressing(&self, output_playing: bool) -> bool {
+        self.detector.lock().unwrap().is_suppressing(output_playing, Instant::now())
+    }
+
+    pub fn status(&self, output_playing: bool) -> EchoStatus {
+        self.detector.lock().unwrap().status(output_playing, Instant::now())
+    }
+
+    pub fn reset(&self) {
+        self.detector.lock().unwrap().reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+    const FRAME: usize = 160;
+
+    // Deterministic white noise standing in for speech
+    fn noise(seed: u32, len: usize) -> Vec<i16> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                ((state >> 16) as i16) / 4
+            })
+            .collect()
+    }
+
+    // Plays `playback` while capturing `capture`, 10 ms at a time; returns
+    // the detector and the levels it reported
+    fn run(playback: &[i16], capture: &[i16]) -> (AcousticEchoDetector, Vec<f32>) {
+        let mut detector = AcousticEchoDetector::new(ECHO_THRESHOLD);
+        let start = Instant::now();
+        let mut reported = Vec::new();
+        for (i, (played, captured)) in playback.chunks(FRAME).zip(capture.chunks(FRAME)).enumerate() {
+            let now = start + Duration::from_millis(10 * i as u64);
+            detector.push_playback(played, RATE, now);
+            reported.extend(detector.push_capture(captured, RATE, now));
+        }
+        (detector, reported)
+    }
+
+    #[test]
+    fn finds_delayed_playback_in_the_capture() {
+        let playback = noise(1, RATE as usize * 2);
+        // 100 ms of delay, quieter, with some room noise on top
+        let delay = RATE as usize / 10;
+        let room = noise(2, playback.len());
+        let capture: Vec<i16> = (0..playback.len())
+            .map(|i| {
+                let echo = if i >= delay { playback[i - delay] as f32 * 0.6 } else { 0.0 };
+                (echo + room[i] as f32 * 0.1) as i16
+            })
+            .collect();
+
+        let (detector, reported) = run(&playback, &capture);
+        assert_eq!(reported.len(), 1, "one event per interval");
+        assert!(reported[0] > 0.9, "level {}", reported[0]);
+        assert!(detector.is_suppressing(false, Instant::now()));
+    }
+
+    #[test]
+    fn unrelated_sound_is_not_echo() {
+        let playback = noise(1, RATE as usize * 2);
+        let capture = noise(3, playback.len());
+        let (detector, reported) = run(&playback, &capture);
+        assert!(reported.is_empty());
+        assert!(detector.level.is_some_and(|level| level < ECHO_THRESHOLD));
+        assert!(!detector.status(true, Instant::now()).suppression_active);
+    }
+
+    #[test]
+    fn nothing_is_estimated_without_playback() {
+        let playback = vec![0; RATE as usize * 2];
+        let capture = noise(3, playback.len());
+        let (detector, reported) = run(&playback, &capture);
+        assert!(reported.is_empty());
+        assert_eq!(detector.level, None);
+    }
+
+    #[test]
+    fn suppresses_only_while_something_plays() {
+        let playback = noise(1, RATE as usize * 2);
+        let (mut detector, _) = run(&playback, &playback);
+        // The run's clock ends two seconds in
+        let later = Instant::now() + Duration::from_secs(2) + PLAYBACK_HOLD;
+        assert!(!detector.is_suppressing(false, later));
+        assert!(detector.is_suppressing(true, later));
+
+        detector.reset();
+        assert!(!detector.is_suppressing(true, later));
+    }
+}
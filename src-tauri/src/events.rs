@@ -0,0 +1,258 @@
+// events.rs - Payloads of the events emitted to the frontend. Keep these in
+// sync with the frontend's listeners; field names are the wire format.
+use serde::Serialize;
+
+use crate::config::{DisplayMode, MonitorPlacement, WindowMode};
+use crate::session::CloseReason;
+use crate::theme::SystemTheme;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WakeWordDetectedPayload {
+    pub keyword_index: usize,
+    // The matched text, e.g. "Hey Jackson"
+    pub phrase: String,
+    pub confidence: f32,
+    // Milliseconds since the UNIX epoch
+    pub timestamp_ms: u64,
+    // Loudness of the audio behind the wake word; 0 without audio capture
+    pub audio_energy_rms: f32,
+}
+
+// A wake word was heard but the audio behind it was too quiet
+#[derive(Clone, Debug, Serialize)]
+pub struct WakeWordRejectedPayload {
+    pub rms: f32,
+    pub threshold: f32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ListeningReadyPayload {}
+
+// The app is about to exit; the frontend should save what it needs now
+#[derive(Clone, Debug, Serialize)]
+pub struct AppShuttingDownPayload {}
+
+// The microphone is picking up our own playback; level is the correlation
+// between the two, from 0 to 1
+#[derive(Clone, Debug, Serialize)]
+pub struct EchoDetectedPayload {
+    pub level: f32,
+}
+
+// Listening was toggled from the hotkey or the tray; error says why it didn't
+// go the requested way
+#[derive(Clone, Debug, Serialize)]
+pub struct ListeningToggledPayload {
+    pub listening: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NumberDictatedPayload {
+    // Digits with an optional leading "-" and decimal point, e.g. "3.14"
+    pub number: String,
+}
+
+// One microphone of a multi-device capture failed; the others keep going
+#[derive(Clone, Debug, Serialize)]
+pub struct CaptureDeviceErrorPayload {
+    pub device: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeechSessionStartedPayload {
+    pub session_id: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeechSessionEndedPayload {
+    pub session_id: u64,
+    pub reason: CloseReason,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ModelDownloadProgressPayload {
+    pub model: String,
+    pub percent: u8,
+    pub bytes_downloaded: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UtteranceAudioWarningPayload {
+    pub utterance_id: u64,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CalibrationProgressPayload {
+    pub percent: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VocabularyChangedPayload {
+    pub phrases: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowShownPayload {}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OverlayEnteredPayload {}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MicrophonePausedPayload {}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MicrophoneResumedPayload {}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OverlayExitedPayload {}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowHiddenPayload {}
+
+// Sent alongside "window-hidden" when the window was hidden for inactivity
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowAutoHiddenPayload {
+    pub idle_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowConfigChangedPayload {
+    pub always_on_top: bool,
+    pub show_without_focus: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowResizedPayload {
+    // Logical size, as the frontend lays out in
+    pub width: f64,
+    pub height: f64,
+    pub physical_width: u32,
+    pub physical_height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub reason: ResizeReason,
+}
+
+// What made the backend resize the window
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeReason {
+    // resize_window, resize_and_position_window or reset_window_position
+    UserRequest,
+    // Shown at the compact preset
+    WakeWordShow,
+    // set_window_mode, overlay mode, or the first transcript expanding the window
+    ModeChange,
+    // Re-fitted after the window settings or the displays changed
+    Clamp,
+}
+
+// Returned by get_window_info and sent as "window-geometry-changed" after the
+// backend moves, resizes, shows or hides the window. Sizes and positions are
+// physical pixels.
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowInfo {
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub visible: bool,
+    pub focused: bool,
+    pub monitor_name: Option<String>,
+    pub scale_factor: f64,
+    pub ignore_cursor_events: bool,
+}
+
+// One entry of list_monitors. Positions are physical pixels and negative for
+// displays left of or above the primary one.
+#[derive(Clone, Debug, Serialize)]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+// The display chosen with set_display isn't connected, so the window is on
+// the primary one. Sent once until the display is back.
+#[derive(Clone, Debug, Serialize)]
+pub struct DisplayUnavailablePayload {
+    pub display: MonitorPlacement,
+}
+
+// Sent as "dnd-enabled" or "dnd-disabled"
+#[derive(Clone, Debug, Serialize)]
+pub struct DoNotDisturbPayload {
+    pub enabled: bool,
+}
+
+// Sent as "system-theme" at startup and "system-theme-changed" after
+#[derive(Clone, Debug, Serialize)]
+pub struct ThemePayload {
+    pub theme: SystemTheme,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeechTrainingStartedPayload {}
+
+// The training wizard closed; exit_code is None if it was killed
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeechTrainingCompletePayload {
+    pub exit_code: Option<i32>,
+}
+
+// Show `prompt` for the user to read out; recording has started
+#[derive(Clone, Debug, Serialize)]
+pub struct TrainingPhrasePromptPayload {
+    pub prompt: String,
+}
+
+// A training phrase was recorded and saved to `path`
+#[derive(Clone, Debug, Serialize)]
+pub struct TrainingPhraseRecordedPayload {
+    pub prompt: String,
+    pub path: String,
+    pub duration_ms: u64,
+}
+
+// Monitor information couldn't be read while placing the window, which
+// happens in some RDP sessions and just after the display wakes
+#[derive(Clone, Debug, Serialize)]
+pub struct MonitorInfoUnavailablePayload {
+    // Otherwise the window was resized where it was
+    pub using_cached_work_area: bool,
+    // Since the app started
+    pub occurrences: u64,
+}
+
+// A hotkey from the settings couldn't be registered at startup, usually
+// because another app already uses it
+#[derive(Clone, Debug, Serialize)]
+pub struct HotkeyRegistrationFailedPayload {
+    pub error: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeechTimeoutUpdatedPayload {
+    // 0 is unlimited
+    pub seconds: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowModeChangedPayload {
+    pub mode: WindowMode,
+}
+
+// Sent as "display-mode-changed" alongside "window-mode-changed", except for
+// the overlay
+#[derive(Clone, Debug, Serialize)]
+pub struct DisplayModeChangedPayload {
+    pub mode: DisplayMode,
+}
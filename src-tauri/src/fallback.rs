@@ -0,0 +1,220 @@
+// fallback.rs - Run utterance audio through an ordered chain of recognition backends
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::speech_recognition::{RecognitionBackend, RecognitionResult};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChainStep {
+    pub backend: String,
+    // Move on to the next step if this backend fails
+    #[serde(default = "default_on_error")]
+    pub on_error: bool,
+    // ...or if its confidence is below this. Results without a confidence
+    // are always accepted.
+    #[serde(default)]
+    pub confidence_below: Option<f32>,
+}
+
+fn default_on_error() -> bool {
+    true
+}
+
+// Builds the backend for a step; create_backend outside of tests
+pub type BackendFactory<'a> = &'a dyn Fn(&str) -> Result<Box<dyn RecognitionBackend + Send>>;
+
+// Reject unknown backends, ones that can't transcribe stored audio, repeats
+// (which would only loop back over the same engine) and out-of-range thresholds
+pub fn validate(chain: &[ChainStep], known_backends: &[&str], transcribing_backends: &[&str]) -> Result<(), String> {
+    for (index, step) in chain.iter().enumerate() {
+        if !known_backends.contains(&step.backend.as_str()) {
+            return Err(format!("Unknown recognition backend in chain: {}", step.backend));
+        }
+        if !transcribing_backends.contains(&step.backend.as_str()) {
+            return Err(format!("The {} backend can't transcribe stored audio, so it can't be in the chain", step.backend));
+        }
+        if chain[..index].iter().any(|earlier| earlier.backend == step.backend) {
+            return Err(format!("Backend {} appears more than once in the chain", step.backend));
+        }
+        if let Some(threshold) = step.confidence_below {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(format!("Confidence threshold for {} must be between 0 and 1", step.backend));
+            }
+        }
+    }
+    Ok(())
+}
+
+// The steps of a saved chain that can run. Empty means live results are
+// delivered as they are.
+pub fn runnable(chain: &[ChainStep], transcribing_backends: &[&str]) -> Vec<ChainStep> {
+    chain
+        .iter()
+        .filter(|step| transcribing_backends.contains(&step.backend.as_str()))
+        .cloned()
+        .collect()
+}
+
+// Try each step in order until one gives a result its conditions accept. The
+// result is tagged with the backend that produced it and how many steps were
+// skipped. If the chain runs out, the best low-confidence result so far wins.
+pub fn run(chain: &[ChainStep], samples: &[i16], sample_rate: u32, create: BackendFactory) -> Result<RecognitionResult, String> {
+    let mut best: Option<RecognitionResult> = None;
+    let mut last_error = "The backend chain is empty".to_string();
+
+    for (index, step) in chain.iter().enumerate() {
+        let is_last = index + 1 == chain.len();
+        let outcome = create(&step.backend).and_then(|backend| backend.transcribe(samples, sample_rate));
+        match outcome {
+            Ok(mut result) => {
+                result.source = step.backend.clone();
+                result.fallbacks = index as u32;
+                let low_confidence = matches!(
+                    (result.confidence, step.confidence_below),
+                    (Some(confidence), Some(threshold)) if confidence < threshold
+                );
+                if !low_confidence || is_last {
+                    return Ok(result);
+                }
+                println!("↪️ {} confidence too low, falling back", step.backend);
+                best = Some(result);
+            }
+            Err(e) => {
+                last_error = format!("{} failed: {}", step.backend, e);
+                if !step.on_error {
+                    break;
+                }
+                println!("↪️ {}, falling back", last_error);
+            }
+        }
+    }
+
+    best.ok_or(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::speech_recognition::{EndpointingConfig, ResultCallback};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    struct MockBackend {
+        outcome: Result<(String, Option<f32>), String>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl RecognitionBackend for MockBackend {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+        fn start(&self, _callback: ResultCallback) -> Result<()> {
+            Ok(())
+        }
+        fn stop(&self) {}
+        fn feed_audio(&self, _samples: &[i16], _sample_rate: u32) {}
+        fn set_language(&self, _language: &str) -> Result<()> {
+            Ok(())
+        }
+        fn set_endpointing(&self, _config: &EndpointingConfig) {}
+        fn set_vocabulary(&self, _phrases: &[String]) {}
+
+        fn transcribe(&self, _samples: &[i16], _sample_rate: u32) -> Result<RecognitionResult> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let (text, confidence) = self.outcome.clone().map_err(|e| anyhow::anyhow!(e))?;
+            Ok(RecognitionResult {
+                text: text.clone(),
+                raw_text: text,
+                constraint_index: None,
+                confidence,
+                source: String::new(),
+                entities: Vec::new(),
+                fallbacks: 0,
+                alternatives: Vec::new(),
+            })
+        }
+    }
+
+    fn step(backend: &str, confidence_below: Option<f32>) -> ChainStep {
+        ChainStep {
+            backend: backend.to_string(),
+            on_error: true,
+            confidence_below,
+        }
+    }
+
+    // Backends "failing", "unsure" (confidence 0.3) and "good" (0.9), counting calls per name
+    fn factory(calls: Arc<AtomicU32>) -> impl Fn(&str) -> Result<Box<dyn RecognitionBackend + Send>> {
+        move |name| {
+            let outcome = match name {
+                "failing" => Err("model missing".to_string()),
+                "unsure" => Ok(("unsure text".to_string(), Some(0.3))),
+                _ => Ok(("good text".to_string(), Some(0.9))),
+            };
+            Ok(Box::new(MockBackend {
+                outcome,
+                calls: Arc::clone(&calls),
+            }))
+        }
+    }
+
+    #[test]
+    fn falls_back_once_when_the_first_backend_fails() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let create = factory(Arc::clone(&calls));
+        let chain = [step("failing", None), step("good", None)];
+
+        let result = run(&chain, &[0; 160], 16_000, &create).unwrap();
+
+        assert_eq!(result.text, "good text");
+        assert_eq!(result.source, "good");
+        assert_eq!(result.fallbacks, 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn low_confidence_falls_through_but_is_kept_if_nothing_better() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let create = factory(Arc::clone(&calls));
+
+        let chain = [step("unsure", Some(0.5)), step("good", None)];
+        assert_eq!(run(&chain, &[], 16_000, &create).unwrap().source, "good");
+
+        let chain = [step("unsure", Some(0.5)), step("failing", None)];
+        let result = run(&chain, &[], 16_000, &create).unwrap();
+        assert_eq!(result.source, "unsure");
+        assert_eq!(result.fallbacks, 0);
+    }
+
+    #[test]
+    fn stops_on_error_when_not_allowed_to_fall_back() {
+        let create = factory(Arc::new(AtomicU32::new(0)));
+        let mut failing = step("failing", None);
+        failing.on_error = false;
+
+        let error = run(&[failing, step("good", None)], &[], 16_000, &create).unwrap_err();
+
+        assert!(error.contains("model missing"));
+    }
+
+    #[test]
+    fn rejects_duplicate_and_unknown_backends() {
+        let known = ["failing", "good"];
+        assert!(validate(&[step("failing", None), step("good", Some(0.5))], &known, &known).is_ok());
+        assert!(validate(&[step("good", None), step("good", None)], &known, &known).is_err());
+        assert!(validate(&[step("vosk", None)], &known, &known).is_err());
+        assert!(validate(&[step("good", Some(1.5))], &known, &known).is_err());
+    }
+
+    #[test]
+    fn backends_that_cant_transcribe_are_kept_out() {
+        let known = ["live", "good"];
+        let transcribing = ["good"];
+        let error = validate(&[step("live", None), step("good", None)], &known, &transcribing).unwrap_err();
+        assert!(error.contains("can't transcribe"));
+
+        let saved = [step("live", None), step("good", Some(0.5))];
+        assert_eq!(runnable(&saved, &transcribing), vec![step("good", Some(0.5))]);
+        assert!(runnable(&saved, &[]).is_empty());
+    }
+}
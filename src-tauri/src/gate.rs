@@ -0,0 +1,151 @@
+// gate.rs - A cheap energy gate in front of SAPI, so the recognition loop can
+// idle while the room is quiet
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::audio;
+use crate::pipeline::AudioProcessor;
+
+// The gate stays open this long after the last loud frame, enough for the
+// rest of a wake phrase and for SAPI to finish recognizing it
+const HANGOVER: Duration = Duration::from_millis(1500);
+// Without frames this recent the gate is open, so a failed capture never
+// silences the wake word
+const STALE_AFTER: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+struct GateTimes {
+    last_frame: Option<Instant>,
+    last_loud: Option<Instant>,
+}
+
+// Shared between the filter, which sees the audio, and the recognition loop
+#[derive(Clone, Default)]
+pub struct EnergyGate {
+    times: Arc<Mutex<GateTimes>>,
+}
+
+impl EnergyGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open_at(Instant::now())
+    }
+
+    fn is_open_at(&self, now: Instant) -> bool {
+        let times = self.times.lock().unwrap();
+        let recent = |time: Option<Instant>, within: Duration| time.is_some_and(|time| now.saturating_duration_since(time) < within);
+        !recent(times.last_frame, STALE_AFTER) || recent(times.last_loud, HANGOVER)
+    }
+
+    fn record(&self, loud: bool, now: Instant) {
+        let mut times = self.times.lock().unwrap();
+        times.last_frame = Some(now);
+        if loud {
+            times.last_loud = Some(now);
+        }
+    }
+
+    // Back to open, e.g. when the capture restarts
+    pub fn reset(&self) {
+        *self.times.lock().unwrap() = GateTimes::default();
+    }
+}
+
+// Opens the gate on frames louder than threshold_rms. The audio itself passes
+// through untouched.
+pub struct EnergyGateFilter {
+    gate: EnergyGate,
+    threshold_rms: f32,
+}
+
+impl EnergyGateFilter {
+    pub fn new(gate: EnergyGate, threshold_rms: f32) -> Self {
+        Self { gate, threshold_rms }
+    }
+}
+
+impl AudioProcessor for EnergyGateFilter {
+    fn process(&mut self, frame: &mut [i16], _sample_rate: u32) {
+        if frame.is_empty() {
+            return;
+        }
+        self.gate.record(audio::rms(frame) >= self.threshold_rms, Instant::now());
+    }
+
+    fn reset(&mut self) {
+        self.gate.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_until_audio_arrives() {
+        let gate = EnergyGate::new();
+        assert!(gate.is_open());
+    }
+
+    #[test]
+    fn closes_on_quiet_audio_and_opens_on_loud() {
+        let gate = EnergyGate::new();
+        let start = Instant::now();
+        gate.record(false, start);
+        assert!(!gate.is_open_at(start));
+
+        gate.record(true, start + Duration::from_millis(100));
+        assert!(gate.is_open_at(start + Duration::from_millis(100)));
+        gate.record(false, start + Duration::from_millis(1000));
+        assert!(gate.is_open_at(start + Duration::from_millis(1000)));
+
+        gate.record(false, start + Duration::from_millis(1700));
+        assert!(!gate.is_open_at(start + Duration::from_millis(1700)));
+    }
+
+    #[test]
+    fn opens_when_frames_stop() {
+        let gate = EnergyGate::new();
+        let start = Instant::now();
+        gate.record(false, start);
+        assert!(gate.is_open_at(start + STALE_AFTER));
+    }
+
+    #[test]
+    fn filter_leaves_audio_alone() {
+        let gate = EnergyGate::new();
+        let mut filter = EnergyGateFilter::new(gate.clone(), 0.01);
+        let mut frame = vec![0i16; 160];
+        filter.process(&mut frame, 16_000);
+        assert!(frame.iter().all(|&sample| sample == 0));
+        assert!(!gate.is_open());
+
+        let mut frame = vec![8_000i16; 160];
+        filter.process(&mut frame, 16_000);
+        assert!(frame.iter().all(|&sample| sample == 8_000));
+        assert!(gate.is_open());
+    }
+
+    // Counts recognize calls, not CPU: over a quiet minute with one second of
+    // speech in it, the loop only calls SAPI while the gate is open
+    #[test]
+    fn recognize_is_only_called_around_speech() {
+        const SLICE: Duration = Duration::from_millis(50);
+        let gate = EnergyGate::new();
+        let start = Instant::now();
+        let mut calls = 0;
+        for i in 0..1200 {
+            let level = if (600..620).contains(&i) { 8_000 } else { 50 };
+            let now = start + SLICE * i;
+            gate.record(audio::rms(&[level; 800]) >= 0.01, now);
+            if gate.is_open_at(now) {
+                calls += 1;
+            }
+        }
+        // The second of speech and the hangover after it
+        assert!((20..=50).contains(&calls), "{} calls", calls);
+    }
+}
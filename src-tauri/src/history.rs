@@ -0,0 +1,97 @@
+// history.rs - Bounded in-memory history of final transcripts
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_CAPACITY: usize = 500;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TranscriptEntry {
+    pub id: u64,
+    pub session_id: u64,
+    // Milliseconds since the UNIX epoch
+    pub timestamp_ms: u64,
+    pub text: String,
+    // Text before post-processing, only kept when raw transcript logging is on
+    pub raw_text: Option<String>,
+    pub confidence: Option<f32>,
+    pub source: String,
+    // WAV clip of the utterance, when saving utterance audio is on
+    pub audio_path: Option<String>,
+}
+
+pub struct TranscriptHistory {
+    entries: VecDeque<TranscriptEntry>,
+    capacity: usize,
+    next_id: u64,
+}
+
+impl TranscriptHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            next_id: 1,
+        }
+    }
+
+    // Append a transcript, evicting the oldest entry once the buffer is full
+    pub fn push(
+        &mut self,
+        session_id: u64,
+        text: String,
+        raw_text: Option<String>,
+        confidence: Option<f32>,
+        source: &str,
+    ) -> TranscriptEntry {
+        let entry = TranscriptEntry {
+            id: self.next_id,
+            session_id,
+            timestamp_ms: now_ms(),
+            text,
+            raw_text,
+            confidence,
+            source: source.to_string(),
+            audio_path: None,
+        };
+        self.next_id += 1;
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry.clone());
+        entry
+    }
+
+    // The most recent `limit` entries newer than `since_timestamp`, oldest first
+    pub fn query(&self, limit: Option<usize>, since_timestamp: Option<u64>) -> Vec<TranscriptEntry> {
+        let matching: Vec<&TranscriptEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| since_timestamp.map_or(true, |since| entry.timestamp_ms > since))
+            .collect();
+        let skip = limit.map_or(0, |limit| matching.len().saturating_sub(limit));
+        matching.into_iter().skip(skip).cloned().collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&TranscriptEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    pub fn set_audio_path(&mut self, id: u64, path: String) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.audio_path = Some(path);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
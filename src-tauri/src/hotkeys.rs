@@ -0,0 +1,88 @@
+// hotkeys.rs - Global hotkeys, which still work when the window can't be clicked
+use serde::{Deserialize, Serialize};
+use tauri_plugin_global_shortcut::Shortcut;
+
+pub const DEFAULT_TOGGLE_HOTKEY: &str = "Ctrl+Shift+J";
+pub const DEFAULT_LISTENING_HOTKEY: &str = "Ctrl+Alt+J";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hotkeys {
+    // Shows the window, or hides it if it's showing
+    pub toggle: String,
+    // Makes the whole window take mouse input again, in case it's stuck
+    // click-through; None for no hotkey
+    pub interact: Option<String>,
+    // Starts or stops listening for the wake word; None for no hotkey
+    pub listening: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ParsedHotkeys {
+    pub toggle: Shortcut,
+    pub interact: Option<Shortcut>,
+    pub listening: Option<Shortcut>,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            toggle: DEFAULT_TOGGLE_HOTKEY.to_string(),
+            interact: None,
+            listening: Some(DEFAULT_LISTENING_HOTKEY.to_string()),
+        }
+    }
+}
+
+impl Hotkeys {
+    // Parse the accelerators, e.g. "Ctrl+Shift+J"; no two may be the same
+    pub fn parse(&self) -> Result<ParsedHotkeys, String> {
+        let toggle = parse_hotkey(&self.toggle)?;
+        let interact = self.interact.as_deref().map(parse_hotkey).transpose()?;
+        let listening = self.listening.as_deref().map(parse_hotkey).transpose()?;
+        if interact == Some(toggle) {
+            return Err(format!("{} can't be both the toggle and the interact hotkey", self.toggle));
+        }
+        if listening == Some(toggle) {
+            return Err(format!("{} can't be both the toggle and the listening hotkey", self.toggle));
+        }
+        if let (Some(interact), Some(listening)) = (interact, listening) {
+            if interact == listening {
+                return Err(format!("{} can't be both the interact and the listening hotkey", self.listening.as_deref().unwrap_or_default()));
+            }
+        }
+        Ok(ParsedHotkeys { toggle, interact, listening })
+    }
+
+    // The tray tooltip, naming the toggle hotkey
+    pub fn tooltip(&self, base: &str) -> String {
+        format!("{} ({})", base, self.toggle)
+    }
+}
+
+fn parse_hotkey(hotkey: &str) -> Result<Shortcut, String> {
+    hotkey.parse().map_err(|e| format!("Invalid hotkey \"{}\": {}", hotkey, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hotkeys_must_parse_and_differ() {
+        assert!(Hotkeys::default().parse().is_ok());
+
+        let same = Hotkeys { toggle: "Ctrl+Shift+J".to_string(), interact: Some("control+shift+j".to_string()), listening: None };
+        assert!(same.parse().unwrap_err().contains("both"));
+
+        let invalid = Hotkeys { toggle: "Ctrl+Nope".to_string(), interact: None, listening: None };
+        assert!(invalid.parse().unwrap_err().starts_with("Invalid hotkey"));
+
+        let listening_clash = Hotkeys { interact: Some("Ctrl+Alt+J".to_string()), ..Hotkeys::default() };
+        assert!(listening_clash.parse().unwrap_err().contains("interact and the listening"));
+        let listening_off = Hotkeys { listening: None, ..listening_clash };
+        assert!(listening_off.parse().unwrap().listening.is_none());
+
+        assert_eq!(Hotkeys::default().tooltip("Jackson Assistant"), "Jackson Assistant (Ctrl+Shift+J)");
+    }
+}
@@ -0,0 +1,350 @@
+// intents.rs - Match final transcripts against registered intent patterns
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// Patterns the app understands out of the box; the frontend can add more
+const BUILT_IN_INTENTS: &[(&str, &[&str])] = &[
+    ("set_timer", &["set a timer for {duration}", "set a {duration} timer"]),
+    ("weather", &["what's the weather in {city}", "what is the weather in {city}"]),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum PatternPart {
+    Word(String),
+    // Matches one or more words
+    Slot(String),
+}
+
+#[derive(Clone, Debug)]
+struct Pattern {
+    source: String,
+    parts: Vec<PatternPart>,
+}
+
+impl Pattern {
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        for word in source.split_whitespace() {
+            let part = match word.strip_prefix('{').and_then(|w| w.strip_suffix('}')) {
+                Some("") => return Err(format!("Empty slot name in \"{}\"", source)),
+                Some(slot) => {
+                    if parts.contains(&PatternPart::Slot(slot.to_string())) {
+                        return Err(format!("Slot {{{}}} appears twice in \"{}\"", slot, source));
+                    }
+                    // Two slots in a row have no word to split them on
+                    if matches!(parts.last(), Some(PatternPart::Slot(_))) {
+                        return Err(format!("Adjacent slots in \"{}\"", source));
+                    }
+                    PatternPart::Slot(slot.to_string())
+                }
+                None => match normalize_word(word) {
+                    Some(word) => PatternPart::Word(word),
+                    None => continue,
+                },
+            };
+            parts.push(part);
+        }
+
+        if !parts.iter().any(|part| matches!(part, PatternPart::Word(_))) {
+            return Err(format!("Pattern \"{}\" needs at least one word", source));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            parts,
+        })
+    }
+
+    // More literal words means a more specific pattern
+    fn specificity(&self) -> usize {
+        self.parts.iter().filter(|part| matches!(part, PatternPart::Word(_))).count()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IntentMatch {
+    pub intent: String,
+    pub pattern: String,
+    pub slots: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DetectedIntent {
+    pub intent: String,
+    pub pattern: String,
+    pub slots: BTreeMap<String, String>,
+    // Other intents that also matched, most specific first
+    pub alternatives: Vec<IntentMatch>,
+}
+
+// How one registered pattern fares against a test phrase
+#[derive(Clone, Debug, Serialize)]
+pub struct CommandMatchResult {
+    pub pattern: String,
+    pub matched: bool,
+    // Share of the phrase's words the pattern matched literally; 0 if it didn't match
+    pub confidence: f32,
+    // The intent and slots "intent-detected" would carry, for the one pattern
+    // that would fire; empty for every other pattern
+    pub would_execute: String,
+}
+
+pub struct IntentRegistry {
+    intents: Vec<(String, Vec<Pattern>)>,
+}
+
+impl IntentRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self { intents: Vec::new() };
+        for (name, patterns) in BUILT_IN_INTENTS {
+            let patterns: Vec<String> = patterns.iter().map(|pattern| pattern.to_string()).collect();
+            registry
+                .register(name, &patterns)
+                .expect("built-in intent patterns are valid");
+        }
+        registry
+    }
+
+    // Add an intent, replacing any existing one with the same name
+    pub fn register(&mut self, name: &str, patterns: &[String]) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Intent name cannot be empty".to_string());
+        }
+        if patterns.is_empty() {
+            return Err(format!("Intent {} needs at least one pattern", name));
+        }
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Pattern::parse(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.intents.retain(|(existing, _)| existing != name);
+        self.intents.push((name.to_string(), patterns));
+        Ok(())
+    }
+
+    // The most specific matching intent for a transcript, with any other
+    // intents that matched as alternatives
+    pub fn detect(&self, text: &str) -> Option<DetectedIntent> {
+        let words = transcript_words(text);
+
+        // Best pattern per intent; ties go to the pattern registered first
+        let mut matches: Vec<(usize, IntentMatch)> = self
+            .intents
+            .iter()
+            .filter_map(|(name, patterns)| {
+                patterns
+                    .iter()
+                    .filter_map(|pattern| {
+                        let mut slots = BTreeMap::new();
+                        match_parts(&pattern.parts, &words, &mut slots).then(|| {
+                            let intent_match = IntentMatch {
+                                intent: name.clone(),
+                                pattern: pattern.source.clone(),
+                                slots,
+                            };
+                            (pattern.specificity(), intent_match)
+                        })
+                    })
+                    .reduce(|best, next| if next.0 > best.0 { next } else { best })
+            })
+            .collect();
+
+        // Stable, so equally specific intents keep registration order
+        matches.sort_by_key(|(specificity, _)| std::cmp::Reverse(*specificity));
+        let mut matches = matches.into_iter().map(|(_, intent_match)| intent_match);
+        let best = matches.next()?;
+        Some(DetectedIntent {
+            intent: best.intent,
+            pattern: best.pattern,
+            slots: best.slots,
+            alternatives: matches.collect(),
+        })
+    }
+
+    // Dry run: match the phrase against every pattern without emitting
+    // anything, so new patterns can be checked before relying on them
+    pub fn test_match(&self, text: &str) -> Vec<CommandMatchResult> {
+        let words = transcript_words(text);
+        let fired = self.detect(text);
+
+        self.intents
+            .iter()
+            .flat_map(|(name, patterns)| patterns.iter().map(move |pattern| (name, pattern)))
+            .map(|(name, pattern)| {
+                let mut slots = BTreeMap::new();
+                let matched = match_parts(&pattern.parts, &words, &mut slots);
+                let fires = fired
+                    .as_ref()
+                    .is_some_and(|fired| fired.intent == *name && fired.pattern == pattern.source);
+                CommandMatchResult {
+                    pattern: pattern.source.clone(),
+                    matched,
+                    confidence: if matched {
+                        pattern.specificity() as f32 / words.len().max(1) as f32
+                    } else {
+                        0.0
+                    },
+                    would_execute: if fires { format!("{} {:?}", name, slots) } else { String::new() },
+                }
+            })
+            .collect()
+    }
+}
+
+fn transcript_words(text: &str) -> Vec<(String, &str)> {
+    text.split_whitespace()
+        .filter_map(|word| normalize_word(word).map(|normalized| (normalized, trim_punctuation(word))))
+        .collect()
+}
+
+// Match the whole transcript against the pattern, filling in slots. Slots take
+// as few words as possible while still letting the rest of the pattern match.
+fn match_parts(parts: &[PatternPart], words: &[(String, &str)], slots: &mut BTreeMap<String, String>) -> bool {
+    match parts.split_first() {
+        None => words.is_empty(),
+        Some((PatternPart::Word(expected), rest)) => match words.split_first() {
+            Some(((word, _), remaining)) if word == expected => match_parts(rest, remaining, slots),
+            _ => false,
+        },
+        Some((PatternPart::Slot(name), rest)) => {
+            for len in 1..=words.len() {
+                if match_parts(rest, &words[len..], slots) {
+                    let value: Vec<&str> = words[..len].iter().map(|(_, original)| *original).collect();
+                    slots.insert(name.clone(), value.join(" "));
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+// Lowercase and strip surrounding punctuation; "What’s?" becomes "what's"
+fn normalize_word(word: &str) -> Option<String> {
+    let trimmed = trim_punctuation(word);
+    (!trimmed.is_empty()).then(|| trimmed.to_lowercase().replace('\u{2019}', "'"))
+}
+
+fn trim_punctuation(word: &str) -> &str {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '%')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_reports_every_pattern_and_the_one_that_fires() {
+        let registry = IntentRegistry::new();
+        let results = registry.test_match("Set a timer for five minutes");
+
+        assert_eq!(results.len(), 4);
+        let fired: Vec<_> = results.iter().filter(|result| !result.would_execute.is_empty()).collect();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].pattern, "set a timer for {duration}");
+        assert_eq!(fired[0].would_execute, r#"set_timer {"duration": "five minutes"}"#);
+        assert!((fired[0].confidence - 4.0 / 6.0).abs() < 1e-6);
+        assert!(results.iter().filter(|result| result.pattern.contains("weather")).all(|result| !result.matched));
+    }
+
+    fn registry_with(intents: &[(&str, &[&str])]) -> IntentRegistry {
+        let mut registry = IntentRegistry { intents: Vec::new() };
+        for (name, patterns) in intents {
+            let patterns: Vec<String> = patterns.iter().map(|pattern| pattern.to_string()).collect();
+            registry.register(name, &patterns).unwrap();
+        }
+        registry
+    }
+
+    fn slots(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn slots_take_the_original_words_between_literals() {
+        let registry = IntentRegistry::new();
+        let detected = registry.detect("What\u{2019}s the weather in New York City?").unwrap();
+        assert_eq!(detected.intent, "weather");
+        assert_eq!(detected.slots, slots(&[("city", "New York City")]));
+
+        let detected = registry.detect("Set a 10% timer.").unwrap();
+        assert_eq!(detected.pattern, "set a {duration} timer");
+        assert_eq!(detected.slots, slots(&[("duration", "10%")]));
+
+        // A slot needs at least one word, and the whole transcript has to match
+        assert!(registry.detect("set a timer for").is_none());
+        assert!(registry.detect("please set a timer for ten minutes").is_none());
+    }
+
+    #[test]
+    fn the_most_specific_pattern_wins() {
+        let registry = registry_with(&[
+            ("play", &["play {song}"]),
+            ("play_on", &["play {song} on the {device}"]),
+        ]);
+        let detected = registry.detect("play jazz on the kitchen speaker").unwrap();
+        assert_eq!(detected.intent, "play_on");
+        assert_eq!(detected.slots, slots(&[("song", "jazz"), ("device", "kitchen speaker")]));
+
+        // Within one intent too, whatever order the patterns were given in
+        let registry = registry_with(&[("timer", &["set {what}", "set a timer for {duration}"])]);
+        let detected = registry.detect("set a timer for five minutes").unwrap();
+        assert_eq!(detected.pattern, "set a timer for {duration}");
+        assert!(detected.alternatives.is_empty());
+    }
+
+    #[test]
+    fn other_matching_intents_are_alternatives_in_the_payload() {
+        let registry = registry_with(&[
+            ("play", &["play {song}"]),
+            ("play_on", &["play {song} on the {device}"]),
+            ("speaker", &["{words} speaker"]),
+            ("stop", &["stop"]),
+        ]);
+        let detected = registry.detect("play jazz on the kitchen speaker").unwrap();
+        let alternatives: Vec<_> = detected
+            .alternatives
+            .iter()
+            .map(|alternative| (alternative.intent.as_str(), alternative.slots.clone()))
+            .collect();
+        // Equally specific intents keep registration order
+        assert_eq!(
+            alternatives,
+            [
+                ("play", slots(&[("song", "jazz on the kitchen speaker")])),
+                ("speaker", slots(&[("words", "play jazz on the kitchen")])),
+            ]
+        );
+
+        let payload = serde_json::to_value(&detected).unwrap();
+        assert_eq!(payload["intent"], "play_on");
+        assert_eq!(payload["alternatives"][0]["pattern"], "play {song}");
+        assert_eq!(payload["alternatives"][1]["slots"]["words"], "play jazz on the kitchen");
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        let mut registry = IntentRegistry::new();
+        for pattern in ["set a {} timer", "move {item} to {item}", "call {first} {last}", "{anything}", "?!"] {
+            assert!(registry.register("broken", &[pattern.to_string()]).is_err(), "{:?}", pattern);
+        }
+        assert!(registry.register(" ", &["stop".to_string()]).is_err());
+        assert!(registry.register("empty", &[]).is_err());
+
+        // One bad pattern keeps the whole intent out, and a rejected
+        // replacement leaves the existing intent alone
+        assert!(registry.register("stop", &["stop".to_string(), "{a} {b}".to_string()]).is_err());
+        assert!(registry.detect("stop").is_none());
+        assert!(registry.register("weather", &["{city}".to_string()]).is_err());
+        assert_eq!(registry.detect("what is the weather in Paris").unwrap().intent, "weather");
+    }
+
+    #[test]
+    fn registering_a_name_again_replaces_its_patterns() {
+        let mut registry = IntentRegistry::new();
+        registry.register("weather", &["forecast for {city}".to_string()]).unwrap();
+        assert!(registry.detect("what is the weather in Paris").is_none());
+        assert_eq!(registry.detect("forecast for Paris").unwrap().slots, slots(&[("city", "Paris")]));
+    }
+}
@@ -1,16 +1,3756 @@
+// lib.rs - App state, commands and setup. main.rs just calls run(); the
+// integration tests build the same app on Tauri's mock runtime.
+mod agc;
+mod analytics;
+mod audio;
+mod autostart;
+mod bug_report;
+mod commands;
+pub mod config;
+mod context;
+mod corners;
+mod debounce;
+mod echo;
+mod events;
+mod fallback;
+mod gate;
+mod history;
+mod hotkeys;
+mod intents;
+mod metrics;
+mod models;
+mod normalize;
+mod opacity;
+mod outbound;
+mod pipeline;
+mod postprocess;
+mod profanity;
+mod regions;
+mod sapi_adaptation;
+mod sapi_health;
+mod sapi_stats;
+mod screenshot;
+mod session;
+mod shadow;
+mod shutdown;
+mod speech_recognition;
+mod theme;
+mod training;
+mod tray_status;
+mod utterances;
+mod vocabulary;
+mod volume;
+pub mod wake_word;
+mod window_manager;
+
+// Pure audio helpers, exposed for the fuzz targets in fuzz/
+pub use audio::{convert_samples, rms};
+// Exposed for the integration tests, whose mock window can't be resized
+pub use window_manager::resize_target;
+
+use wake_word::WakeWordDetector;
+use audio::{AudioCapture, AudioPlayback, ChannelRouting, Earcon, EarconManager};
+use config::{AppConfig, DisplayMode, MonitorPlacement, WindowAnchor, WindowConfig, WindowMode, WindowPosition, WindowSize};
+use autostart::AutostartError;
+use debounce::{Debounced, Debouncer};
+use echo::{EchoMonitor, EchoStatus};
+use history::TranscriptHistory;
+use hotkeys::Hotkeys;
+use intents::IntentRegistry;
+use metrics::SpeechMetricsTracker;
+use outbound::{Delivery, OutboundQueue};
+use postprocess::PostProcessConfig;
+use tray_status::TrayStatus;
+use session::{CloseReason, SessionHandle, SessionOrchestrator, SessionSink};
+use speech_recognition::{EndpointingConfig, RecognitionResult, SpeechError, TranscriptionResult};
+use utterances::UtteranceAudioStore;
+use window_manager::{ease_out, lerp, ProgrammaticMoves, WindowManager, WorkAreaCache};
+use crossbeam_channel::RecvTimeoutError;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::menu::{CheckMenuItem, MenuBuilder, MenuItem};
+use tauri::tray::{TrayIconBuilder, TrayIconEvent};
+use tauri::webview::PageLoadEvent;
+use tauri::PhysicalPosition;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tauri::{Emitter, Listener, Manager, Runtime, State};
+use std::time::Duration;
+
+// Checks or unchecks the tray's Do Not Disturb item
+type MenuSync = Box<dyn Fn(bool) + Send>;
+// Updates the tray's listening items for a detector state (None without SAPI)
+type ListeningMenuSync = Box<dyn Fn(Option<wake_word::DetectorState>) + Send>;
+
+// What the tray is showing; the tooltip names the toggle hotkey while one is registered
+struct TrayDisplay {
+    status: TrayStatus,
+    hotkeys: Option<Hotkeys>,
+    // The microphone listening was last started with
+    microphone: Option<String>,
+}
+
+impl TrayDisplay {
+    fn tooltip(&self) -> String {
+        let title = self
+            .hotkeys
+            .as_ref()
+            .map_or_else(|| tray_status::APP_NAME.to_string(), |hotkeys| hotkeys.tooltip(tray_status::APP_NAME));
+        tray_status::tooltip(self.status, &title, self.microphone.as_deref())
+    }
+}
+
+struct AppState {
+    wake_word_detector: Arc<Mutex<Option<WakeWordDetector>>>,
+    config: Arc<Mutex<AppConfig>>,
+    resize_debounce: Arc<Mutex<Debouncer<(f64, f64)>>>,
+    speech_metrics: Arc<Mutex<SpeechMetricsTracker>>,
+    speech_session_active: Arc<AtomicBool>,
+    postprocess_config: Arc<Mutex<PostProcessConfig>>,
+    do_not_disturb: Arc<AtomicBool>,
+    dnd_menu_sync: Arc<Mutex<Option<MenuSync>>>,
+    on_top_menu_sync: Arc<Mutex<Option<MenuSync>>>,
+    pin_menu_sync: Arc<Mutex<Option<MenuSync>>>,
+    listening_menu_sync: Arc<Mutex<Option<ListeningMenuSync>>>,
+    mic_menu_sync: Arc<Mutex<Option<MenuSync>>>,
+    autostart_menu_sync: Arc<Mutex<Option<MenuSync>>>,
+    // Started by the Run entry rather than by the user
+    launched_at_login: bool,
+    // Set once the shutdown sequence has started
+    shutting_down: Arc<AtomicBool>,
+    // Set by pause_microphone; a new capture starts unpaused
+    microphone_paused: Arc<AtomicBool>,
+    // Last state the detector reported; None without SAPI
+    detector_state: Arc<Mutex<Option<wake_word::DetectorState>>>,
+    tray_display: Arc<Mutex<TrayDisplay>>,
+    tray_status_debounce: Arc<Mutex<Debouncer<TrayStatus>>>,
+    work_area_cache: WorkAreaCache,
+    programmatic_moves: ProgrammaticMoves,
+    // Times monitor information couldn't be read while placing the window
+    monitor_info_failures: Arc<AtomicU64>,
+    // The display picked with set_display was missing the last time it was looked for
+    display_missing: Arc<AtomicBool>,
+    transcripts: Arc<Mutex<TranscriptHistory>>,
+    last_transcription: Arc<Mutex<Option<TranscriptionResult>>>,
+    // Training phrases recorded this run, and whether one is being recorded
+    training_phrases: Arc<Mutex<Vec<events::TrainingPhraseRecordedPayload>>>,
+    training_recording: Arc<AtomicBool>,
+    pending_resize_event: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    resize_animation: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    current_window_mode: Arc<Mutex<WindowMode>>,
+    // What to go back to when overlay mode ends; Some while it's on
+    overlay: Arc<Mutex<Option<OverlayRestore>>>,
+    // The session opacity the window is at or fading towards
+    window_opacity: Arc<Mutex<f64>>,
+    // How far the window is faded in, 0 (invisible) to 1 (at window_opacity)
+    window_fade_level: Arc<Mutex<f64>>,
+    window_fade: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    hover_watcher: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    // While any are set, only these parts of the window take mouse input
+    interactive_regions: Arc<Mutex<regions::InteractiveRegions>>,
+    region_watcher: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    // Tauri can't be asked whether the window is click-through, so it's tracked here
+    ignore_cursor_events: Arc<AtomicBool>,
+    // A pinned window is never hidden for losing focus or going idle, nor
+    // moved by resizes or wake words
+    window_pinned: Arc<AtomicBool>,
+    auto_hide_timer: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    // Last transcript, resize request or frontend heartbeat
+    last_activity: Arc<Mutex<std::time::Instant>>,
+    // Opened the first time something is played
+    playback: Arc<Mutex<Option<AudioPlayback>>>,
+    // Hears the playback through the output tap and the microphone through the detector
+    echo: EchoMonitor,
+    earcons: Arc<Mutex<EarconManager>>,
+    session: SessionHandle,
+    intents: Arc<Mutex<IntentRegistry>>,
+    session_analytics: Arc<Mutex<analytics::SessionAnalyticsTracker>>,
+    utterance_audio: Arc<Mutex<UtteranceAudioStore>>,
+    outbound: Arc<Mutex<OutboundQueue>>,
+    outbound_ready: Arc<tokio::sync::Notify>,
+    // Held while the settings window is looked for and created
+    settings_window_opening: Arc<Mutex<()>>,
+}
+
+// Apply a change to the settings and persist them. Every window hears about
+// it as "settings-changed", so the settings window and the overlay agree.
+fn update_config<R: Runtime>(app: &tauri::AppHandle<R>, change: impl FnOnce(&mut AppConfig)) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut config = state.config.lock().unwrap();
+    change(&mut config);
+    config::save(app, &config).map_err(|e| format!("Failed to save settings: {}", e))?;
+    let settings = config.clone();
+    drop(config);
+    send_event(app, "settings-changed", settings, Delivery::Latest);
+    Ok(())
+}
+
+// Queue an event for the frontend. The flusher task emits it, so a slow
+// webview doesn't hold up the caller.
+fn send_event<R: Runtime>(app: &tauri::AppHandle<R>, event: &str, payload: impl serde::Serialize, delivery: Delivery) {
+    let state = app.state::<AppState>();
+    let payload = serde_json::to_value(payload).unwrap_or_default();
+    state.outbound.lock().unwrap().push(event, payload, delivery);
+    state.outbound_ready.notify_one();
+}
+
+// Current session metrics with the outbound event counters
+fn speech_metrics(state: &AppState) -> metrics::SpeechMetrics {
+    let (coalesced, dropped) = {
+        let outbound = state.outbound.lock().unwrap();
+        (outbound.coalesced(), outbound.dropped())
+    };
+    state.speech_metrics.lock().unwrap().snapshot().with_event_counts(coalesced, dropped)
+}
+
+// Carries out the session orchestrator's decisions against the running app:
+// backend start/stop, metrics, history, and the frontend events
+struct AppSessionSink<R: Runtime> {
+    app: tauri::AppHandle<R>,
+}
+
+impl<R: Runtime> SessionSink for AppSessionSink<R> {
+    // Start the backend and emit metrics updates every 5 seconds until the
+    // session ends
+    fn session_opened(&mut self, session_id: u64) {
+        let app = &self.app;
+        let state = app.state::<AppState>();
+        let metrics_id = state.speech_metrics.lock().unwrap().reset();
+        state.session_analytics.lock().unwrap().start(session_id, history::now_ms());
+        state.speech_session_active.store(true, Ordering::SeqCst);
+        refresh_tray_status(app);
+        send_event(app, "speech-session-started", events::SpeechSessionStartedPayload { session_id }, Delivery::Reliable);
+        
+        if let Some(detector) = state.wake_word_detector.lock().unwrap().as_ref() {
+            if let Err(e) = detector.start_speech_recognition() {
+                // Backends report a SpeechError where they can; anything else is unexpected
+                let error = match e.downcast::<SpeechError>() {
+                    Ok(error) => error,
+                    Err(e) => SpeechError::Internal(format!("Failed to start {} recognition: {}", detector.backend_name(), e)),
+                };
+                eprintln!("❌ {}", error);
+                send_event(app, "speech-error", error.payload(), Delivery::Reliable);
+                play_earcon(app, Earcon::Error);
+            }
+        }
+        
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                let state = app.state::<AppState>();
+                if !state.speech_session_active.load(Ordering::SeqCst) {
+                    break;
+                }
+                // A newer session has its own emitter
+                if state.speech_metrics.lock().unwrap().session_id() != metrics_id {
+                    break;
+                }
+                send_event(&app, "speech-metrics-update", speech_metrics(&state), Delivery::Latest);
+            }
+        });
+    }
+    
+    fn result(&mut self, session_id: Option<u64>, result: RecognitionResult) {
+        let app = &self.app;
+        let state = app.state::<AppState>();
+        let audio = session_id.and_then(|_| {
+            state.wake_word_detector.lock().unwrap().as_ref().and_then(|detector| detector.take_utterance_audio())
+        });
+        let (chain, options) = {
+            let config = state.config.lock().unwrap();
+            (config.runnable_backend_chain(), backend_options(app, &config))
+        };
+        
+        match audio {
+            // Hold the live result until the chain has had the audio, so only
+            // one final result goes out for the utterance
+            Some(audio) if !chain.is_empty() => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    let outcome = tauri::async_runtime::spawn_blocking(move || {
+                        let outcome = fallback::run(&chain, &audio, utterances::STORED_SAMPLE_RATE, &|name: &str| speech_recognition::create_backend(name, &options));
+                        (outcome, audio)
+                    })
+                    .await;
+                    match outcome {
+                        Ok((Ok(chained), audio)) => deliver_result(&app, session_id, chained, Some(audio)),
+                        Ok((Err(e), audio)) => {
+                            eprintln!("⚠️ Backend chain failed ({}), keeping the live result", e);
+                            deliver_result(&app, session_id, result, Some(audio));
+                        }
+                        Err(e) => {
+                            eprintln!("⚠️ Backend chain task failed ({}), keeping the live result", e);
+                            deliver_result(&app, session_id, result, None);
+                        }
+                    }
+                });
+            }
+            audio => deliver_result(app, session_id, result, audio),
+        }
+    }
+    
+    fn session_closed(&mut self, session_id: u64, reason: CloseReason) {
+        let app = &self.app;
+        let state = app.state::<AppState>();
+        state.speech_session_active.store(false, Ordering::SeqCst);
+        refresh_tray_status(app);
+        let mut audio = Duration::ZERO;
+        if let Some(detector) = state.wake_word_detector.lock().unwrap().as_ref() {
+            detector.stop_speech_recognition();
+            audio = detector.session_audio();
+        }
+        if reason == CloseReason::Timeout {
+            play_earcon(app, Earcon::Timeout);
+        }
+        apply_session_opacity(app, false);
+        
+        // Send the final numbers for the session that just ended
+        send_event(app, "speech-metrics-update", speech_metrics(&state), Delivery::Latest);
+        let payload = events::SpeechSessionEndedPayload { session_id, reason };
+        send_event(app, "speech-session-ended", payload, Delivery::Reliable);
+        let summary = state
+            .session_analytics
+            .lock()
+            .unwrap()
+            .finish(session_id, history::now_ms(), audio.as_millis() as u64);
+        if let Some(summary) = summary {
+            send_event(app, "session-ended", summary, Delivery::Reliable);
+        }
+        
+        // Sessions that end on their own take the window with them
+        if matches!(reason, CloseReason::StopPhrase | CloseReason::Timeout) {
+            hide_main_window(app);
+        }
+    }
+}
+
+// Post-process a final result, record it in the history (with its audio, if
+// any) and send it to the frontend
+fn deliver_result<R: Runtime>(app: &tauri::AppHandle<R>, session_id: Option<u64>, mut result: RecognitionResult, audio: Option<Vec<i16>>) {
+    touch_activity(app);
+    let state = app.state::<AppState>();
+    let postprocess_config = state.postprocess_config.lock().unwrap().clone();
+    let processed = postprocess::process(&result.raw_text, &postprocess_config);
+    result.text = processed.text;
+    result.entities = processed.entities;
+    result.alternatives = postprocess::process_alternatives(&result.text, &result.alternatives, &postprocess_config);
+    
+    if let Some(session_id) = session_id {
+        state.speech_metrics.lock().unwrap().record_phrase(&result.text);
+        state.session_analytics.lock().unwrap().record_phrase(session_id, result.confidence);
+        // Shown some other way (e.g. the hotkey), the window is still compact
+        let compact = *state.current_window_mode.lock().unwrap() == WindowMode::Compact;
+        if compact {
+            apply_window_mode(app, WindowMode::Expanded).unwrap_or_else(|e| eprintln!("⚠️ {}", e));
+        }
+    }
+    let store_raw = state.config.lock().unwrap().store_raw_transcripts;
+    let raw_text = store_raw.then(|| result.raw_text.clone());
+    let mut entry = state.transcripts.lock().unwrap().push(
+        session_id.unwrap_or(0),
+        result.text.clone(),
+        raw_text,
+        result.confidence,
+        &result.source,
+    );
+    if let Some(session_id) = session_id {
+        if let Some(audio) = audio {
+            let (store, save) = {
+                let config = state.config.lock().unwrap();
+                (config.store_utterance_audio, config.save_utterance_audio)
+            };
+            if save {
+                entry.audio_path = save_utterance_clip(app, session_id, entry.id, &audio);
+                if let Some(path) = &entry.audio_path {
+                    state.transcripts.lock().unwrap().set_audio_path(entry.id, path.clone());
+                }
+            }
+            if store {
+                state.utterance_audio.lock().unwrap().insert(entry.id, audio);
+            }
+        }
+    }
+    send_event(app, "transcript-appended", entry, Delivery::Reliable);
+    let intent = state.intents.lock().unwrap().detect(&result.text);
+    *state.last_transcription.lock().unwrap() = Some(result.transcription());
+    // The raw text is unfiltered, so it only goes out if it's kept anyway
+    if !store_raw {
+        result.raw_text.clear();
+    }
+    send_event(app, "continuous-speech", result, Delivery::Reliable);
+    if session_id.is_some() {
+        play_earcon(app, Earcon::Confirm);
+    }
+    
+    if let Some(intent) = intent {
+        println!("🧭 Intent detected: {}", intent.intent);
+        if let Some(session_id) = session_id {
+            state.session_analytics.lock().unwrap().record_command(session_id);
+        }
+        send_event(app, "intent-detected", intent, Delivery::Reliable);
+    }
+}
+
+fn models_dir<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("models"))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+fn backend_options<R: Runtime>(app: &tauri::AppHandle<R>, config: &AppConfig) -> speech_recognition::BackendOptions {
+    let whisper_model = config
+        .whisper_model
+        .as_deref()
+        .and_then(|name| models_dir(app).ok().map(|dir| dir.join(models::file_name(name))));
+    speech_recognition::BackendOptions { whisper_model }
+}
+
+// Write an utterance's WAV clip and apply the retention policy. Failures are
+// reported as "utterance-audio-warning" instead of interrupting recognition.
+fn save_utterance_clip<R: Runtime>(app: &tauri::AppHandle<R>, session_id: u64, utterance_id: u64, audio: &[i16]) -> Option<String> {
+    let warn = |message: String| {
+        eprintln!("⚠️ {}", message);
+        let payload = events::UtteranceAudioWarningPayload { utterance_id, message };
+        send_event(app, "utterance-audio-warning", payload, Delivery::Reliable);
+    };
+    let dir = match app.path().app_data_dir() {
+        Ok(dir) => dir.join("utterances"),
+        Err(e) => {
+            warn(format!("Failed to resolve app data directory: {}", e));
+            return None;
+        }
+    };
+    
+    let path = dir.join(session_id.to_string()).join(format!("{}.wav", utterance_id));
+    if let Err(e) = utterances::write_wav(&path, audio, utterances::STORED_SAMPLE_RATE) {
+        warn(format!("Failed to save utterance audio to {}: {}", path.display(), e));
+        return None;
+    }
+    let max_bytes = app.state::<AppState>().config.lock().unwrap().max_utterance_audio_bytes;
+    match utterances::enforce_retention(&dir, max_bytes) {
+        Ok(0) => {}
+        Ok(deleted) => println!("🧹 Deleted {} old utterance clips", deleted),
+        Err(e) => warn(format!("Failed to apply utterance audio retention: {}", e)),
+    }
+    Some(path.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+async fn start_wake_word_detection<R: Runtime>(
+    app: tauri::AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let ready_receiver = {
+        let detector_guard = state.wake_word_detector.lock().unwrap();
+        let detector = detector_guard
+            .as_ref()
+            .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+    
+        // Set the app handle so the detector can emit events
+        detector.set_app_handle(app.clone());
+    
+        let app_clone = app.clone();
+    
+        detector.start_listening(move |detection| {
+            // Wake word detected!
+            println!("🎯 Wake word detected with index: {}!", detection.keyword_index);
+            println!("🎉 HELLO WORLD! WAKE WORD DETECTED! 🎉");
+        
+            play_earcon(&app_clone, Earcon::Wake);
+            
+            if show_main_window(&app_clone) {
+                apply_session_opacity(&app_clone, true);
+            }
+            // The window shows compact and grows to full size for the session
+            apply_window_mode(&app_clone, WindowMode::Expanded).unwrap_or_else(|e| eprintln!("⚠️ {}", e));
+        
+            // Emit an event to the frontend with the keyword index
+            let payload = events::WakeWordDetectedPayload {
+                keyword_index: detection.keyword_index,
+                phrase: detection.phrase,
+                confidence: detection.confidence,
+                timestamp_ms: history::now_ms(),
+                audio_energy_rms: detection.audio_energy_rms,
+            };
+            app_clone.emit("wake-word-detected", payload).unwrap();
+        })
+    };
+    
+    // Only report success once SAPI is set up and the recognition loop is running
+    let ready = tauri::async_runtime::spawn_blocking(move || {
+        ready_receiver.recv_timeout(Duration::from_secs(5))
+    })
+    .await
+    .map_err(|e| format!("Failed to wait for wake word detector: {}", e))?;
+    
+    match ready {
+        Ok(()) => {
+            app.emit("wake-word-listening-ready", events::ListeningReadyPayload {}).unwrap();
+            Ok(())
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            let error = SpeechError::Timeout("wake word detector did not start within 5 seconds".to_string());
+            let _ = app.emit("speech-error", error.payload());
+            Err(error.message())
+        }
+        Err(RecvTimeoutError::Disconnected) => {
+            Err("Wake word detector failed to start".to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn stop_wake_word_detection(state: State<AppState>) -> Result<(), String> {
+    state.session.close();
+    let detector_guard = state.wake_word_detector.lock().unwrap();
+    if let Some(detector) = detector_guard.as_ref() {
+        detector.stop_listening();
+        Ok(())
+    } else {
+        Err("Wake word detector not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+fn get_wake_word_state(state: State<AppState>) -> Option<wake_word::DetectorState> {
+    state.wake_word_detector.lock().unwrap().as_ref().map(|detector| detector.state())
+}
+
+// Final transcripts from the frontend's Web Speech recognizer, so backend
+// bookkeeping sees them the same way as results from the SAPI path. The
+// recognizer's other hypotheses (maxAlternatives) can come along.
+#[tauri::command]
+fn report_transcript(text: String, confidence: Option<f32>, alternatives: Option<Vec<String>>, state: State<AppState>) {
+    let alternatives = speech_recognition::distinct_alternatives(&text, alternatives.unwrap_or_default());
+    state.session.push_result(RecognitionResult {
+        text: text.clone(),
+        raw_text: text,
+        constraint_index: None,
+        confidence,
+        source: "webspeech".to_string(),
+        entities: Vec::new(),
+        fallbacks: 0,
+        alternatives,
+    });
+}
+
+// The last final result with its alternatives, for a correction bar
+#[tauri::command]
+fn get_recognized_alternatives(state: State<AppState>) -> Option<TranscriptionResult> {
+    state.last_transcription.lock().unwrap().clone()
+}
+
+#[tauri::command]
+fn get_transcripts(
+    limit: Option<usize>,
+    since_timestamp: Option<u64>,
+    state: State<AppState>,
+) -> Vec<history::TranscriptEntry> {
+    state.transcripts.lock().unwrap().query(limit, since_timestamp)
+}
+
+#[tauri::command]
+fn clear_transcripts(state: State<AppState>) {
+    state.transcripts.lock().unwrap().clear();
+    state.utterance_audio.lock().unwrap().clear();
+}
+
+// Opt in to keeping the last couple of minutes of utterance audio in memory
+// for playback. Turning it off drops what was stored.
+#[tauri::command]
+fn set_store_utterance_audio<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    if !enabled {
+        app.state::<AppState>().utterance_audio.lock().unwrap().clear();
+    }
+    update_config(&app, |settings| settings.store_utterance_audio = enabled)?;
+    sync_utterance_recording(&app);
+    Ok(())
+}
+
+// Opt in to saving every utterance as a WAV clip under the app data folder
+#[tauri::command]
+fn set_save_utterance_audio<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    update_config(&app, |settings| settings.save_utterance_audio = enabled)?;
+    sync_utterance_recording(&app);
+    println!("💾 Saving utterance audio {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// The detector records session audio if either utterance audio option is on,
+// or a backend chain needs it
+fn sync_utterance_recording<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let state = app.state::<AppState>();
+    let record = state.config.lock().unwrap().records_utterance_audio();
+    if let Some(detector) = state.wake_word_detector.lock().unwrap().as_ref() {
+        detector.set_record_utterance_audio(record);
+    };
+}
+
+#[tauri::command]
+fn get_backend_chain(state: State<AppState>) -> Vec<fallback::ChainStep> {
+    state.config.lock().unwrap().backend_chain.clone()
+}
+
+// Replace the backend fallback chain; an empty chain turns it off
+#[tauri::command]
+fn set_backend_chain<R: Runtime>(app: tauri::AppHandle<R>, chain: Vec<fallback::ChainStep>) -> Result<(), String> {
+    fallback::validate(&chain, speech_recognition::BACKENDS, speech_recognition::TRANSCRIBING_BACKENDS)?;
+    let steps = chain.len();
+    update_config(&app, |settings| settings.backend_chain = chain)?;
+    sync_utterance_recording(&app);
+    println!("🔗 Backend chain set ({} steps)", steps);
+    Ok(())
+}
+
+// Path of the saved WAV clip for a transcript entry
+#[tauri::command]
+fn get_utterance_audio(id: u64, state: State<AppState>) -> Result<String, String> {
+    let path = state
+        .transcripts
+        .lock()
+        .unwrap()
+        .get(id)
+        .ok_or_else(|| format!("No transcript with id {}", id))?
+        .audio_path
+        .clone()
+        .ok_or_else(|| format!("No audio was saved for utterance {}", id))?;
+    if !std::path::Path::new(&path).exists() {
+        return Err(format!("Audio for utterance {} was deleted by the retention policy", id));
+    }
+    Ok(path)
+}
+
+// Play an utterance from memory if it's still stored, otherwise its saved clip
+#[tauri::command]
+fn play_utterance_audio<R: Runtime>(app: tauri::AppHandle<R>, id: u64, state: State<AppState>) -> Result<(), String> {
+    let stored = state.utterance_audio.lock().unwrap().get(id).map(<[i16]>::to_vec);
+    if let Some(samples) = stored {
+        return with_playback(&app, |playback| {
+            playback.play_samples(samples, utterances::STORED_SAMPLE_RATE);
+            Ok(())
+        });
+    }
+    let path = get_utterance_audio(id, state)?;
+    with_playback(&app, |playback| playback.play_file(std::path::Path::new(&path)))
+}
+
+// Turn earcons on or off and optionally set their volume (0.0 to 1.0)
+#[tauri::command]
+fn set_earcons<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool, volume: Option<f32>) -> Result<(), String> {
+    if let Some(volume) = volume {
+        if !(0.0..=1.0).contains(&volume) {
+            return Err("Earcon volume must be between 0 and 1".to_string());
+        }
+        app.state::<AppState>().earcons.lock().unwrap().set_volume(volume);
+    }
+    update_config(&app, |settings| {
+        settings.earcons_enabled = enabled;
+        if let Some(volume) = volume {
+            settings.earcon_volume = volume;
+        }
+    })?;
+    println!("🔔 Earcons {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// Play an earcon by name ("wake", "confirm", "error", "timeout") even if
+// earcons are off, so settings can preview them
+#[tauri::command]
+fn test_earcon<R: Runtime>(app: tauri::AppHandle<R>, name: String) -> Result<(), String> {
+    let earcon = Earcon::from_name(&name).ok_or_else(|| format!("Unknown earcon: {}", name))?;
+    let earcons = Arc::clone(&app.state::<AppState>().earcons);
+    with_playback(&app, |playback| {
+        earcons.lock().unwrap().play(playback, earcon);
+        Ok(())
+    })
+}
+
+// Run `play` on the output device, opening it first if needed
+fn with_playback<R: Runtime>(app: &tauri::AppHandle<R>, play: impl FnOnce(&AudioPlayback) -> anyhow::Result<()>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut playback = state.playback.lock().unwrap();
+    if playback.is_none() {
+        let opened = AudioPlayback::new().map_err(|e| format!("Failed to open audio output: {}", e))?;
+        let echo = state.echo.clone();
+        opened.on_output(move |samples, sample_rate| echo.push_playback(samples, sample_rate));
+        *playback = Some(opened);
+    }
+    let result = playback.as_ref().map_or(Ok(()), play);
+    result.map_err(|e| format!("Failed to play audio: {}", e))
+}
+
+// Beep if earcons are on; failing to play one is only logged
+fn play_earcon<R: Runtime>(app: &tauri::AppHandle<R>, earcon: Earcon) {
+    let state = app.state::<AppState>();
+    if !state.config.lock().unwrap().earcons_enabled {
+        return;
+    }
+    let earcons = Arc::clone(&state.earcons);
+    let played = with_playback(app, |playback| {
+        earcons.lock().unwrap().play(playback, earcon);
+        Ok(())
+    });
+    if let Err(e) = played {
+        eprintln!("⚠️ {}", e);
+    }
+}
+
+#[tauri::command]
+fn get_speech_metrics(state: State<AppState>) -> metrics::SpeechMetrics {
+    speech_metrics(&state)
+}
+
+// Summaries of the last `n` sessions, newest first
+#[tauri::command]
+fn get_session_history(n: usize, state: State<AppState>) -> Vec<analytics::SessionAnalytics> {
+    state.session_analytics.lock().unwrap().recent(n)
+}
+
+#[tauri::command]
+fn clear_session_history(state: State<AppState>) {
+    state.session_analytics.lock().unwrap().clear();
+}
+
+#[tauri::command]
+fn set_session_history_size<R: Runtime>(app: tauri::AppHandle<R>, size: usize) -> Result<(), String> {
+    app.state::<AppState>().session_analytics.lock().unwrap().set_capacity(size);
+    update_config(&app, |settings| settings.session_history_size = size)
+}
+
+// Seconds without recognized speech before a session closes; 0 is never
+#[tauri::command]
+fn get_speech_timeout(state: State<AppState>) -> u64 {
+    state.config.lock().unwrap().speech_timeout_secs
+}
+
+#[tauri::command]
+fn set_speech_timeout<R: Runtime>(app: tauri::AppHandle<R>, seconds: u64) -> Result<(), String> {
+    if seconds > MAX_SPEECH_TIMEOUT_SECS {
+        return Err(format!("The speech timeout can be at most {} seconds (0 for unlimited)", MAX_SPEECH_TIMEOUT_SECS));
+    }
+    update_config(&app, |settings| settings.speech_timeout_secs = seconds)?;
+    app.state::<AppState>().session.set_timeout(session::timeout_from_secs(seconds));
+    println!("⏱️ Speech timeout set to {}", if seconds == 0 { "unlimited".to_string() } else { format!("{}s", seconds) });
+    send_event(&app, "speech-timeout-updated", events::SpeechTimeoutUpdatedPayload { seconds }, Delivery::Reliable);
+    Ok(())
+}
+
+const MAX_SPEECH_TIMEOUT_SECS: u64 = 3600;
+
+#[tauri::command]
+fn set_pause_threshold(threshold_ms: u64, state: State<AppState>) {
+    state.speech_metrics.lock().unwrap().set_pause_threshold(Duration::from_millis(threshold_ms));
+}
+
+// Whether SAPI is installed and a recognizer can be created
+#[tauri::command]
+fn check_sapi_health() -> sapi_health::SapiHealthReport {
+    sapi_health::check()
+}
+
+// What SAPI recognized since the last reset, for diagnostics
+#[tauri::command]
+fn get_sapi_stats(state: State<AppState>) -> Result<sapi_stats::SapiStats, String> {
+    let detector_guard = state.wake_word_detector.lock().unwrap();
+    let detector = detector_guard
+        .as_ref()
+        .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+    Ok(detector.sapi_stats())
+}
+
+#[tauri::command]
+fn reset_sapi_stats<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    with_detector(&app, |detector| {
+        detector.reset_sapi_stats();
+        Ok(())
+    })
+}
+
+// Where to point people whose Windows has no training wizard
+const SPEECH_TRAINING_HELP_URL: &str = "https://support.microsoft.com/windows/use-voice-recognition-in-windows-83ff75bd-63eb-0b6c-18d4-6fae94050571";
+
+// Open the Windows speech training wizard, which improves SAPI's accuracy for
+// the user's voice. "speech-training-complete" is emitted once it's closed.
+#[tauri::command]
+fn open_speech_training<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+    let path = PathBuf::from(system_root).join(r"Speech\Common\sapisvr.exe");
+    if !path.exists() {
+        return Err(format!(
+            "The speech training wizard isn't installed ({} not found). See {}",
+            path.display(),
+            SPEECH_TRAINING_HELP_URL
+        ));
+    }
+    let mut child = std::process::Command::new(&path)
+        .arg("-SpeechUX")
+        .spawn()
+        .map_err(|e| format!("Failed to start speech training: {}. See {}", e, SPEECH_TRAINING_HELP_URL))?;
+    println!("🎓 Speech training started");
+    send_event(&app, "speech-training-started", events::SpeechTrainingStartedPayload {}, Delivery::Reliable);
+    
+    tauri::async_runtime::spawn_blocking(move || {
+        let exit_code = child.wait().ok().and_then(|status| status.code());
+        println!("🎓 Speech training finished");
+        send_event(&app, "speech-training-complete", events::SpeechTrainingCompletePayload { exit_code }, Delivery::Reliable);
+    });
+    Ok(())
+}
+
+// Record the user reading `prompt` out for speech training. The prompt goes out
+// as "training-phrase-prompt", recording starts at the first speech and ends
+// after the endpointing silence, and the WAV is saved to `output_path` (or the
+// training folder in app data).
+#[tauri::command]
+async fn record_training_phrase<R: Runtime>(
+    app: tauri::AppHandle<R>,
+    prompt: String,
+    output_path: Option<String>,
+) -> Result<events::TrainingPhraseRecordedPayload, String> {
+    if prompt.trim().is_empty() {
+        return Err("Training prompt cannot be empty".to_string());
+    }
+    let path = match output_path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let dir = app.path().app_data_dir().map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+            let stamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis())
+                .unwrap_or_default();
+            dir.join("training").join(format!("{}.wav", stamp))
+        }
+    };
+    
+    let state = app.state::<AppState>();
+    if state.training_recording.swap(true, Ordering::SeqCst) {
+        return Err("A training phrase is already being recorded".to_string());
+    }
+    let (threshold, endpointing) = {
+        let config = state.config.lock().unwrap();
+        (config.min_wake_energy_rms.max(training::TRAINING_SPEECH_RMS), config.endpointing.clone())
+    };
+    
+    println!("🎓 Recording training phrase: {}", prompt);
+    let payload = events::TrainingPhrasePromptPayload { prompt: prompt.clone() };
+    send_event(&app, "training-phrase-prompt", payload, Delivery::Reliable);
+    let recording = tauri::async_runtime::spawn_blocking(move || -> anyhow::Result<Option<(Vec<i16>, u32)>> {
+        // Captured separately from the wake word detector so nothing is recognized
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let mut capture = AudioCapture::new();
+        capture.start_capture_with_sample_rate(move |frame, sample_rate| {
+            let _ = sender.send((frame, sample_rate));
+        })?;
+        
+        let mut recorder = training::PhraseRecorder::new(threshold, &endpointing);
+        let result = loop {
+            match receiver.recv_timeout(training::NO_SPEECH_TIMEOUT) {
+                Ok((frame, sample_rate)) => match recorder.push(&frame, sample_rate) {
+                    training::RecorderState::Finished => break Ok(Some(recorder.into_recording())),
+                    training::RecorderState::NoSpeech => break Ok(None),
+                    _ => {}
+                },
+                Err(_) => break Err(anyhow::anyhow!("The microphone stopped delivering audio")),
+            }
+        };
+        capture.stop_capture();
+        result
+    })
+    .await;
+    app.state::<AppState>().training_recording.store(false, Ordering::SeqCst);
+    
+    let (samples, sample_rate) = recording
+        .map_err(|e| format!("Training recording task failed: {}", e))?
+        .map_err(|e| format!("Failed to capture audio: {}", e))?
+        .ok_or_else(|| "No speech was heard, try reading the phrase again".to_string())?;
+    utterances::write_wav(&path, &samples, sample_rate)
+        .map_err(|e| format!("Failed to save training phrase to {}: {}", path.display(), e))?;
+    
+    let payload = events::TrainingPhraseRecordedPayload {
+        prompt,
+        path: path.to_string_lossy().into_owned(),
+        duration_ms: samples.len() as u64 * 1000 / sample_rate as u64,
+    };
+    println!("🎓 Training phrase saved to {}", payload.path);
+    app.state::<AppState>().training_phrases.lock().unwrap().push(payload.clone());
+    send_event(&app, "training-phrase-recorded", payload.clone(), Delivery::Reliable);
+    Ok(payload)
+}
+
+// Training phrases recorded since the app started, oldest first
+#[tauri::command]
+fn get_training_phrases(state: State<AppState>) -> Vec<events::TrainingPhraseRecordedPayload> {
+    state.training_phrases.lock().unwrap().clone()
+}
+
+// Adapt the SAPI speech profile to recorded training phrases (by path). They
+// must be this run's recordings and still on disk; each is played through
+// SAPI in training mode against its prompt.
+#[tauri::command]
+async fn submit_training_phrases<R: Runtime>(app: tauri::AppHandle<R>, paths: Vec<String>) -> Result<training::TrainingSubmission, String> {
+    let recorded = app.state::<AppState>().training_phrases.lock().unwrap().clone();
+    let recorded_paths: Vec<String> = recorded.iter().map(|phrase| phrase.path.clone()).collect();
+    let selected = training::select_submitted(&paths, &recorded_paths)?;
+    if let Some(missing) = paths.iter().find(|path| !Path::new(path.as_str()).is_file()) {
+        return Err(format!("Training phrase {} no longer exists", missing));
+    }
+    
+    let total_duration_ms = selected.iter().map(|&i| recorded[i].duration_ms).sum();
+    let phrases: Vec<_> = selected.iter().map(|&i| recorded[i].clone()).collect();
+    println!("🎓 Training SAPI on {} phrases ({} ms)", phrases.len(), total_duration_ms);
+    let trained = tauri::async_runtime::spawn_blocking(move || {
+        let phrases: Vec<_> = phrases
+            .iter()
+            .map(|phrase| sapi_adaptation::RecordedPhrase { prompt: &phrase.prompt, path: &phrase.path })
+            .collect();
+        sapi_adaptation::adapt_to_recordings(&phrases)
+    })
+    .await
+    .map_err(|e| format!("Training task failed: {}", e))?
+    .map_err(|e| format!("Failed to train SAPI: {}", e))?;
+    
+    println!("🎓 SAPI recognized {} of {} training phrases", trained, selected.len());
+    Ok(training::submission(selected.len(), trained, total_duration_ms))
+}
+
+#[tauri::command]
+fn set_recognition_backend<R: Runtime>(app: tauri::AppHandle<R>, name: String) -> Result<(), String> {
+    let options = backend_options(&app, &app.state::<AppState>().config.lock().unwrap());
+    let backend = speech_recognition::create_backend(&name, &options).map_err(|e| e.to_string())?;
+    
+    // Don't swap the engine out from under a running session
+    app.state::<AppState>().session.close();
+    {
+        let state = app.state::<AppState>();
+        let detector_guard = state.wake_word_detector.lock().unwrap();
+        let detector = detector_guard
+            .as_ref()
+            .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+        detector.set_backend(backend);
+        detector.set_endpointing(&state.config.lock().unwrap().endpointing);
+    }
+    
+    update_config(&app, |config| config.recognition_backend = name)
+}
+
+// Dock the window at `anchor`, `margin` pixels from the edges (the current
+// margin if not given). A visible window moves right away.
+#[tauri::command]
+fn set_window_anchor<R: Runtime>(app: tauri::AppHandle<R>, anchor: WindowAnchor, margin: Option<u32>) -> Result<(), String> {
+    update_config(&app, |settings| {
+        settings.window_anchor = anchor;
+        settings.window_position = None;
+        if let Some(margin) = margin {
+            settings.window.margin = margin;
+        }
+    })?;
+    if let Some(manager) = window_manager(&app).filter(|manager| manager.is_visible()) {
+        if let Some(size) = manager.size() {
+            let position = manager.anchored_position(size)?;
+            manager.move_to(position, size).map_err(|e| e.to_string())?;
+            emit_window_geometry(manager.window());
+        }
+    }
+    println!("⚓ Window anchored to {:?}", anchor);
+    Ok(())
+}
+
+// Move the window back on screen if less than half of it is on a monitor,
+// e.g. after a resolution change or a display was unplugged
+#[tauri::command]
+fn ensure_window_visible<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    let manager = window_manager(&app).ok_or_else(|| "Window not found".to_string())?;
+    if manager.ensure_visible().map_err(|e| e.to_string())? {
+        println!("🪟 Window was off screen, moved back");
+        emit_window_geometry(manager.window());
+    }
+    Ok(())
+}
+
+// Choose the monitor the window appears on; applies the next time it's shown
+#[tauri::command]
+fn set_window_monitor<R: Runtime>(app: tauri::AppHandle<R>, placement: MonitorPlacement) -> Result<(), String> {
+    if let MonitorPlacement::Index(index) = placement {
+        let count = app.available_monitors().map_err(|e| e.to_string())?.len();
+        if index >= count {
+            return Err(format!("Monitor {} doesn't exist ({} available)", index, count));
+        }
+    }
+    println!("🖥️ Window monitor set to {:?}", placement);
+    update_config(&app, |settings| settings.window_monitor = placement)
+}
+
+#[tauri::command]
+fn list_monitors<R: Runtime>(app: tauri::AppHandle<R>) -> Result<Vec<events::MonitorInfo>, String> {
+    let primary = app.primary_monitor().map_err(|e| e.to_string())?;
+    let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+    Ok(monitors
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| events::MonitorInfo {
+            index,
+            name: monitor.name().cloned(),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+            is_primary: primary
+                .as_ref()
+                .is_some_and(|primary| primary.name() == monitor.name() && primary.position() == monitor.position()),
+        })
+        .collect())
+}
+
+// A display from list_monitors, by index or name
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum DisplayTarget {
+    Index(usize),
+    Name(String),
+}
+
+// Keep the window on one display. It's looked up again every time the window
+// is placed, so the choice survives displays being re-enumerated.
+#[tauri::command]
+fn set_display<R: Runtime>(app: tauri::AppHandle<R>, target: DisplayTarget) -> Result<(), String> {
+    let placement = match target {
+        DisplayTarget::Index(index) => MonitorPlacement::Index(index),
+        DisplayTarget::Name(name) => {
+            let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+            if !monitors.iter().any(|monitor| monitor.name().is_some_and(|candidate| candidate.eq_ignore_ascii_case(&name))) {
+                return Err(format!("No display named {}", name));
+            }
+            MonitorPlacement::Name(name)
+        }
+    };
+    place_on_display(&app, placement)
+}
+
+// The display the window is placed on by name; None goes back to the
+// primary one. Stored as the window_monitor placement like set_display.
+#[tauri::command]
+fn set_preferred_monitor<R: Runtime>(app: tauri::AppHandle<R>, name: Option<String>) -> Result<(), String> {
+    match name {
+        Some(name) => set_display(app, DisplayTarget::Name(name)),
+        None => place_on_display(&app, MonitorPlacement::Primary),
+    }
+}
+
+// Save the placement and move a visible window there
+fn place_on_display<R: Runtime>(app: &tauri::AppHandle<R>, placement: MonitorPlacement) -> Result<(), String> {
+    set_window_monitor(app.clone(), placement)?;
+    update_config(app, |settings| settings.window_position = None)?;
+    app.state::<AppState>().display_missing.store(false, Ordering::SeqCst);
+    if let Some(manager) = window_manager(app).filter(|manager| manager.is_visible()) {
+        let size = manager.size().unwrap_or(manager.restored_size());
+        manager.apply_size(manager.clamp(size)).map_err(|e| e.to_string())?;
+        emit_window_resized(app, events::ResizeReason::Clamp);
+    }
+    Ok(())
+}
+
+// Recognize spoken numbers as digits until stop_number_dictation; each one is
+// emitted as "number-dictated"
+#[tauri::command]
+fn start_number_dictation<R: Runtime>(app: tauri::AppHandle<R>, state: State<AppState>) -> Result<(), String> {
+    let detector_guard = state.wake_word_detector.lock().unwrap();
+    let detector = detector_guard
+        .as_ref()
+        .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+    detector
+        .start_number_input(move |number| {
+            send_event(&app, "number-dictated", events::NumberDictatedPayload { number }, Delivery::Reliable);
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn stop_number_dictation(state: State<AppState>) {
+    if let Some(detector) = state.wake_word_detector.lock().unwrap().as_ref() {
+        detector.stop_number_input();
+    }
+}
+
+#[tauri::command]
+fn list_input_devices() -> Vec<String> {
+    audio::input_device_names()
+}
+
+// Capture from these microphones (null is the default device), mixing them if
+// there is more than one. Applies the next time wake word detection starts.
+#[tauri::command]
+fn set_input_devices<R: Runtime>(app: tauri::AppHandle<R>, devices: Vec<Option<String>>) -> Result<(), String> {
+    let available = audio::input_device_names();
+    if let Some(missing) = devices.iter().flatten().find(|device| !available.contains(device)) {
+        return Err(format!("Input device not found: {}", missing));
+    }
+    for (index, device) in devices.iter().enumerate() {
+        if devices[..index].contains(device) {
+            return Err("Each input device can only be used once".to_string());
+        }
+    }
+    
+    if let Some(detector) = app.state::<AppState>().wake_word_detector.lock().unwrap().as_ref() {
+        detector.set_input_devices(devices.clone());
+    }
+    println!("🎤 Input devices set: {:?}", devices);
+    update_config(&app, |settings| settings.input_devices = devices)
+}
+
+// Which channels of a multi-channel microphone to listen to. Applies the next
+// time wake word detection starts.
+#[tauri::command]
+fn set_channel_routing<R: Runtime>(app: tauri::AppHandle<R>, routing: ChannelRouting) -> Result<(), String> {
+    if routing == ChannelRouting::BeamformChannels(Vec::new()) {
+        return Err("Pick at least one channel to beamform".to_string());
+    }
+    if let Some(detector) = app.state::<AppState>().wake_word_detector.lock().unwrap().as_ref() {
+        detector.set_channel_routing(routing.clone());
+    }
+    println!("🎤 Channel routing set: {:?}", routing);
+    update_config(&app, |settings| settings.channel_routing = routing)
+}
+
+#[tauri::command]
+fn list_available_models<R: Runtime>(app: tauri::AppHandle<R>) -> Result<Vec<models::ModelInfo>, String> {
+    Ok(models::list(&models_dir(&app)?))
+}
+
+// Fetch a Whisper model into {app_data}/models, reporting progress as
+// "model-download-progress" events
+#[tauri::command]
+async fn download_model<R: Runtime>(app: tauri::AppHandle<R>, model_name: String) -> Result<(), String> {
+    let model = models::list(&models_dir(&app)?)
+        .into_iter()
+        .find(|model| model.name == model_name)
+        .ok_or_else(|| format!("Unknown Whisper model: {}", model_name))?;
+    let dest = models_dir(&app)?.join(models::file_name(model.name));
+    
+    println!("⬇️ Downloading Whisper model {} from {}", model.name, model.url);
+    let mut last_percent = None;
+    models::download(&model.url, &dest, |bytes_downloaded, total_bytes| {
+        let total_bytes = total_bytes.unwrap_or(model.size_bytes);
+        let percent = (bytes_downloaded * 100 / total_bytes.max(1)).min(100) as u8;
+        // One event per percent is plenty for a progress bar
+        if last_percent != Some(percent) {
+            last_percent = Some(percent);
+            let payload = events::ModelDownloadProgressPayload {
+                model: model.name.to_string(),
+                percent,
+                bytes_downloaded,
+                total_bytes,
+            };
+            send_event(&app, "model-download-progress", payload, Delivery::Latest);
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to download Whisper model {}: {}", model.name, e))?;
+    println!("✅ Whisper model {} saved to {}", model.name, dest.display());
+    Ok(())
+}
+
+// Choose the downloaded model the whisper backend is to load. Whisper can't
+// be selected until a runtime ships, so nothing is rebuilt.
+#[tauri::command]
+fn set_active_model<R: Runtime>(app: tauri::AppHandle<R>, model_name: String) -> Result<(), String> {
+    if !models::is_known(&model_name) {
+        return Err(format!("Unknown Whisper model: {}", model_name));
+    }
+    let path = models_dir(&app)?.join(models::file_name(&model_name));
+    if !path.is_file() {
+        return Err(format!("Whisper model {} hasn't been downloaded", model_name));
+    }
+    update_config(&app, |settings| settings.whisper_model = Some(model_name))
+}
+
+// set_recognition_backend by type rather than name
+#[tauri::command]
+fn set_speech_backend<R: Runtime>(app: tauri::AppHandle<R>, backend: speech_recognition::SpeechBackendType) -> Result<(), String> {
+    set_recognition_backend(app, backend.name().to_string())
+}
+
+#[tauri::command]
+fn set_endpointing_config<R: Runtime>(app: tauri::AppHandle<R>, config: EndpointingConfig) -> Result<(), String> {
+    config.validate()?;
+    
+    if let Some(detector) = app.state::<AppState>().wake_word_detector.lock().unwrap().as_ref() {
+        detector.set_endpointing(&config);
+    }
+    update_config(&app, |settings| settings.endpointing = config.clone())?;
+    app.emit("endpointing-config-changed", config).unwrap();
+    Ok(())
+}
+
+// Longest calibration the frontend can ask for
+const MAX_CALIBRATION_MS: u64 = 30_000;
+
+// How often calibration progress is reported
+const CALIBRATION_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// A wake word needs this much more energy than the background to count
+const WAKE_ENERGY_NOISE_MULTIPLIER: f32 = 1.5;
+const MAX_ENERGY_GATE_RATIO: f32 = 100.0;
+
+// Sample the background audio for `duration_ms` and store its RMS as the noise
+// floor. The minimum wake word energy is set from it. Returns the noise floor.
+#[tauri::command]
+async fn calibrate_noise_floor<R: Runtime>(app: tauri::AppHandle<R>, duration_ms: u64) -> Result<f32, String> {
+    if duration_ms == 0 || duration_ms > MAX_CALIBRATION_MS {
+        return Err(format!("Calibration duration must be between 1 and {} ms", MAX_CALIBRATION_MS));
+    }
+    
+    println!("🎚️ Calibrating noise floor for {} ms...", duration_ms);
+    let progress_app = app.clone();
+    let samples = tauri::async_runtime::spawn_blocking(move || -> anyhow::Result<Vec<i16>> {
+        // Captured separately from the wake word detector so nothing is recognized
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let captured = Arc::clone(&samples);
+        let mut capture = AudioCapture::new();
+        capture.start_capture(move |frame| captured.lock().unwrap().extend(frame))?;
+        
+        let duration = Duration::from_millis(duration_ms);
+        let start = std::time::Instant::now();
+        while start.elapsed() < duration {
+            std::thread::sleep(CALIBRATION_PROGRESS_INTERVAL.min(duration.saturating_sub(start.elapsed())));
+            let percent = (start.elapsed().as_millis() * 100 / duration.as_millis()).min(100) as u32;
+            let _ = progress_app.emit("calibration-progress", events::CalibrationProgressPayload { percent });
+        }
+        capture.stop_capture();
+        
+        let samples = samples.lock().unwrap().clone();
+        Ok(samples)
+    })
+    .await
+    .map_err(|e| format!("Calibration task failed: {}", e))?
+    .map_err(|e| format!("Failed to capture audio: {}", e))?;
+    
+    if samples.is_empty() {
+        return Err("No audio was captured during calibration".to_string());
+    }
+    
+    let noise_floor = audio::rms(&samples);
+    let threshold = noise_floor * WAKE_ENERGY_NOISE_MULTIPLIER;
+    update_config(&app, |settings| {
+        settings.noise_floor_rms = noise_floor;
+        settings.min_wake_energy_rms = threshold;
+    })?;
+    let gate_rms = app.state::<AppState>().config.lock().unwrap().energy_gate_rms();
+    if let Some(detector) = app.state::<AppState>().wake_word_detector.lock().unwrap().as_ref() {
+        detector.set_min_wake_energy(threshold);
+        detector.set_energy_gate(gate_rms);
+    }
+    println!("🎚️ Noise floor {:.4}, minimum wake word energy {:.4}", noise_floor, threshold);
+    Ok(noise_floor)
+}
+
+// Turn automatic gain control on or off, optionally changing the level it
+// aims for (the current target if not given)
+#[tauri::command]
+fn set_agc<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool, target_rms: Option<f32>) -> Result<(), String> {
+    if let Some(target) = target_rms {
+        if !(target > 0.0 && target <= 1.0) {
+            return Err("AGC target RMS must be above 0 and at most 1".to_string());
+        }
+    }
+    update_config(&app, |settings| {
+        settings.agc_enabled = enabled;
+        if let Some(target) = target_rms {
+            settings.agc_target_rms = target;
+        }
+    })?;
+    let target = app.state::<AppState>().config.lock().unwrap().agc_target_rms;
+    if let Some(detector) = app.state::<AppState>().wake_word_detector.lock().unwrap().as_ref() {
+        detector.set_agc(enabled.then_some(target));
+    }
+    println!("🎚️ Automatic gain control {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// Only poll SAPI while audio is `ratio` times louder than the calibrated noise
+// floor; 0 turns the gate off
+#[tauri::command]
+fn set_energy_gate_ratio<R: Runtime>(app: tauri::AppHandle<R>, ratio: f32) -> Result<(), String> {
+    if !(0.0..=MAX_ENERGY_GATE_RATIO).contains(&ratio) {
+        return Err(format!("Energy gate ratio must be between 0 and {}", MAX_ENERGY_GATE_RATIO));
+    }
+    update_config(&app, |settings| settings.energy_gate_ratio = ratio)?;
+    let gate_rms = app.state::<AppState>().config.lock().unwrap().energy_gate_rms();
+    if let Some(detector) = app.state::<AppState>().wake_word_detector.lock().unwrap().as_ref() {
+        detector.set_energy_gate(gate_rms);
+    }
+    match gate_rms {
+        Some(rms) => println!("🚪 Energy gate at {:.4}", rms),
+        None => println!("🚪 Energy gate off"),
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct AppHealth {
+    sapi_initialized: bool,
+    wake_word_state: Option<wake_word::DetectorState>,
+    audio_input_available: bool,
+}
+
+#[tauri::command]
+fn get_app_health(state: State<AppState>) -> AppHealth {
+    app_health(&state)
+}
+
+fn app_health(state: &AppState) -> AppHealth {
+    let wake_word_state = state.wake_word_detector.lock().unwrap().as_ref().map(|detector| detector.state());
+    AppHealth {
+        // The detector only exists if SAPI initialized
+        sapi_initialized: wake_word_state.is_some(),
+        wake_word_state,
+        audio_input_available: audio::input_device_available(),
+    }
+}
+
+// Save a PNG of the window as it appears on screen
+#[tauri::command]
+fn capture_window_screenshot<R: Runtime>(app: tauri::AppHandle<R>, output_path: String) -> Result<(), String> {
+    let window = app.get_webview_window("main").ok_or_else(|| "Window not found".to_string())?;
+    screenshot::capture_window(&window, std::path::Path::new(&output_path)).map_err(|e| e.to_string())?;
+    println!("📸 Window screenshot saved to {}", output_path);
+    Ok(())
+}
+
+// Open the default mail client with a bug report filled in: app version,
+// health, where the logs are, and a screenshot of the window to attach
+#[tauri::command]
+fn open_bug_report<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    let log_dir = app.path().app_log_dir().map_err(|e| e.to_string())?;
+    // A missing screenshot shouldn't stop the report
+    let screenshot_path = log_dir.join(format!("bug-report-{}.png", history::now_ms()));
+    let screenshot = std::fs::create_dir_all(&log_dir)
+        .map_err(|e| e.to_string())
+        .and_then(|_| capture_window_screenshot(app.clone(), screenshot_path.to_string_lossy().into_owned()));
+    if let Err(e) = &screenshot {
+        eprintln!("⚠️ Bug report without screenshot: {}", e);
+    }
+    
+    let report = bug_report::BugReport {
+        app_version: app.package_info().version.to_string(),
+        health: serde_json::to_string(&app_health(&app.state::<AppState>())).unwrap_or_default(),
+        log_path: log_dir.to_string_lossy().into_owned(),
+        screenshot_path: screenshot.ok().map(|_| screenshot_path.to_string_lossy().into_owned()),
+    };
+    open::that(report.mailto_uri("Jackson bug report")).map_err(|e| format!("Failed to open the mail client: {}", e))
+}
+
+#[tauri::command]
+fn get_noise_floor(state: State<AppState>) -> f32 {
+    state.config.lock().unwrap().noise_floor_rms
+}
+
+#[derive(serde::Serialize)]
+struct AppStatus {
+    wake_word_state: Option<wake_word::DetectorState>,
+    recognition_backend: String,
+    speech_session_active: bool,
+    do_not_disturb: bool,
+    endpointing: EndpointingConfig,
+    output_playing: bool,
+    suppressed_results: u64,
+    auto_hide_on_blur: bool,
+    auto_hide_delay_ms: u64,
+    window_pinned: bool,
+    launched_at_login: bool,
+}
+
+#[tauri::command]
+fn get_app_status(state: State<AppState>) -> AppStatus {
+    let wake_word_state = state.wake_word_detector.lock().unwrap().as_ref().map(|detector| detector.state());
+    let config = state.config.lock().unwrap();
+    AppStatus {
+        wake_word_state,
+        recognition_backend: config.recognition_backend.clone(),
+        speech_session_active: state.speech_session_active.load(Ordering::SeqCst),
+        do_not_disturb: state.do_not_disturb.load(Ordering::SeqCst),
+        endpointing: config.endpointing.clone(),
+        output_playing: state.session.output_playing(),
+        suppressed_results: state.session.suppressed_results(),
+        auto_hide_on_blur: config.auto_hide_on_blur,
+        auto_hide_delay_ms: config.auto_hide_delay_ms,
+        window_pinned: state.window_pinned.load(Ordering::SeqCst),
+        launched_at_login: state.launched_at_login,
+    }
+}
+
+// Called by the frontend around spoken replies so the recognizer doesn't
+// pick up the assistant's own voice
+#[tauri::command]
+fn set_output_playing(playing: bool, state: State<AppState>) {
+    let max_playing = state.config.lock().unwrap().max_output_playing_ms;
+    state.session.set_output_playing(playing, Duration::from_millis(max_playing));
+    println!("🔈 Output playing: {}", playing);
+}
+
+// Whether the microphone was found picking up our playback, and so whether
+// wake words are being ignored while output plays
+#[tauri::command]
+fn get_echo_suppression_status(state: State<AppState>) -> EchoStatus {
+    state.echo.status(state.session.output_playing())
+}
+
+#[tauri::command]
+fn get_postprocess_config(state: State<AppState>) -> PostProcessConfig {
+    state.postprocess_config.lock().unwrap().clone()
+}
+
+#[tauri::command]
+fn set_postprocess_config<R: Runtime>(app: tauri::AppHandle<R>, mut config: PostProcessConfig) -> Result<(), String> {
+    // Only the profanity settings are persisted
+    update_config(&app, |settings| {
+        settings.profanity_filter = config.profanity_filter;
+        settings.custom_profanity = config.custom_profanity.clone();
+    })?;
+    let state = app.state::<AppState>();
+    let mut current = state.postprocess_config.lock().unwrap();
+    // The vocabulary has its own commands
+    config.vocabulary = std::mem::take(&mut current.vocabulary);
+    *current = config;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_profanity_filter<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    app.state::<AppState>().postprocess_config.lock().unwrap().profanity_filter = enabled;
+    update_config(&app, |settings| settings.profanity_filter = enabled)?;
+    println!("🤐 Profanity filter {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// Words masked in addition to the built-in list
+#[tauri::command]
+fn set_custom_profanity<R: Runtime>(app: tauri::AppHandle<R>, words: Vec<String>) -> Result<(), String> {
+    let words: Vec<String> = words
+        .into_iter()
+        .map(|word| word.trim().to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect();
+    app.state::<AppState>().postprocess_config.lock().unwrap().custom_profanity = words.clone();
+    update_config(&app, |settings| settings.custom_profanity = words)
+}
+
+#[tauri::command]
+fn list_vocabulary(state: State<AppState>) -> Vec<String> {
+    state.config.lock().unwrap().vocabulary.clone()
+}
+
+#[tauri::command]
+fn add_vocabulary_phrase<R: Runtime>(app: tauri::AppHandle<R>, phrase: String) -> Result<(), String> {
+    let phrase = postprocess::collapse_whitespace(&phrase);
+    if phrase.is_empty() {
+        return Err("Vocabulary phrase cannot be empty".to_string());
+    }
+    let mut vocabulary = app.state::<AppState>().config.lock().unwrap().vocabulary.clone();
+    if vocabulary.iter().any(|existing| existing.eq_ignore_ascii_case(&phrase)) {
+        return Err(format!("\"{}\" is already in the vocabulary", phrase));
+    }
+    println!("📖 Added vocabulary phrase \"{}\"", phrase);
+    vocabulary.push(phrase);
+    apply_vocabulary(&app, vocabulary)
+}
+
+#[tauri::command]
+fn remove_vocabulary_phrase<R: Runtime>(app: tauri::AppHandle<R>, phrase: String) -> Result<(), String> {
+    let phrase = postprocess::collapse_whitespace(&phrase);
+    let mut vocabulary = app.state::<AppState>().config.lock().unwrap().vocabulary.clone();
+    let count = vocabulary.len();
+    vocabulary.retain(|existing| !existing.eq_ignore_ascii_case(&phrase));
+    if vocabulary.len() == count {
+        return Err(format!("\"{}\" is not in the vocabulary", phrase));
+    }
+    println!("📖 Removed vocabulary phrase \"{}\"", phrase);
+    apply_vocabulary(&app, vocabulary)
+}
+
+// Persist the vocabulary and hand it to the post-processor and recognizers;
+// backends pick it up when the next session starts
+fn apply_vocabulary<R: Runtime>(app: &tauri::AppHandle<R>, vocabulary: Vec<String>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.postprocess_config.lock().unwrap().vocabulary = vocabulary.clone();
+    if let Some(detector) = state.wake_word_detector.lock().unwrap().as_ref() {
+        detector.set_vocabulary(&vocabulary);
+    }
+    let _ = app.emit("vocabulary-changed", events::VocabularyChangedPayload { phrases: vocabulary.clone() });
+    update_config(app, |settings| settings.vocabulary = vocabulary)
+}
+
+// Add or replace an intent. Patterns are plain words with {slot} placeholders,
+// e.g. "set a timer for {duration}".
+#[tauri::command]
+fn register_intent(name: String, patterns: Vec<String>, state: State<AppState>) -> Result<(), String> {
+    state.intents.lock().unwrap().register(&name, &patterns)?;
+    println!("🧭 Registered intent {} with {} patterns", name, patterns.len());
+    Ok(())
+}
+
+// Which registered patterns a phrase matches and which would fire, without
+// emitting "intent-detected"
+#[tauri::command]
+fn test_command_match(text: String, state: State<AppState>) -> Vec<intents::CommandMatchResult> {
+    state.intents.lock().unwrap().test_match(&text)
+}
+
+#[tauri::command]
+fn set_recognition_constraints(phrases: Vec<String>, state: State<AppState>) -> Result<(), String> {
+    let phrases: Vec<String> = phrases
+        .into_iter()
+        .map(|phrase| phrase.trim().to_string())
+        .filter(|phrase| !phrase.is_empty())
+        .collect();
+    if phrases.is_empty() {
+        return Err("At least one constraint phrase is required".to_string());
+    }
+    
+    let detector_guard = state.wake_word_detector.lock().unwrap();
+    let detector = detector_guard
+        .as_ref()
+        .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+    detector.set_constraints(phrases);
+    Ok(())
+}
+
+#[tauri::command]
+fn clear_recognition_constraints(state: State<AppState>) -> Result<(), String> {
+    let detector_guard = state.wake_word_detector.lock().unwrap();
+    let detector = detector_guard
+        .as_ref()
+        .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+    detector.clear_constraints();
+    Ok(())
+}
+
+// Sync commands run on the main thread, where the fade, the Win32 window
+// calls and the config save behind hiding would hold up the event loop and
+// every other command. Keep this async with the work on the blocking pool.
+#[tauri::command]
+async fn hide_window<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    println!("Hide window command called");
+    tauri::async_runtime::spawn_blocking(move || hide_main_window(&app))
+        .await
+        .map_err(|e| format!("Failed to hide the window: {}", e))
+}
+
+fn hide_main_window<R: Runtime>(app: &tauri::AppHandle<R>) {
+    // Hiding the window ends the session, so drop any recognition constraints
+    if let Some(detector) = app.state::<AppState>().wake_word_detector.lock().unwrap().as_ref() {
+        detector.clear_constraints();
+    }
+    app.state::<AppState>().session.close();
+    if window_manager(app).is_some_and(|manager| manager.is_visible()) {
+        start_window_fade(app, 0.0, finish_hiding);
+    } else {
+        finish_hiding(app);
+    }
+}
+
+// The end of hiding, once the window has faded out
+fn finish_hiding<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(manager) = window_manager(app) {
+        app.emit("window-hidden", events::WindowHiddenPayload {}).unwrap();
+        // Snapshot the size so the next show restores it
+        if let Some(size) = manager.hide() {
+            remember_window_size(app, size.width, size.height);
+        }
+        save_window_position(app);
+        emit_window_geometry(manager.window());
+    }
+}
+
+// A Moved event from the user dragging the main window. Its position is
+// kept in memory while dragging and saved when the window hides or the app
+// exits.
+fn note_window_moved<R: Runtime>(app: &tauri::AppHandle<R>, position: PhysicalPosition<i32>) {
+    let state = app.state::<AppState>();
+    let ours = state.programmatic_moves.lock().unwrap().is_some_and(|until| std::time::Instant::now() < until);
+    let visible = app.get_webview_window("main").is_some_and(|window| matches!(window.is_visible(), Ok(true)));
+    if ours || !visible {
+        return;
+    }
+    let position = WindowPosition { x: position.x, y: position.y };
+    let previous = state.config.lock().unwrap().window_position.replace(position);
+    if previous.is_none() {
+        println!("🪟 Window moved by the user, keeping it there");
+    }
+}
+
+fn save_window_position<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let state = app.state::<AppState>();
+    let config = state.config.lock().unwrap();
+    if let Err(e) = config::save(app, &config) {
+        eprintln!("⚠️ Failed to save the window position: {}", e);
+    }
+}
+
+// Forget where the user dragged the window and go back to the anchor
+#[tauri::command]
+fn reset_window_position<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    update_config(&app, |settings| settings.window_position = None)?;
+    println!("⚓ Window position reset to the anchor");
+    if let Some(manager) = window_manager(&app).filter(|manager| manager.is_visible()) {
+        if let Some(size) = manager.size() {
+            manager.apply_size(size).map_err(|e| e.to_string())?;
+            emit_window_resized(&app, events::ResizeReason::UserRequest);
+            emit_window_geometry(manager.window());
+        }
+    }
+    Ok(())
+}
+
+// Async for the same reason as hide_window: placing the window reads the
+// monitor layout and DPI, then it's moved, shown and faded in, all of which
+// would otherwise run on the main thread
+#[tauri::command]
+async fn show_window<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    println!("Show window command called");
+    tauri::async_runtime::spawn_blocking(move || {
+        show_main_window(&app);
+    })
+    .await
+    .map_err(|e| format!("Failed to show the window: {}", e))
+}
+
+// The main window with the current window settings
+fn window_manager<R: Runtime>(app: &tauri::AppHandle<R>) -> Option<WindowManager<R>> {
+    let window = app.get_webview_window("main")?;
+    let state = app.state::<AppState>();
+    let failures = Arc::clone(&state.monitor_info_failures);
+    let handle = app.clone();
+    let on_monitor_unavailable = Arc::new(move |using_cached: bool| {
+        let occurrences = failures.fetch_add(1, Ordering::SeqCst) + 1;
+        eprintln!(
+            "⚠️ Monitor information unavailable, {}",
+            if using_cached { "using the last known work area" } else { "keeping the window where it is" }
+        );
+        let payload = events::MonitorInfoUnavailablePayload { using_cached_work_area: using_cached, occurrences };
+        send_event(&handle, "monitor-info-unavailable", payload, Delivery::Latest);
+    });
+    let display_missing = Arc::clone(&state.display_missing);
+    let handle = app.clone();
+    let on_display_missing = Arc::new(move |display: &MonitorPlacement, missing: bool| {
+        // Warn once per disappearance, not on every placement
+        if display_missing.swap(missing, Ordering::SeqCst) || !missing {
+            return;
+        }
+        eprintln!("⚠️ Display {:?} isn't connected, using the primary one", display);
+        let payload = events::DisplayUnavailablePayload { display: display.clone() };
+        send_event(&handle, "display-unavailable", payload, Delivery::Latest);
+    });
+    let config = state.config.lock().unwrap();
+    Some(WindowManager::new(
+        window,
+        &config,
+        Arc::clone(&state.work_area_cache),
+        on_monitor_unavailable,
+        on_display_missing,
+        Arc::clone(&state.programmatic_moves),
+    ))
+}
+
+// Every way of showing the window goes through here: the compact preset at
+// the anchor, faded in, then "window-shown". False if there's no window.
+fn show_main_window<R: Runtime>(app: &tauri::AppHandle<R>) -> bool {
+    let Some(manager) = window_manager(app) else {
+        return false;
+    };
+    show_with(app, manager)
+}
+
+// The tray icon and its "Show" item open the window next to the tray, unless
+// tray_adjacent is off; the wake word and hotkey keep to the anchor
+fn show_from_tray<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let Some(manager) = window_manager(app) else {
+        return;
+    };
+    let tray_adjacent = app.state::<AppState>().config.lock().unwrap().tray_adjacent;
+    show_with(app, if tray_adjacent { manager.at_tray() } else { manager });
+}
+
+fn show_with<R: Runtime>(app: &tauri::AppHandle<R>, manager: WindowManager<R>) -> bool {
+    touch_activity(app);
+    // A window that's fading out is still visible, and fades back in from there
+    let hidden = !manager.is_visible();
+    if hidden {
+        *app.state::<AppState>().window_fade_level.lock().unwrap() = 0.0;
+        apply_fade_level(app, 0.0);
+    }
+    let appearing = hidden || window_fading(app);
+    // Showing can bring the frame's shadow back
+    let window_shadow = app.state::<AppState>().config.lock().unwrap().window_shadow;
+    shadow::set_shadow(manager.window(), window_shadow).unwrap_or_else(|e| eprintln!("⚠️ {}", e));
+    // A pinned window stays exactly where the user left it, and the overlay
+    // keeps covering its monitor
+    if overlay_active(app) {
+        manager.present();
+        // present() goes back to the configured setting
+        let _ = manager.window().set_always_on_top(true);
+    } else if app.state::<AppState>().window_pinned.load(Ordering::SeqCst) && manager.is_visible() {
+        manager.present();
+    } else {
+        set_current_window_mode(app, WindowMode::Compact);
+        manager.show_at_anchor(manager.limits().compact);
+        emit_window_resized(app, events::ResizeReason::WakeWordShow);
+    }
+    if appearing {
+        start_window_fade(app, 1.0, finish_showing);
+    } else {
+        finish_showing(app);
+    }
+    true
+}
+
+fn finish_showing<R: Runtime>(app: &tauri::AppHandle<R>) {
+    app.emit("window-shown", events::WindowShownPayload {}).unwrap();
+    if let Some(window) = app.get_webview_window("main") {
+        emit_window_geometry(&window);
+    }
+}
+
+const WINDOW_FADE_TICK: Duration = Duration::from_millis(16);
+
+// Fade towards `to` (0 invisible, 1 fully in) and call `then` once there. A
+// new fade replaces the one in flight and carries on from wherever it got
+// to, so a hide during a fade-in (or the other way round) never leaves the
+// window half faded, and the replaced fade's `then` never runs.
+fn start_window_fade<R: Runtime>(app: &tauri::AppHandle<R>, to: f64, then: fn(&tauri::AppHandle<R>)) {
+    let state = app.state::<AppState>();
+    let mut fade = state.window_fade.lock().unwrap();
+    if let Some(previous) = fade.take() {
+        previous.abort();
+    }
+    let duration = Duration::from_millis(state.config.lock().unwrap().fade_ms);
+    if duration.is_zero() {
+        *state.window_fade_level.lock().unwrap() = to;
+        apply_fade_level(app, to);
+        drop(fade);
+        then(app);
+        return;
+    }
+    
+    let app = app.clone();
+    *fade = Some(tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let from = *state.window_fade_level.lock().unwrap();
+        let start = std::time::Instant::now();
+        loop {
+            let progress = (start.elapsed().as_secs_f64() / duration.as_secs_f64()).min(1.0);
+            let level = lerp(from, to, ease_out(progress));
+            *state.window_fade_level.lock().unwrap() = level;
+            apply_fade_level(&app, level);
+            if progress >= 1.0 {
+                break;
+            }
+            tokio::time::sleep(WINDOW_FADE_TICK).await;
+        }
+        then(&app);
+    }));
+}
+
+fn window_fading<R: Runtime>(app: &tauri::AppHandle<R>) -> bool {
+    let state = app.state::<AppState>();
+    let fade = state.window_fade.lock().unwrap();
+    fade.as_ref().is_some_and(|fade| !fade.inner().is_finished())
+}
+
+fn apply_fade_level<R: Runtime>(app: &tauri::AppHandle<R>, level: f64) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let opacity = *app.state::<AppState>().window_opacity.lock().unwrap();
+    let result = if level >= 1.0 {
+        opacity::set_opacity(&window, opacity)
+    } else {
+        opacity::set_fade_opacity(&window, opacity * level)
+    };
+    result.unwrap_or_else(|e| eprintln!("⚠️ Failed to set window opacity: {}", e));
+}
+
+// Shared by the command and the tray item so both stay in sync
+fn apply_always_on_top<R: Runtime>(app: &tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("main") {
+        window.set_always_on_top(enabled).map_err(|e| e.to_string())?;
+    }
+    update_config(app, |settings| settings.always_on_top = enabled)?;
+    if let Some(sync_menu) = app.state::<AppState>().on_top_menu_sync.lock().unwrap().as_ref() {
+        sync_menu(enabled);
+    }
+    println!("📌 Always on top {}", if enabled { "enabled" } else { "disabled" });
+    emit_window_config_changed(app);
+    Ok(())
+}
+
+fn emit_window_config_changed<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let payload = {
+        let state = app.state::<AppState>();
+        let config = state.config.lock().unwrap();
+        events::WindowConfigChangedPayload {
+            always_on_top: config.always_on_top,
+            show_without_focus: config.show_without_focus,
+        }
+    };
+    send_event(app, "window-config-changed", payload, Delivery::Reliable);
+}
+
+#[tauri::command]
+fn set_always_on_top<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    apply_always_on_top(&app, enabled)
+}
+
+// Only has an effect while the window is always on top
+#[tauri::command]
+fn set_show_without_focus<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    update_config(&app, |settings| settings.show_without_focus = enabled)?;
+    emit_window_config_changed(&app);
+    Ok(())
+}
+
+// Open the window next to the tray when it's opened from the tray
+#[tauri::command]
+fn set_tray_adjacent<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    update_config(&app, |settings| settings.tray_adjacent = enabled)
+}
+
+// Make the window translucent right away (clamped to 0.2 - 1.0). The next
+// wake word or session end applies the configured levels again.
+#[tauri::command]
+fn set_window_opacity<R: Runtime>(app: tauri::AppHandle<R>, opacity: f64) -> Result<(), String> {
+    let window = app.get_webview_window("main").ok_or_else(|| "Window not found".to_string())?;
+    opacity::set_opacity(&window, opacity).map_err(|e| e.to_string())
+}
+
+// Round or square the window's corners on Windows 11; a no-op on older versions
+#[tauri::command]
+fn set_window_corner_style<R: Runtime>(app: tauri::AppHandle<R>, style: corners::CornerStyle) -> Result<(), String> {
+    let window = app.get_webview_window("main").ok_or_else(|| "Window not found".to_string())?;
+    corners::set_corner_style(&window, style).map_err(|e| e.to_string())?;
+    update_config(&app, |settings| settings.corner_style = style)
+}
+
+#[tauri::command]
+fn set_window_shadow<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let window = app.get_webview_window("main").ok_or_else(|| "Window not found".to_string())?;
+    shadow::set_shadow(&window, enabled).map_err(|e| e.to_string())?;
+    update_config(&app, |settings| settings.window_shadow = enabled)
+}
+
+// Opacity applied when a session ends and when the wake word is heard
+#[tauri::command]
+fn set_opacity_levels<R: Runtime>(app: tauri::AppHandle<R>, idle: f64, active: f64) -> Result<(), String> {
+    update_config(&app, |settings| {
+        settings.idle_opacity = opacity::clamp(idle);
+        settings.active_opacity = opacity::clamp(active);
+    })?;
+    let active_now = app.state::<AppState>().speech_session_active.load(Ordering::SeqCst);
+    apply_session_opacity(&app, active_now);
+    Ok(())
+}
+
+// A fade in flight picks the new opacity up as it goes
+fn apply_session_opacity<R: Runtime>(app: &tauri::AppHandle<R>, active: bool) {
+    let state = app.state::<AppState>();
+    let opacity = {
+        let config = state.config.lock().unwrap();
+        if active { config.active_opacity } else { config.idle_opacity }
+    };
+    *state.window_opacity.lock().unwrap() = opacity;
+    let level = *state.window_fade_level.lock().unwrap();
+    if level >= 1.0 {
+        apply_fade_level(app, level);
+    }
+}
+
+#[tauri::command]
+fn set_window_fade<R: Runtime>(app: tauri::AppHandle<R>, duration_ms: u64) -> Result<(), String> {
+    update_config(&app, |settings| settings.fade_ms = duration_ms)
+}
+
+#[tauri::command]
+fn set_auto_hide_on_blur<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool, delay_ms: u64) -> Result<(), String> {
+    update_config(&app, |settings| {
+        settings.auto_hide_on_blur = enabled;
+        settings.auto_hide_delay_ms = delay_ms;
+    })?;
+    if !enabled {
+        cancel_auto_hide(&app);
+    }
+    Ok(())
+}
+
+// Keep the window up and where it is: no hiding when it loses focus or goes
+// idle, and resizes and wake words don't move it
+#[tauri::command]
+fn set_window_pinned<R: Runtime>(app: tauri::AppHandle<R>, pinned: bool) {
+    apply_window_pinned(&app, pinned);
+}
+
+// Shared by the command and the tray item so both stay in sync
+fn apply_window_pinned<R: Runtime>(app: &tauri::AppHandle<R>, pinned: bool) {
+    let state = app.state::<AppState>();
+    state.window_pinned.store(pinned, Ordering::SeqCst);
+    if let Some(sync_menu) = state.pin_menu_sync.lock().unwrap().as_ref() {
+        sync_menu(pinned);
+    }
+    if pinned {
+        cancel_auto_hide(app);
+    } else {
+        // A window that lost focus while pinned is due to hide now
+        let blurred = app
+            .get_webview_window("main")
+            .is_some_and(|window| matches!(window.is_visible(), Ok(true)) && !window.is_focused().unwrap_or(false));
+        if blurred {
+            schedule_auto_hide(app);
+        }
+    }
+    println!("📌 Window {}", if pinned { "pinned" } else { "unpinned" });
+}
+
+const TRAY_ID: &str = "main";
+
+// Replace the global hotkeys. If one of them is taken, the previous ones are
+// registered again and the error is returned.
+#[tauri::command]
+fn set_hotkeys<R: Runtime>(app: tauri::AppHandle<R>, hotkeys: Hotkeys) -> Result<(), String> {
+    hotkeys.parse()?;
+    let previous = app.state::<AppState>().config.lock().unwrap().hotkeys.clone();
+    if let Err(e) = register_hotkeys(&app, &hotkeys) {
+        let restored = register_hotkeys(&app, &previous).is_ok();
+        update_tray_tooltip(&app, restored.then_some(&previous));
+        return Err(e);
+    }
+    update_tray_tooltip(&app, Some(&hotkeys));
+    println!("⌨️ Hotkeys set: {:?}", hotkeys);
+    update_config(&app, |settings| settings.hotkeys = hotkeys)
+}
+
+fn register_hotkeys<R: Runtime>(app: &tauri::AppHandle<R>, hotkeys: &Hotkeys) -> Result<(), String> {
+    let parsed = hotkeys.parse()?;
+    let shortcuts = app.global_shortcut();
+    shortcuts.unregister_all().map_err(|e| format!("Failed to unregister hotkeys: {}", e))?;
+    shortcuts
+        .on_shortcut(parsed.toggle, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                toggle_main_window(app);
+            }
+        })
+        .map_err(|e| format!("Couldn't register {}: {}", hotkeys.toggle, e))?;
+    if let (Some(interact), Some(name)) = (parsed.interact, &hotkeys.interact) {
+        let registered = shortcuts.on_shortcut(interact, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                force_window_interactive(app);
+            }
+        });
+        if let Err(e) = registered {
+            let _ = shortcuts.unregister_all();
+            return Err(format!("Couldn't register {}: {}", name, e));
+        }
+    }
+    if let (Some(listening), Some(name)) = (parsed.listening, &hotkeys.listening) {
+        let registered = shortcuts.on_shortcut(listening, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                println!("⌨️ Listening hotkey pressed");
+                toggle_listening(app);
+            }
+        });
+        if let Err(e) = registered {
+            let _ = shortcuts.unregister_all();
+            return Err(format!("Couldn't register {}: {}", name, e));
+        }
+    }
+    Ok(())
+}
+
+// Same as show_window and hide_window, picked by whether the window is showing
+fn toggle_main_window<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let visible = window_manager(app).is_some_and(|manager| manager.is_visible());
+    println!("⌨️ Toggle hotkey pressed");
+    if visible {
+        hide_main_window(app);
+    } else {
+        show_main_window(app);
+    }
+}
+
+// The escape hatch for a window stuck click-through: drop the interactive
+// regions and take mouse input everywhere
+fn force_window_interactive<R: Runtime>(app: &tauri::AppHandle<R>) {
+    println!("⌨️ Interact hotkey pressed");
+    app.state::<AppState>().interactive_regions.lock().unwrap().clear();
+    set_ignore_cursor_events(app.clone(), false);
+}
+
+fn update_tray_tooltip<R: Runtime>(app: &tauri::AppHandle<R>, hotkeys: Option<&Hotkeys>) {
+    update_tray_display(app, |display| display.hotkeys = hotkeys.cloned());
+}
+
+// Change what the tooltip shows besides the status, which goes through
+// refresh_tray_status
+fn update_tray_display<R: Runtime>(app: &tauri::AppHandle<R>, update: impl FnOnce(&mut TrayDisplay)) {
+    // Not held while the tray is updated, which waits for the main thread
+    let tooltip = {
+        let state = app.state::<AppState>();
+        let mut display = state.tray_display.lock().unwrap();
+        update(&mut display);
+        display.tooltip()
+    };
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+// What the tooltip calls the microphones in `devices` (none is the default one)
+fn microphone_label(devices: &[Option<String>]) -> Option<String> {
+    match devices {
+        [] | [None] => audio::default_input_device_name(),
+        [Some(name)] => Some(name.clone()),
+        devices => Some(format!("{} microphones", devices.len())),
+    }
+}
+
+// A flapping state changes the tray icon at most this often
+const TRAY_STATUS_INTERVAL: Duration = Duration::from_millis(250);
+
+// Show the tray status for the current detector, session and microphone state
+fn refresh_tray_status<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let state = app.state::<AppState>();
+    let status = TrayStatus::from_state(
+        *state.detector_state.lock().unwrap(),
+        state.speech_session_active.load(Ordering::SeqCst),
+        state.microphone_paused.load(Ordering::SeqCst),
+    );
+    let decision = state.tray_status_debounce.lock().unwrap().request(status, std::time::Instant::now(), TRAY_STATUS_INTERVAL);
+    match decision {
+        Debounced::Apply(status) => apply_tray_status(app, status),
+        Debounced::Schedule(wait) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(wait).await;
+                let pending = app.state::<AppState>().tray_status_debounce.lock().unwrap().flush(std::time::Instant::now());
+                if let Some(status) = pending {
+                    apply_tray_status(&app, status);
+                }
+            });
+        }
+        Debounced::Queued => {}
+    }
+}
+
+// The icon and tooltip change together
+fn apply_tray_status<R: Runtime>(app: &tauri::AppHandle<R>, status: TrayStatus) {
+    // Not held while the tray is updated, which waits for the main thread
+    let tooltip = {
+        let state = app.state::<AppState>();
+        let mut display = state.tray_display.lock().unwrap();
+        if display.status == status {
+            return;
+        }
+        display.status = status;
+        display.tooltip()
+    };
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match tauri::image::Image::from_bytes(status.icon()) {
+        Ok(icon) => {
+            let _ = tray.set_icon(Some(icon));
+        }
+        Err(e) => eprintln!("⚠️ Failed to load the {:?} tray icon: {}", status, e),
+    }
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+// Start the countdown to hiding the window after it lost focus. It's checked
+// again when the delay is up, since a session may have started meanwhile.
+fn schedule_auto_hide<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let state = app.state::<AppState>();
+    let (enabled, delay) = {
+        let config = state.config.lock().unwrap();
+        (config.auto_hide_on_blur, Duration::from_millis(config.auto_hide_delay_ms))
+    };
+    if !enabled {
+        return;
+    }
+    
+    let app = app.clone();
+    let mut timer = state.auto_hide_timer.lock().unwrap();
+    if let Some(previous) = timer.take() {
+        previous.abort();
+    }
+    *timer = Some(tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+        let state = app.state::<AppState>();
+        let busy = state.speech_session_active.load(Ordering::SeqCst) || state.window_pinned.load(Ordering::SeqCst);
+        let still_blurred = app
+            .get_webview_window("main")
+            .is_some_and(|window| matches!(window.is_visible(), Ok(true)) && !window.is_focused().unwrap_or(false));
+        if !busy && still_blurred {
+            println!("🙈 Hiding window after losing focus");
+            hide_main_window(&app);
+        }
+    }));
+}
+
+fn cancel_auto_hide<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(timer) = app.state::<AppState>().auto_hide_timer.lock().unwrap().take() {
+        timer.abort();
+    }
+}
+
+// Hide the window after `timeout_ms` of inactivity; 0 turns it off
+#[tauri::command]
+fn set_idle_hide<R: Runtime>(app: tauri::AppHandle<R>, timeout_ms: u64) -> Result<(), String> {
+    touch_activity(&app);
+    update_config(&app, |settings| settings.idle_hide_ms = timeout_ms)
+}
+
+// The frontend calls this while the user interacts with it, so the window
+// isn't hidden for inactivity mid-interaction
+#[tauri::command]
+fn frontend_heartbeat<R: Runtime>(app: tauri::AppHandle<R>) {
+    touch_activity(&app);
+}
+
+fn touch_activity<R: Runtime>(app: &tauri::AppHandle<R>) {
+    *app.state::<AppState>().last_activity.lock().unwrap() = std::time::Instant::now();
+}
+
+// How often the idle watcher checks for inactivity
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+const DISPLAY_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+// Tauri has no event for displays being plugged in, unplugged or rearranged,
+// so the monitor layout is polled. When it changes, a visible window that
+// ended up mostly off screen is pulled back.
+fn start_display_watcher<R: Runtime>(app: tauri::AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        let layout = |app: &tauri::AppHandle<R>| {
+            app.available_monitors().ok().map(|monitors| {
+                monitors
+                    .iter()
+                    .map(|monitor| (monitor.name().cloned(), *monitor.position(), *monitor.size()))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let mut last = layout(&app);
+        loop {
+            tokio::time::sleep(DISPLAY_CHECK_INTERVAL).await;
+            let current = layout(&app);
+            if current.is_none() || current == last {
+                continue;
+            }
+            last = current;
+            println!("🖥️ Display layout changed");
+            if overlay_active(&app) {
+                fit_overlay(&app, events::ResizeReason::Clamp).unwrap_or_else(|e| eprintln!("⚠️ Failed to refit the overlay: {}", e));
+                continue;
+            }
+            if let Some(manager) = window_manager(&app).filter(|manager| manager.is_visible()) {
+                match manager.ensure_visible() {
+                    Ok(true) => {
+                        println!("🪟 Window was off screen, moved back");
+                        emit_window_resized(&app, events::ResizeReason::Clamp);
+                        emit_window_geometry(manager.window());
+                    }
+                    Ok(false) => {}
+                    Err(e) => eprintln!("⚠️ Failed to check the window position: {:?}", e),
+                }
+            }
+        }
+    });
+}
+
+// Hide the visible window once nothing has happened for idle_hide_ms. A
+// running session or a pinned window counts as activity.
+fn start_idle_watcher<R: Runtime>(app: tauri::AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+            let state = app.state::<AppState>();
+            let timeout = Duration::from_millis(state.config.lock().unwrap().idle_hide_ms);
+            if state.speech_session_active.load(Ordering::SeqCst) || state.window_pinned.load(Ordering::SeqCst) {
+                touch_activity(&app);
+                continue;
+            }
+            let idle = state.last_activity.lock().unwrap().elapsed();
+            let visible = app
+                .get_webview_window("main")
+                .is_some_and(|window| matches!(window.is_visible(), Ok(true)));
+            if timeout.is_zero() || !visible || idle < timeout {
+                continue;
+            }
+            
+            println!("💤 Hiding window after {} s of inactivity", idle.as_secs());
+            hide_main_window(&app);
+            let payload = events::WindowAutoHiddenPayload { idle_ms: idle.as_millis() as u64 };
+            send_event(&app, "window-auto-hidden", payload, Delivery::Reliable);
+        }
+    });
+}
+
+// Persist the window size if it changed
+fn remember_window_size<R: Runtime>(app: &tauri::AppHandle<R>, width: f64, height: f64) {
+    let size = WindowSize { width, height };
+    if app.state::<AppState>().config.lock().unwrap().window_size == Some(size) {
+        return;
+    }
+    if let Err(e) = update_config(app, |settings| settings.window_size = Some(size)) {
+        eprintln!("⚠️ Failed to remember window size: {}", e);
+    }
+}
+
+// Emit "window-resized" once things settle, replacing any pending emit so an
+// animation or a burst of resizes is reported once, with whatever size and
+// position the window ended up at after clamping. Every programmatic resize
+// is reported through here.
+fn emit_window_resized<R: Runtime>(app: &tauri::AppHandle<R>, reason: events::ResizeReason) {
+    let state = app.state::<AppState>();
+    let mut pending = state.pending_resize_event.lock().unwrap();
+    if let Some(previous) = pending.take() {
+        previous.abort();
+    }
+    
+    let app = app.clone();
+    *pending = Some(tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let Some(window) = app.get_webview_window("main") else {
+            return;
+        };
+        let (Ok(size), Ok(scale), Ok(position)) = (window.inner_size(), window.scale_factor(), window.outer_position()) else {
+            eprintln!("⚠️ Failed to read the window size after resizing");
+            return;
+        };
+        let logical = size.to_logical::<f64>(scale);
+        let payload = events::WindowResizedPayload {
+            width: logical.width,
+            height: logical.height,
+            physical_width: size.width,
+            physical_height: size.height,
+            x: position.x,
+            y: position.y,
+            reason,
+        };
+        let _ = app.emit("window-resized", payload);
+    }));
+}
+
+// Requests within the debounce interval of the last resize are held back and
+// only the newest is applied once it's over, so a burst still ends at the
+// final size
+// Returns the size after clamping to the minimum and the monitor.
+// Async because the frontend calls this as its content grows: clamping looks
+// up the monitor work area and applying moves and resizes the window, which
+// on the main thread would stall the UI the frontend is trying to lay out
+#[tauri::command]
+async fn resize_window<R: Runtime>(app: tauri::AppHandle<R>, width: f64, height: f64) -> Result<WindowSize, String> {
+    tauri::async_runtime::spawn_blocking(move || request_resize(app, width, height))
+        .await
+        .map_err(|e| format!("Failed to resize the window: {}", e))?
+}
+
+fn request_resize<R: Runtime>(app: tauri::AppHandle<R>, width: f64, height: f64) -> Result<WindowSize, String> {
+    touch_activity(&app);
+    let state = app.state::<AppState>();
+    let clamped = window_manager(&app)
+        .ok_or_else(|| "Window not found".to_string())?
+        .clamp(WindowSize { width, height });
+    let interval = Duration::from_millis(state.config.lock().unwrap().resize_debounce_ms);
+    let decision = state.resize_debounce.lock().unwrap().request((width, height), std::time::Instant::now(), interval);
+    match decision {
+        Debounced::Apply((width, height)) => apply_resize(&app, width, height),
+        Debounced::Schedule(wait) => {
+            println!("⏳ Resize deferred by {} ms", wait.as_millis());
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(wait).await;
+                let state = app.state::<AppState>();
+                let pending = state.resize_debounce.lock().unwrap().flush(std::time::Instant::now());
+                if let Some((width, height)) = pending {
+                    apply_resize(&app, width, height);
+                }
+            });
+        }
+        Debounced::Queued => {}
+    }
+    Ok(clamped)
+}
+
+fn apply_resize<R: Runtime>(app: &tauri::AppHandle<R>, width: f64, height: f64) {
+    let Some(manager) = window_manager(app) else {
+        return;
+    };
+    // Only resize if window is visible
+    if !manager.is_visible() {
+        return;
+    }
+    let current_size = manager.window().inner_size().unwrap_or(tauri::PhysicalSize::new(400, 300));
+    
+    // Only resize if the size actually changes significantly
+    let Some((new_width, new_height)) = resize_target(current_size, width, height, manager.limits().min_size()) else {
+        return;
+    };
+    let size = manager.clamp(WindowSize { width: new_width, height: new_height });
+    println!("📏 Resizing window: {}x{} -> {}x{}", current_size.width, current_size.height, size.width as i32, size.height as i32);
+    
+    let result = if app.state::<AppState>().window_pinned.load(Ordering::SeqCst) {
+        manager.resize_in_place(size)
+    } else {
+        manager.apply_size(size)
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to resize window: {:?}", e);
+        return;
+    }
+    emit_window_resized(app, events::ResizeReason::UserRequest);
+    remember_window_size(app, size.width, size.height);
+    emit_window_geometry(manager.window());
+}
+
+// Returns the size the window animates to after clamping. Async like
+// resize_window: it's called on every transcript update, and reading the
+// window and monitor geometry for the clamp shouldn't block other commands.
+#[tauri::command]
+async fn resize_and_position_window<R: Runtime>(app: tauri::AppHandle<R>, width: f64, height: f64) -> Result<WindowSize, String> {
+    tauri::async_runtime::spawn_blocking(move || position_and_resize(&app, width, height))
+        .await
+        .map_err(|e| format!("Failed to resize the window: {}", e))?
+}
+
+fn position_and_resize<R: Runtime>(app: &tauri::AppHandle<R>, width: f64, height: f64) -> Result<WindowSize, String> {
+    touch_activity(app);
+    let manager = window_manager(app).ok_or_else(|| "Window not found".to_string())?;
+    if overlay_active(app) {
+        println!("⏭️ Skipping resize - overlay mode is on");
+        return manager.size().ok_or_else(|| "Failed to read the window size".to_string());
+    }
+    let size = manager.clamp(WindowSize { width, height });
+    start_resize_animation(app, size, None, events::ResizeReason::UserRequest);
+    Ok(size)
+}
+
+// Switch to a preset from the window settings: "compact", "expanded" or
+// "fullscreen-overlay"
+#[tauri::command]
+fn set_window_mode<R: Runtime>(app: tauri::AppHandle<R>, mode: WindowMode) -> Result<(), String> {
+    if overlay_active(&app) {
+        return Err("Exit overlay mode first".to_string());
+    }
+    if !window_manager(&app).is_some_and(|manager| manager.is_visible()) {
+        return Err("The window isn't showing".to_string());
+    }
+    apply_window_mode(&app, mode)
+}
+
+// Kept for frontends using the compact/full API; the same as
+// set_window_mode with "compact" or "expanded"
+#[tauri::command]
+fn set_display_mode<R: Runtime>(app: tauri::AppHandle<R>, mode: DisplayMode) -> Result<(), String> {
+    set_window_mode(app, mode.into())
+}
+
+// Animate to the mode's geometry. Shared by the command and the first
+// transcript of a session.
+fn apply_window_mode<R: Runtime>(app: &tauri::AppHandle<R>, mode: WindowMode) -> Result<(), String> {
+    let manager = window_manager(app).ok_or_else(|| "Window not found".to_string())?;
+    let (size, position) = manager.mode_geometry(mode)?;
+    set_current_window_mode(app, mode);
+    start_resize_animation(app, size, position, events::ResizeReason::ModeChange);
+    Ok(())
+}
+
+fn set_current_window_mode<R: Runtime>(app: &tauri::AppHandle<R>, mode: WindowMode) {
+    let previous = std::mem::replace(&mut *app.state::<AppState>().current_window_mode.lock().unwrap(), mode);
+    if previous != mode {
+        println!("🪟 Window mode: {:?}", mode);
+        send_event(app, "window-mode-changed", events::WindowModeChangedPayload { mode }, Delivery::Reliable);
+        if let Some(mode) = DisplayMode::from_window_mode(mode) {
+            send_event(app, "display-mode-changed", events::DisplayModeChangedPayload { mode }, Delivery::Reliable);
+        }
+    }
+}
+
+// Where the window was and how it behaved before overlay mode
+struct OverlayRestore {
+    position: PhysicalPosition<i32>,
+    size: WindowSize,
+    mode: WindowMode,
+    always_on_top: bool,
+    ignore_cursor_events: bool,
+}
+
+fn overlay_active<R: Runtime>(app: &tauri::AppHandle<R>) -> bool {
+    app.state::<AppState>().overlay.lock().unwrap().is_some()
+}
+
+// Cover the work area of the window's monitor, on top of everything and
+// click-through except over interactive regions. The wake word won't move
+// the window while it's on.
+#[tauri::command]
+fn enter_overlay_mode<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    let manager = window_manager(&app).ok_or_else(|| "Window not found".to_string())?;
+    if !manager.is_visible() {
+        return Err("The window isn't showing".to_string());
+    }
+    let state = app.state::<AppState>();
+    let mut overlay = state.overlay.lock().unwrap();
+    if overlay.is_some() {
+        return Ok(());
+    }
+    let window = manager.window();
+    *overlay = Some(OverlayRestore {
+        position: window.outer_position().map_err(|e| e.to_string())?,
+        size: manager.size().ok_or_else(|| "Failed to read the window size".to_string())?,
+        mode: *state.current_window_mode.lock().unwrap(),
+        always_on_top: window.is_always_on_top().map_err(|e| e.to_string())?,
+        ignore_cursor_events: state.ignore_cursor_events.load(Ordering::SeqCst),
+    });
+    drop(overlay);
+    
+    let entered = window
+        .set_always_on_top(true)
+        .and_then(|_| apply_ignore_cursor_events(window, true))
+        .map_err(|e| e.to_string())
+        .and_then(|_| fit_overlay(&app, events::ResizeReason::ModeChange));
+    if let Err(e) = entered {
+        exit_overlay_mode(app.clone())?;
+        return Err(e);
+    }
+    set_current_window_mode(&app, WindowMode::FullscreenOverlay);
+    println!("🔲 Overlay mode on");
+    send_event(&app, "overlay-entered", events::OverlayEnteredPayload {}, Delivery::Reliable);
+    Ok(())
+}
+
+// Put the window back exactly as it was before enter_overlay_mode
+#[tauri::command]
+fn exit_overlay_mode<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    let Some(restore) = app.state::<AppState>().overlay.lock().unwrap().take() else {
+        return Ok(());
+    };
+    let manager = window_manager(&app).ok_or_else(|| "Window not found".to_string())?;
+    stop_resize_animation(&app);
+    let window = manager.window();
+    manager.resize_in_place(restore.size).map_err(|e| e.to_string())?;
+    manager.move_to(restore.position, restore.size).map_err(|e| e.to_string())?;
+    window.set_always_on_top(restore.always_on_top).map_err(|e| e.to_string())?;
+    apply_ignore_cursor_events(window, restore.ignore_cursor_events).map_err(|e| e.to_string())?;
+    set_current_window_mode(&app, restore.mode);
+    emit_window_resized(&app, events::ResizeReason::ModeChange);
+    println!("🔲 Overlay mode off");
+    send_event(&app, "overlay-exited", events::OverlayExitedPayload {}, Delivery::Reliable);
+    emit_window_geometry(window);
+    Ok(())
+}
+
+// Size the overlay to the work area of the monitor it's on, again whenever
+// the monitor layout changes
+fn fit_overlay<R: Runtime>(app: &tauri::AppHandle<R>, reason: events::ResizeReason) -> Result<(), String> {
+    let manager = window_manager(app).ok_or_else(|| "Window not found".to_string())?;
+    let work_area = manager.current_work_area().ok_or_else(|| "Failed to get monitor information".to_string())?;
+    stop_resize_animation(app);
+    let size = work_area.logical_size();
+    manager.resize_in_place(size).map_err(|e| e.to_string())?;
+    manager.move_to(work_area.position, size).map_err(|e| e.to_string())?;
+    emit_window_resized(app, reason);
+    emit_window_geometry(manager.window());
+    Ok(())
+}
+
+fn stop_resize_animation<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(animation) = app.state::<AppState>().resize_animation.lock().unwrap().take() {
+        animation.abort();
+    }
+}
+
+// Animate to `size` at `position`, or at the anchor (where the window is, if
+// it's pinned) for None
+fn start_resize_animation<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    size: WindowSize,
+    position: Option<PhysicalPosition<i32>>,
+    reason: events::ResizeReason,
+) {
+    let state = app.state::<AppState>();
+    let Some(manager) = window_manager(app) else {
+        eprintln!("❌ Window not found");
+        return;
+    };
+    // Only resize if window is visible
+    if !manager.is_visible() {
+        println!("⚠️ Window not visible, skipping resize");
+        return;
+    }
+    
+    let limits = *manager.limits();
+    // An explicit position comes with a size made to fit there (the overlay)
+    let size = match position {
+        Some(_) => WindowSize { width: size.width.max(limits.min_width), height: size.height.max(limits.min_height) },
+        None => manager.clamp(size),
+    };
+    let (new_width, new_height) = (size.width, size.height);
+    let current = manager.size().unwrap_or(limits.default_size());
+    
+    // A newer request replaces the animation in flight, so rapid requests end
+    // up at the last target instead of fighting over the window
+    let mut animation = state.resize_animation.lock().unwrap();
+    let superseded = match animation.take() {
+        Some(previous) if !previous.inner().is_finished() => {
+            previous.abort();
+            true
+        }
+        _ => false,
+    };
+    if !superseded && position.is_none() && (current.width - new_width).abs() <= 20.0 && (current.height - new_height).abs() <= 20.0 {
+        println!("⏭️ Skipping resize - size change too small");
+        return;
+    }
+    
+    println!("📏 Resizing and positioning window: {}x{} -> {}x{}", current.width as i32, current.height as i32, new_width as i32, new_height as i32);
+    let duration = Duration::from_millis(state.config.lock().unwrap().resize_animation_ms);
+    let target = WindowSize { width: new_width, height: new_height };
+    *animation = Some(tauri::async_runtime::spawn(animate_resize(app.clone(), manager, current, target, position, duration, reason)));
+}
+
+// About 60 animation steps per second
+const RESIZE_ANIMATION_TICK: Duration = Duration::from_millis(16);
+
+// Ease the window from `from` (its current logical size) and its current
+// position to the `to` size at `position`, or its anchored position
+async fn animate_resize<R: Runtime>(
+    app: tauri::AppHandle<R>,
+    manager: WindowManager<R>,
+    from: WindowSize,
+    to: WindowSize,
+    position: Option<PhysicalPosition<i32>>,
+    duration: Duration,
+    reason: events::ResizeReason,
+) {
+    let window = manager.window();
+    let pinned = app.state::<AppState>().window_pinned.load(Ordering::SeqCst);
+    let target = match (position, pinned, window.outer_position()) {
+        (Some(position), _, _) => Ok(position),
+        // A pinned window grows and shrinks where it is
+        (None, true, Ok(position)) => Ok(position),
+        _ => manager.anchored_position(to),
+    };
+    let target = match target {
+        Ok(position) => position,
+        Err(e) => {
+            eprintln!("❌ Failed to calculate new position: {}", e);
+            return;
+        }
+    };
+    let origin = window.outer_position().unwrap_or(target);
+    let start = std::time::Instant::now();
+    
+    loop {
+        let progress = if duration.is_zero() {
+            1.0
+        } else {
+            (start.elapsed().as_secs_f64() / duration.as_secs_f64()).min(1.0)
+        };
+        let eased = ease_out(progress);
+        let position = PhysicalPosition::new(
+            lerp(origin.x as f64, target.x as f64, eased).round() as i32,
+            lerp(origin.y as f64, target.y as f64, eased).round() as i32,
+        );
+        let size = WindowSize { width: lerp(from.width, to.width, eased), height: lerp(from.height, to.height, eased) };
+        
+        let step = manager
+            .move_to(position, size)
+            .and_then(|_| window.set_size(tauri::LogicalSize::new(size.width, size.height)));
+        if let Err(e) = step {
+            eprintln!("❌ Failed to resize window: {:?}", e);
+            return;
+        }
+        if progress >= 1.0 {
+            break;
+        }
+        tokio::time::sleep(RESIZE_ANIMATION_TICK).await;
+    }
+    
+    println!("✅ Window resized and positioned successfully");
+    emit_window_resized(&app, reason);
+    emit_window_geometry(window);
+    remember_window_size(&app, to.width, to.height);
+}
+
+// How long resize_and_position_window animates for; 0 resizes instantly
+#[tauri::command]
+fn set_resize_animation<R: Runtime>(app: tauri::AppHandle<R>, duration_ms: u64) -> Result<(), String> {
+    if duration_ms > MAX_RESIZE_ANIMATION_MS {
+        return Err(format!("Resize animation can be at most {} ms", MAX_RESIZE_ANIMATION_MS));
+    }
+    update_config(&app, |settings| settings.resize_animation_ms = duration_ms)
+}
+
+const MAX_RESIZE_ANIMATION_MS: u64 = 1000;
+
+#[tauri::command]
+fn set_resize_debounce<R: Runtime>(app: tauri::AppHandle<R>, interval_ms: u64) -> Result<(), String> {
+    if interval_ms > MAX_RESIZE_DEBOUNCE_MS {
+        return Err(format!("Resize debounce can be at most {} ms", MAX_RESIZE_DEBOUNCE_MS));
+    }
+    update_config(&app, |settings| settings.resize_debounce_ms = interval_ms)
+}
+
+const MAX_RESIZE_DEBOUNCE_MS: u64 = 1000;
+
+// Change the window margin, minimum and default sizes. A visible window is
+// re-clamped and moved to match right away.
+#[tauri::command]
+fn set_window_config<R: Runtime>(app: tauri::AppHandle<R>, config: WindowConfig) -> Result<(), String> {
+    let sizes = [
+        config.min_width,
+        config.min_height,
+        config.default_width,
+        config.default_height,
+        config.compact.width,
+        config.compact.height,
+        config.expanded.width,
+        config.expanded.height,
+    ];
+    if sizes.iter().any(|size| !size.is_finite() || *size <= 0.0) {
+        return Err("Window sizes must be positive numbers".to_string());
+    }
+    if config.default_width < config.min_width || config.default_height < config.min_height {
+        return Err("The default window size can't be below the minimum".to_string());
+    }
+    update_config(&app, |settings| settings.window = config)?;
+    println!("🪟 Window config set to {:?}", config);
+    
+    let Some(manager) = window_manager(&app).filter(|manager| manager.is_visible()) else {
+        return Ok(());
+    };
+    if let Some(size) = manager.size() {
+        let size = manager.clamp(size);
+        manager.apply_size(size).map_err(|e| e.to_string())?;
+        emit_window_resized(&app, events::ResizeReason::Clamp);
+        emit_window_geometry(manager.window());
+    }
+    Ok(())
+}
+
+// Shared by the command and the tray item so both stay in sync
+fn apply_do_not_disturb<R: Runtime>(app: &tauri::AppHandle<R>, enable: bool) {
+    let state = app.state::<AppState>();
+    state.do_not_disturb.store(enable, Ordering::SeqCst);
+    if let Some(sync_menu) = state.dnd_menu_sync.lock().unwrap().as_ref() {
+        sync_menu(enable);
+    }
+    
+    println!("{} Do not disturb {}", if enable { "🔕" } else { "🔔" }, if enable { "enabled" } else { "disabled" });
+    let event = if enable { "dnd-enabled" } else { "dnd-disabled" };
+    app.emit(event, events::DoNotDisturbPayload { enabled: enable }).unwrap();
+}
+
+#[tauri::command]
+fn set_do_not_disturb<R: Runtime>(app: tauri::AppHandle<R>, enable: bool) {
+    apply_do_not_disturb(&app, enable);
+}
+
+#[tauri::command]
+fn get_do_not_disturb(state: State<AppState>) -> bool {
+    state.do_not_disturb.load(Ordering::SeqCst)
+}
+
+#[tauri::command]
+fn get_autostart() -> Result<bool, AutostartError> {
+    autostart::is_enabled()
+}
+
+// Start Jackson at login; fails with kind "access_denied" where the account
+// may not change its startup programs
+#[tauri::command]
+fn set_autostart<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), AutostartError> {
+    apply_autostart(&app, enabled)
+}
+
+fn apply_autostart<R: Runtime>(app: &tauri::AppHandle<R>, enabled: bool) -> Result<(), AutostartError> {
+    let result = autostart::set_enabled(enabled);
+    // The check follows the registry, so a denied change leaves it as it was
+    let checked = if result.is_ok() { enabled } else { autostart::is_enabled().unwrap_or(!enabled) };
+    if let Some(sync_menu) = app.state::<AppState>().autostart_menu_sync.lock().unwrap().as_ref() {
+        sync_menu(checked);
+    }
+    match &result {
+        Ok(()) => println!("🚀 Autostart {}", if enabled { "enabled" } else { "disabled" }),
+        Err(e) => eprintln!("❌ {}", e.message),
+    }
+    result
+}
+
+// Whether the frontend starts wake word detection once it has loaded
+#[tauri::command]
+fn get_listen_on_startup(state: State<AppState>) -> bool {
+    state.config.lock().unwrap().listen_on_startup
+}
+
+#[tauri::command]
+fn set_listen_on_startup<R: Runtime>(app: tauri::AppHandle<R>, enabled: bool) -> Result<(), String> {
+    update_config(&app, |settings| settings.listen_on_startup = enabled)
+}
+
+// The app in the foreground, for routing commands by app. Sync on purpose:
+// it's a few Win32 queries with no I/O.
+#[tauri::command]
+fn get_focused_app() -> Result<context::FocusedAppInfo, String> {
+    context::focused_app().ok_or_else(|| "No window is in the foreground".to_string())
+}
+
+#[tauri::command]
+fn get_system_theme() -> theme::SystemTheme {
+    theme::detect_system_theme()
+}
+
+#[tauri::command]
+fn get_system_volume() -> Result<volume::SystemVolumeInfo, String> {
+    volume::system_volume().map_err(|e| e.to_string())
+}
+
+// Stop acting on the microphone without closing the device, e.g. while a
+// reply is spoken: captured audio stops reaching the backends and whatever
+// SAPI or the frontend still recognizes is dropped
+#[tauri::command]
+fn pause_microphone<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    apply_microphone_paused(&app, true)
+}
+
+#[tauri::command]
+fn resume_microphone<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    apply_microphone_paused(&app, false)
+}
+
+// Shared by the commands and the tray item so both stay in sync
+fn apply_microphone_paused<R: Runtime>(app: &tauri::AppHandle<R>, paused: bool) -> Result<(), String> {
+    with_detector(app, |detector| {
+        if paused {
+            detector.pause_microphone();
+        } else {
+            detector.resume_microphone();
+        }
+        Ok(())
+    })?;
+    sync_microphone_paused(app, paused);
+    if paused {
+        send_event(app, "microphone-paused", events::MicrophonePausedPayload {}, Delivery::Reliable);
+    } else {
+        send_event(app, "microphone-resumed", events::MicrophoneResumedPayload {}, Delivery::Reliable);
+    }
+    Ok(())
+}
+
+fn sync_microphone_paused<R: Runtime>(app: &tauri::AppHandle<R>, paused: bool) {
+    let state = app.state::<AppState>();
+    state.microphone_paused.store(paused, Ordering::SeqCst);
+    state.session.set_microphone_paused(paused);
+    let mic_menu_sync = state.mic_menu_sync.lock().unwrap();
+    if let Some(sync_menu) = mic_menu_sync.as_ref() {
+        sync_menu(paused);
+    }
+    drop(mic_menu_sync);
+    refresh_tray_status(app);
+}
+
+// Tray status line and listening action for a detector state
+fn tray_listening_labels(state: Option<wake_word::DetectorState>) -> (String, &'static str) {
+    use wake_word::DetectorState;
+    let status = match state {
+        None => "Speech recognition unavailable".to_string(),
+        Some(DetectorState::Idle) => "Not listening".to_string(),
+        Some(DetectorState::Starting) => "Starting…".to_string(),
+        Some(DetectorState::Listening) => format!("Listening for '{}'", wake_word::WAKE_PHRASE),
+        Some(DetectorState::Retrying) => "Reconnecting to speech recognition…".to_string(),
+        Some(DetectorState::Error) => "Speech recognition stopped".to_string(),
+    };
+    let action = if state.is_some_and(|state| state.is_active()) { "Pause Listening" } else { "Start Listening" };
+    (status, action)
+}
+
+// Follows the detector's state changes so the tray can't disagree with it
+fn on_detector_state_changed<R: Runtime>(app: &tauri::AppHandle<R>, detector_state: wake_word::DetectorState) {
+    *app.state::<AppState>().detector_state.lock().unwrap() = Some(detector_state);
+    // The devices are picked up each time listening starts
+    if detector_state == wake_word::DetectorState::Starting {
+        let devices = app.state::<AppState>().config.lock().unwrap().input_devices.clone();
+        let microphone = microphone_label(&devices);
+        update_tray_display(app, |display| display.microphone = microphone);
+    }
+    // A stopped capture takes its pause with it
+    if !detector_state.is_active() {
+        sync_microphone_paused(app, false);
+    }
+    if let Some(sync_menu) = app.state::<AppState>().listening_menu_sync.lock().unwrap().as_ref() {
+        sync_menu(Some(detector_state));
+    }
+    refresh_tray_status(app);
+}
+
+// Start or stop listening, for the tray item and the hotkey, through the same
+// paths as the commands. A detector that failed at startup is retried first.
+fn toggle_listening<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Err(e) = ensure_detector(app) {
+        report_listening_toggled(app, true, Err(e));
+        return;
+    }
+    let state = app.state::<AppState>();
+    let active = state.wake_word_detector.lock().unwrap().as_ref().is_some_and(|detector| detector.state().is_active());
+    if active {
+        let result = stop_wake_word_detection(state);
+        report_listening_toggled(app, false, result);
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = start_wake_word_detection(app.clone(), app.state()).await;
+        report_listening_toggled(&app, true, result);
+    });
+}
+
+// "listening-toggled" says which way it went, so the frontend can flash it
+fn report_listening_toggled<R: Runtime>(app: &tauri::AppHandle<R>, start: bool, result: Result<(), String>) {
+    let action = if start { "start" } else { "stop" };
+    let payload = match result {
+        Ok(()) => {
+            println!("{} Listening {}", if start { "👂" } else { "🙉" }, if start { "started" } else { "stopped" });
+            events::ListeningToggledPayload { listening: start, error: None }
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to {} listening: {}", action, e);
+            events::ListeningToggledPayload { listening: !start, error: Some(e) }
+        }
+    };
+    send_event(app, "listening-toggled", payload, Delivery::Reliable);
+}
+
+// Set up the wake word detector if it failed at startup, e.g. because speech
+// recognition has been installed since
+fn ensure_detector<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut detector_guard = state.wake_word_detector.lock().unwrap();
+    if detector_guard.is_some() {
+        return Ok(());
+    }
+    let config = state.config.lock().unwrap().clone();
+    // Once only: this runs on a click or hotkey, and trying again is one away
+    let mut detector = create_detector(app, &config, 1).map_err(|e| format!("Failed to initialize wake word detector: {}", e))?;
+    attach_detector(&mut detector, &state.do_not_disturb, &state.session, &state.echo, &config);
+    *detector_guard = Some(detector);
+    drop(detector_guard);
+    println!("✅ Wake word detector initialized on demand");
+    
+    let detector_state = Some(wake_word::DetectorState::Idle);
+    *state.detector_state.lock().unwrap() = detector_state;
+    if let Some(sync_menu) = state.listening_menu_sync.lock().unwrap().as_ref() {
+        sync_menu(detector_state);
+    }
+    refresh_tray_status(app);
+    Ok(())
+}
+
+// A detector set up from the settings, or an error if SAPI isn't usable
+// SAPI initialization at startup is tried this often, waiting 500 ms, then
+// 1 s; the device can be briefly busy while Windows logs in
+const STARTUP_DETECTOR_ATTEMPTS: u32 = 3;
+const DETECTOR_RETRY_DELAY_MS: u64 = 500;
+
+fn create_detector<R: Runtime>(app: &tauri::AppHandle<R>, config: &AppConfig, attempts: u32) -> anyhow::Result<WakeWordDetector> {
+    let detector = WakeWordDetector::new_with_retry(attempts, DETECTOR_RETRY_DELAY_MS)?;
+    match speech_recognition::create_backend(&config.recognition_backend, &backend_options(app, config)) {
+        Ok(backend) => detector.set_backend(backend),
+        Err(e) => eprintln!("⚠️ {}, using {}", e, detector.backend_name()),
+    }
+    detector.set_endpointing(&config.endpointing);
+    detector.set_min_wake_energy(config.min_wake_energy_rms);
+    detector.set_agc(config.agc_enabled.then_some(config.agc_target_rms));
+    detector.set_energy_gate(config.energy_gate_rms());
+    detector.set_input_devices(config.input_devices.clone());
+    detector.set_channel_routing(config.channel_routing.clone());
+    detector.set_context_grammars(context::default_grammars());
+    Ok(detector)
+}
+
+// Connect the detector to the app's shared flags and session
+fn attach_detector(detector: &mut WakeWordDetector, do_not_disturb: &Arc<AtomicBool>, session: &SessionHandle, echo: &EchoMonitor, config: &AppConfig) {
+    detector.set_do_not_disturb_flag(Arc::clone(do_not_disturb));
+    detector.set_session_handle(session.clone());
+    detector.set_echo_monitor(echo.clone());
+    detector.set_vocabulary(&config.vocabulary);
+    detector.set_record_utterance_audio(config.records_utterance_audio());
+}
+
+fn with_detector<R: Runtime>(app: &tauri::AppHandle<R>, action: impl FnOnce(&WakeWordDetector) -> anyhow::Result<()>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let detector_guard = state.wake_word_detector.lock().unwrap();
+    let detector = detector_guard
+        .as_ref()
+        .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+    action(detector).map_err(|e| e.to_string())
+}
+
+// Mute or unmute the default output device, e.g. before speaking a reply
+#[tauri::command]
+fn set_system_mute(muted: bool) -> Result<(), String> {
+    volume::set_mute(muted).map_err(|e| e.to_string())?;
+    println!("🔈 System output {}", if muted { "muted" } else { "unmuted" });
+    Ok(())
+}
+
+#[tauri::command]
+fn set_ignore_cursor_events<R: Runtime>(app: tauri::AppHandle<R>, ignore: bool) {
+    if let Some(window) = app.get_webview_window("main") {
+        apply_ignore_cursor_events(&window, ignore).unwrap_or_else(|e| {
+            eprintln!("Failed to set ignore cursor events: {:?}", e);
+        });
+    }
+}
+
+fn apply_ignore_cursor_events<R: Runtime>(window: &tauri::WebviewWindow<R>, ignore: bool) -> tauri::Result<()> {
+    window.set_ignore_cursor_events(ignore)?;
+    window.state::<AppState>().ignore_cursor_events.store(ignore, Ordering::SeqCst);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_window_info<R: Runtime>(app: tauri::AppHandle<R>) -> Result<events::WindowInfo, String> {
+    let window = app.get_webview_window("main").ok_or_else(|| "Window not found".to_string())?;
+    window_info(&window).map_err(|e| e.to_string())
+}
+
+fn window_info<R: Runtime>(window: &tauri::WebviewWindow<R>) -> tauri::Result<events::WindowInfo> {
+    let size = window.inner_size()?;
+    let position = window.outer_position()?;
+    let monitor = window.current_monitor()?;
+    Ok(events::WindowInfo {
+        width: size.width,
+        height: size.height,
+        x: position.x,
+        y: position.y,
+        visible: window.is_visible()?,
+        focused: window.is_focused()?,
+        monitor_name: monitor.as_ref().and_then(|monitor| monitor.name().cloned()),
+        scale_factor: window.scale_factor()?,
+        ignore_cursor_events: window.state::<AppState>().ignore_cursor_events.load(Ordering::SeqCst),
+    })
+}
+
+// Tell the frontend where the window is after the backend moved, resized,
+// showed or hid it, so it doesn't have to poll
+fn emit_window_geometry<R: Runtime>(window: &tauri::WebviewWindow<R>) {
+    match window_info(window) {
+        Ok(info) => send_event(window.app_handle(), "window-geometry-changed", info, Delivery::Latest),
+        Err(e) => eprintln!("⚠️ Failed to read window geometry: {:?}", e),
+    }
+}
+
+// Make the window click-through except while the cursor is over it
+#[tauri::command]
+fn enable_hover_interaction<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    update_config(&app, |settings| settings.hover_interaction = true)?;
+    start_hover_watcher(&app);
+    Ok(())
+}
+
+// Stop following the cursor and leave the window interactive
+#[tauri::command]
+fn disable_hover_interaction<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    update_config(&app, |settings| settings.hover_interaction = false)?;
+    if let Some(watcher) = app.state::<AppState>().hover_watcher.lock().unwrap().take() {
+        watcher.abort();
+    }
+    set_ignore_cursor_events(app, false);
+    println!("🖱️ Hover interaction disabled");
+    Ok(())
+}
+
+// How often the cursor is checked against the window while hover interaction is on
+const HOVER_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Tauri has no cursor enter/leave window events, and a click-through window
+// gets no mouse input at all, so the cursor is polled against the window bounds
+fn start_hover_watcher<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let state = app.state::<AppState>();
+    let mut watcher = state.hover_watcher.lock().unwrap();
+    if watcher.is_some() {
+        return;
+    }
+    
+    let app = app.clone();
+    *watcher = Some(tauri::async_runtime::spawn(async move {
+        let mut hovered = None;
+        loop {
+            // Interactive regions are finer grained and take over while set,
+            // and the overlay stays click-through outside them
+            if !app.state::<AppState>().interactive_regions.lock().unwrap().is_empty() || overlay_active(&app) {
+                hovered = None;
+            } else if let Some(window) = app.get_webview_window("main") {
+                let inside = match (window.cursor_position(), window.outer_position(), window.outer_size()) {
+                    (Ok(cursor), Ok(position), Ok(size)) => {
+                        cursor.x >= position.x as f64
+                            && cursor.y >= position.y as f64
+                            && cursor.x < position.x as f64 + size.width as f64
+                            && cursor.y < position.y as f64 + size.height as f64
+                    }
+                    _ => false,
+                };
+                if hovered != Some(inside) {
+                    hovered = Some(inside);
+                    let _ = apply_ignore_cursor_events(&window, !inside);
+                }
+            }
+            tokio::time::sleep(HOVER_POLL_INTERVAL).await;
+        }
+    }));
+    println!("🖱️ Hover interaction enabled");
+}
+
+// Only take mouse input over these rectangles (logical pixels, relative to the
+// client area) while the window is visible; an empty list goes back to
+// whole-window behaviour. Regions are cleared when the window hides.
+#[tauri::command]
+fn set_interactive_regions<R: Runtime>(app: tauri::AppHandle<R>, rects: Vec<regions::Rect>) {
+    let state = app.state::<AppState>();
+    let empty = rects.is_empty();
+    state.interactive_regions.lock().unwrap().set(rects);
+    if empty {
+        restore_click_through(&app);
+    } else {
+        start_region_watcher(&app);
+    }
+}
+
+// Back to the click-through window the app starts with; the hover watcher
+// picks it up from here if it's on
+fn restore_click_through<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = apply_ignore_cursor_events(&window, true);
+    }
+}
+
+// Polls the cursor like the hover watcher, but against the interactive
+// regions. Stops once the regions are cleared.
+fn start_region_watcher<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let state = app.state::<AppState>();
+    let mut watcher = state.region_watcher.lock().unwrap();
+    if watcher.as_ref().is_some_and(|watcher| !watcher.inner().is_finished()) {
+        return;
+    }
+    
+    let app = app.clone();
+    *watcher = Some(tauri::async_runtime::spawn(async move {
+        while poll_interactive_regions(&app) {
+            tokio::time::sleep(HOVER_POLL_INTERVAL).await;
+        }
+    }));
+}
+
+// One check of the cursor against the regions; false once there's nothing left to watch
+fn poll_interactive_regions<R: Runtime>(app: &tauri::AppHandle<R>) -> bool {
+    let Some(window) = app.get_webview_window("main") else {
+        return false;
+    };
+    let state = app.state::<AppState>();
+    let mut regions = state.interactive_regions.lock().unwrap();
+    if regions.is_empty() {
+        return false;
+    }
+    if !window.is_visible().unwrap_or(false) {
+        regions.clear();
+        drop(regions);
+        restore_click_through(app);
+        println!("🖱️ Interactive regions cleared, window hidden");
+        return false;
+    }
+    if let (Ok(cursor), Ok(position), Ok(scale)) = (window.cursor_position(), window.inner_position(), window.scale_factor()) {
+        let x = (cursor.x - position.x as f64) / scale;
+        let y = (cursor.y - position.y as f64) / scale;
+        if let Some(interactive) = regions.update(x, y) {
+            let _ = apply_ignore_cursor_events(&window, !interactive);
+        }
+    }
+    true
+}
+
+// The frontend calls this once its listeners are attached, at startup and
+// after every reload; events queued in the meantime are delivered then
+#[tauri::command]
+fn frontend_ready(state: State<AppState>) {
+    state.outbound.lock().unwrap().set_ready(true);
+    state.outbound_ready.notify_one();
+    println!("📬 Frontend ready");
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+fn simulate_wake_word(keyword_index: usize, state: State<AppState>) -> Result<(), String> {
+    let detector_guard = state.wake_word_detector.lock().unwrap();
+    let detector = detector_guard
+        .as_ref()
+        .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+    detector.simulate_wake_word(keyword_index).map_err(|e| e.to_string())
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+fn simulate_speech(text: String, state: State<AppState>) -> Result<(), String> {
+    let detector_guard = state.wake_word_detector.lock().unwrap();
+    let detector = detector_guard
+        .as_ref()
+        .ok_or_else(|| "Wake word detector not initialized".to_string())?;
+    detector.simulate_speech(&text).map_err(|e| e.to_string())
+}
+
+// Every command with a short description, for debugging and test harnesses
+#[tauri::command]
+fn list_commands() -> Vec<commands::CommandInfo> {
+    commands::list()
+}
+
+const SETTINGS_WINDOW: &str = "settings";
+const SETTINGS_WINDOW_SIZE: (f64, f64) = (720.0, 560.0);
+
+// Open the settings window, or bring it forward if it's already open. Unlike
+// the main window it's an ordinary framed window that takes mouse input.
+// Async because creating a window from a synchronous command deadlocks on
+// Windows.
+#[tauri::command]
+async fn open_settings_window<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    open_settings(&app)
+}
+
+fn open_settings<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    // Held until the window exists, so two quick calls can't both create one
+    let state = app.state::<AppState>();
+    let _opening = state.settings_window_opening.lock().unwrap();
+    if let Some(window) = app.get_webview_window(SETTINGS_WINDOW) {
+        let _ = window.unminimize();
+        window.show().and_then(|_| window.set_focus()).map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    
+    let (width, height) = SETTINGS_WINDOW_SIZE;
+    let url = tauri::WebviewUrl::App("index.html?window=settings".into());
+    let window = tauri::WebviewWindowBuilder::new(app, SETTINGS_WINDOW, url)
+        .title("Jackson Settings")
+        .inner_size(width, height)
+        .decorations(true)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to open the settings window: {}", e))?;
+    center_on_active_monitor(&window).unwrap_or_else(|e| eprintln!("⚠️ Failed to center the settings window: {:?}", e));
+    window.show().and_then(|_| window.set_focus()).map_err(|e| e.to_string())?;
+    println!("⚙️ Settings window opened");
+    Ok(())
+}
+
+// The monitor with the cursor, or the primary one
+fn center_on_active_monitor<R: Runtime>(window: &tauri::WebviewWindow<R>) -> tauri::Result<()> {
+    let active = match window.cursor_position() {
+        Ok(cursor) => window.monitor_from_point(cursor.x, cursor.y)?,
+        Err(_) => None,
+    };
+    let Some(monitor) = active.or(window.primary_monitor()?) else {
+        return Ok(());
+    };
+    let area = monitor.work_area();
+    let size = window.outer_size()?;
+    let x = area.position.x + (area.size.width as i32 - size.width as i32).max(0) / 2;
+    let y = area.position.y + (area.size.height as i32 - size.height as i32).max(0) / 2;
+    window.set_position(PhysicalPosition::new(x, y))
+}
+
+// Closing it only closes the window; everything it changed lives in AppState
+#[tauri::command]
+fn close_settings_window<R: Runtime>(app: tauri::AppHandle<R>) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(SETTINGS_WINDOW) {
+        window.close().map_err(|e| e.to_string())?;
+        println!("⚙️ Settings window closed");
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn quit_app<R: Runtime>(app: tauri::AppHandle<R>) {
+    println!("Quit app command called");
+    shut_down(&app);
+    app.exit(0);
+}
+
+// Stop the detector and audio and save settings before exiting. Quitting and
+// the run loop's exit events (closing from the OS) both come through here.
+fn shut_down<R: Runtime>(app: &tauri::AppHandle<R>) {
+    let shutting_down = Arc::clone(&app.state::<AppState>().shutting_down);
+    shutdown::run(&shutting_down, &mut AppShutdown { app });
+}
+
+struct AppShutdown<'a, R: Runtime> {
+    app: &'a tauri::AppHandle<R>,
+}
+
+impl<R: Runtime> shutdown::ShutdownSteps for AppShutdown<'_, R> {
+    fn notify(&mut self) {
+        let _ = self.app.emit("app-shutting-down", events::AppShuttingDownPayload {});
+    }
+
+    fn stop_detection(&mut self) {
+        let state = self.app.state::<AppState>();
+        state.session.close();
+        let detector = state.wake_word_detector.lock().unwrap();
+        if let Some(detector) = detector.as_ref() {
+            detector.request_stop();
+        }
+    }
+
+    fn stop_audio(&mut self) {
+        let state = self.app.state::<AppState>();
+        if let Some(detector) = state.wake_word_detector.lock().unwrap().as_ref() {
+            detector.stop_capture();
+        }
+        let playback = state.playback.lock().unwrap().take();
+        drop(playback);
+    }
+
+    // A detector whose thread finished is dropped, which finalizes SAPI; one
+    // that's still recognizing is left alone rather than finalized under it
+    fn join_workers(&mut self, timeout: Duration) -> bool {
+        let state = self.app.state::<AppState>();
+        let finished = state
+            .wake_word_detector
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(true, |detector| detector.join_listener_within(timeout));
+        if finished {
+            let detector = state.wake_word_detector.lock().unwrap().take();
+            drop(detector);
+        }
+        finished
+    }
+
+    // Transcript history is only kept in memory; the config carries
+    // everything else, the window position included
+    fn persist(&mut self) {
+        let state = self.app.state::<AppState>();
+        let config = state.config.lock().unwrap();
+        if let Err(e) = config::save(self.app, &config) {
+            eprintln!("⚠️ Failed to save settings: {}", e);
+        }
+    }
+}
+
+// Put AppState under management and start the session orchestrator. The
+// detector is None when SAPI failed to initialize (and in tests).
+pub fn manage_state<R: Runtime>(app: &tauri::AppHandle<R>, config: AppConfig, mut detector: Option<WakeWordDetector>) {
+    let postprocess_config = PostProcessConfig {
+        profanity_filter: config.profanity_filter,
+        custom_profanity: config.custom_profanity.clone(),
+        vocabulary: config.vocabulary.clone(),
+        ..PostProcessConfig::default()
+    };
+    let do_not_disturb = Arc::new(AtomicBool::new(false));
+    let (session, session_receiver) = session::channel();
+    let earcon_volume = config.earcon_volume;
+    let idle_opacity = config.idle_opacity;
+    let session_analytics = analytics::SessionAnalyticsTracker::new(config.session_history_size);
+    let speech_timeout = session::timeout_from_secs(config.speech_timeout_secs);
+    let detector_state = detector.as_ref().map(|detector| detector.state());
+    let echo = EchoMonitor::new();
+    
+    if let Some(detector) = detector.as_mut() {
+        attach_detector(detector, &do_not_disturb, &session, &echo, &config);
+    }
+    
+    app.manage(AppState {
+        wake_word_detector: Arc::new(Mutex::new(detector)),
+        config: Arc::new(Mutex::new(config)),
+        resize_debounce: Arc::new(Mutex::new(Debouncer::new())),
+        speech_metrics: Arc::new(Mutex::new(SpeechMetricsTracker::new(metrics::DEFAULT_PAUSE_THRESHOLD))),
+        speech_session_active: Arc::new(AtomicBool::new(false)),
+        postprocess_config: Arc::new(Mutex::new(postprocess_config)),
+        do_not_disturb,
+        dnd_menu_sync: Arc::new(Mutex::new(None)),
+        on_top_menu_sync: Arc::new(Mutex::new(None)),
+        pin_menu_sync: Arc::new(Mutex::new(None)),
+        listening_menu_sync: Arc::new(Mutex::new(None)),
+        mic_menu_sync: Arc::new(Mutex::new(None)),
+        autostart_menu_sync: Arc::new(Mutex::new(None)),
+        launched_at_login: autostart::launched_at_login(),
+        shutting_down: Arc::new(AtomicBool::new(false)),
+        microphone_paused: Arc::new(AtomicBool::new(false)),
+        detector_state: Arc::new(Mutex::new(detector_state)),
+        tray_display: Arc::new(Mutex::new(TrayDisplay {
+            status: TrayStatus::from_state(detector_state, false, false),
+            hotkeys: None,
+            microphone: None,
+        })),
+        tray_status_debounce: Arc::new(Mutex::new(Debouncer::new())),
+        work_area_cache: Arc::new(Mutex::new(None)),
+        programmatic_moves: Arc::new(Mutex::new(None)),
+        monitor_info_failures: Arc::new(AtomicU64::new(0)),
+        display_missing: Arc::new(AtomicBool::new(false)),
+        transcripts: Arc::new(Mutex::new(TranscriptHistory::new(history::DEFAULT_CAPACITY))),
+        last_transcription: Arc::new(Mutex::new(None)),
+        training_phrases: Arc::new(Mutex::new(Vec::new())),
+        training_recording: Arc::new(AtomicBool::new(false)),
+        pending_resize_event: Arc::new(Mutex::new(None)),
+        resize_animation: Arc::new(Mutex::new(None)),
+        current_window_mode: Arc::new(Mutex::new(WindowMode::default())),
+        overlay: Arc::new(Mutex::new(None)),
+        window_opacity: Arc::new(Mutex::new(idle_opacity)),
+        window_fade_level: Arc::new(Mutex::new(1.0)),
+        window_fade: Arc::new(Mutex::new(None)),
+        hover_watcher: Arc::new(Mutex::new(None)),
+        interactive_regions: Arc::new(Mutex::new(regions::InteractiveRegions::default())),
+        region_watcher: Arc::new(Mutex::new(None)),
+        ignore_cursor_events: Arc::new(AtomicBool::new(false)),
+        window_pinned: Arc::new(AtomicBool::new(false)),
+        auto_hide_timer: Arc::new(Mutex::new(None)),
+        last_activity: Arc::new(Mutex::new(std::time::Instant::now())),
+        playback: Arc::new(Mutex::new(None)),
+        echo,
+        earcons: Arc::new(Mutex::new(EarconManager::new(earcon_volume))),
+        session,
+        intents: Arc::new(Mutex::new(IntentRegistry::new())),
+        session_analytics: Arc::new(Mutex::new(session_analytics)),
+        utterance_audio: Arc::new(Mutex::new(UtteranceAudioStore::new(utterances::DEFAULT_MAX_STORED_AUDIO))),
+        outbound: Arc::new(Mutex::new(OutboundQueue::new(outbound::DEFAULT_RELIABLE_CAP))),
+        outbound_ready: Arc::new(tokio::sync::Notify::new()),
+        settings_window_opening: Arc::new(Mutex::new(())),
+    });
+    
+    // Deliver queued events whenever something is pushed or the frontend
+    // becomes ready
+    let flush_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = flush_app.state::<AppState>();
+        let notify = Arc::clone(&state.outbound_ready);
+        loop {
+            notify.notified().await;
+            let events = state.outbound.lock().unwrap().drain();
+            for (event, payload) in events {
+                let _ = flush_app.emit(&event, payload);
+            }
+        }
+    });
+    
+    let orchestrator = SessionOrchestrator::new(
+        session_receiver,
+        AppSessionSink { app: app.clone() },
+        speech_timeout,
+        session::DEFAULT_STOP_PHRASES.iter().map(|phrase| phrase.to_string()).collect(),
+    );
+    tauri::async_runtime::spawn(orchestrator.run());
+}
+
+// Every command the frontend can invoke. Keep commands.rs in sync.
+pub fn invoke_handler<R: Runtime>() -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        start_wake_word_detection,
+        stop_wake_word_detection,
+        get_wake_word_state,
+        report_transcript,
+        get_recognized_alternatives,
+        get_transcripts,
+        clear_transcripts,
+        set_store_utterance_audio,
+        set_save_utterance_audio,
+        get_utterance_audio,
+        play_utterance_audio,
+        set_earcons,
+        test_earcon,
+        get_backend_chain,
+        set_backend_chain,
+        get_speech_metrics,
+        check_sapi_health,
+        get_sapi_stats,
+        reset_sapi_stats,
+        open_speech_training,
+        record_training_phrase,
+        get_training_phrases,
+        submit_training_phrases,
+        get_session_history,
+        clear_session_history,
+        set_session_history_size,
+        get_speech_timeout,
+        set_speech_timeout,
+        set_pause_threshold,
+        set_recognition_backend,
+        set_speech_backend,
+        list_available_models,
+        download_model,
+        set_active_model,
+        start_number_dictation,
+        stop_number_dictation,
+        list_input_devices,
+        set_window_monitor,
+        list_monitors,
+        set_display,
+        set_preferred_monitor,
+        ensure_window_visible,
+        reset_window_position,
+        set_window_anchor,
+        set_input_devices,
+        set_channel_routing,
+        set_endpointing_config,
+        get_app_status,
+        get_app_health,
+        capture_window_screenshot,
+        open_bug_report,
+        calibrate_noise_floor,
+        set_agc,
+        set_energy_gate_ratio,
+        get_noise_floor,
+        set_output_playing,
+        get_echo_suppression_status,
+        get_postprocess_config,
+        set_postprocess_config,
+        set_profanity_filter,
+        set_custom_profanity,
+        list_vocabulary,
+        add_vocabulary_phrase,
+        remove_vocabulary_phrase,
+        register_intent,
+        test_command_match,
+        set_recognition_constraints,
+        clear_recognition_constraints,
+        hide_window,
+        show_window,
+        quit_app,
+        resize_window,
+        resize_and_position_window,
+        set_window_mode,
+        set_display_mode,
+        enter_overlay_mode,
+        exit_overlay_mode,
+        set_resize_animation,
+        set_resize_debounce,
+        set_window_config,
+        set_ignore_cursor_events,
+        get_window_info,
+        enable_hover_interaction,
+        disable_hover_interaction,
+        set_interactive_regions,
+        set_do_not_disturb,
+        get_do_not_disturb,
+        set_always_on_top,
+        set_show_without_focus,
+        set_tray_adjacent,
+        get_autostart,
+        set_autostart,
+        get_listen_on_startup,
+        set_listen_on_startup,
+        set_window_opacity,
+        set_window_corner_style,
+        set_window_shadow,
+        set_window_fade,
+        set_opacity_levels,
+        set_auto_hide_on_blur,
+        set_window_pinned,
+        set_hotkeys,
+        set_idle_hide,
+        frontend_heartbeat,
+        get_focused_app,
+        get_system_theme,
+        get_system_volume,
+        set_system_mute,
+        pause_microphone,
+        resume_microphone,
+        list_commands,
+        frontend_ready,
+        open_settings_window,
+        close_settings_window,
+        #[cfg(debug_assertions)]
+        simulate_wake_word,
+        #[cfg(debug_assertions)]
+        simulate_speech,
+    ]
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-  tauri::Builder::default()
-    .setup(|app| {
-      if cfg!(debug_assertions) {
-        app.handle().plugin(
-          tauri_plugin_log::Builder::default()
-            .level(log::LevelFilter::Info)
-            .build(),
-        )?;
-      }
-      Ok(())
-    })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    tauri::Builder::default()
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        // A reload detaches the frontend's listeners; hold events until it
+        // calls frontend_ready again. The settings window coming and going
+        // doesn't hold anything up.
+        .on_page_load(|webview, payload| {
+            if webview.label() == "main" && payload.event() == PageLoadEvent::Started {
+                webview.state::<AppState>().outbound.lock().unwrap().set_ready(false);
+            }
+        })
+        .on_window_event(|window, event| {
+            if window.label() != "main" {
+                return;
+            }
+            match event {
+                tauri::WindowEvent::Focused(true) => cancel_auto_hide(window.app_handle()),
+                tauri::WindowEvent::Focused(false) => schedule_auto_hide(window.app_handle()),
+                tauri::WindowEvent::Moved(position) => note_window_moved(window.app_handle(), *position),
+                _ => {}
+            }
+        })
+        .setup(|app| {
+            let config = config::load(app.handle());
+            
+            // A missing or broken SAPI install otherwise only shows up as a generic COM failure
+            let sapi = sapi_health::check();
+            if let Some(error) = &sapi.error {
+                eprintln!("❌ Speech recognition unavailable: {}", error);
+                eprintln!("❌ SAPI health: {:?}", sapi);
+            }
+            
+            let detector = match create_detector(app.handle(), &config, STARTUP_DETECTOR_ATTEMPTS) {
+                Ok(detector) => Some(detector),
+                Err(e) => {
+                    eprintln!("❌ Failed to initialize wake word detector: {}", e);
+                    eprintln!("❌ Error details: {:?}", e);
+                    None
+                }
+            };
+            
+            let always_on_top = config.always_on_top;
+            let corner_style = config.corner_style;
+            let window_shadow = config.window_shadow;
+            let hotkeys = config.hotkeys.clone();
+            let detector_state = detector.as_ref().map(|detector| detector.state());
+            manage_state(app.handle(), config, detector);
+            
+            // Create system tray menu with proper IDs
+            let (status, action) = tray_listening_labels(detector_state);
+            let listening_status_item = MenuItem::with_id(app, "listening_status", status, false, None::<&str>).unwrap();
+            let toggle_listening_item = MenuItem::with_id(app, "toggle_listening", action, true, None::<&str>).unwrap();
+            let mute_mic_item = CheckMenuItem::with_id(app, "mute_mic", "Mute Microphone", false, false, None::<&str>).unwrap();
+            let show_item = MenuItem::with_id(app, "show", "Show", true, None::<&str>).unwrap();
+            let hide_item = MenuItem::with_id(app, "hide", "Hide", true, None::<&str>).unwrap();
+            let dnd_item = CheckMenuItem::with_id(app, "dnd", "Do Not Disturb", true, false, None::<&str>).unwrap();
+            let on_top_item = CheckMenuItem::with_id(app, "on_top", "Always on Top", true, always_on_top, None::<&str>).unwrap();
+            let autostart_enabled = autostart::is_enabled().unwrap_or_else(|e| {
+                eprintln!("⚠️ {}", e.message);
+                false
+            });
+            let autostart_item = CheckMenuItem::with_id(app, "autostart", "Start with Windows", true, autostart_enabled, None::<&str>).unwrap();
+            let pin_item = CheckMenuItem::with_id(app, "pin", "Pin window", true, false, None::<&str>).unwrap();
+            let reset_position_item = MenuItem::with_id(app, "reset_position", "Reset position", true, None::<&str>).unwrap();
+            let settings_item = MenuItem::with_id(app, "settings", "Settings…", true, None::<&str>).unwrap();
+            let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>).unwrap();
+            
+            let menu = MenuBuilder::new(app)
+                .item(&listening_status_item)
+                .item(&toggle_listening_item)
+                .item(&mute_mic_item)
+                .separator()
+                .item(&show_item)
+                .item(&hide_item)
+                .separator()
+                .item(&dnd_item)
+                .item(&on_top_item)
+                .item(&pin_item)
+                .item(&reset_position_item)
+                .separator()
+                .item(&autostart_item)
+                .item(&settings_item)
+                .item(&quit_item)
+                .build()
+                .unwrap();
+            
+            let dnd_item_sync = dnd_item.clone();
+            *app.state::<AppState>().dnd_menu_sync.lock().unwrap() = Some(Box::new(move |enabled| {
+                let _ = dnd_item_sync.set_checked(enabled);
+            }));
+            *app.state::<AppState>().on_top_menu_sync.lock().unwrap() = Some(Box::new(move |enabled| {
+                let _ = on_top_item.set_checked(enabled);
+            }));
+            *app.state::<AppState>().pin_menu_sync.lock().unwrap() = Some(Box::new(move |pinned| {
+                let _ = pin_item.set_checked(pinned);
+            }));
+            *app.state::<AppState>().autostart_menu_sync.lock().unwrap() = Some(Box::new(move |enabled| {
+                let _ = autostart_item.set_checked(enabled);
+            }));
+            let mute_mic_item_sync = mute_mic_item.clone();
+            *app.state::<AppState>().mic_menu_sync.lock().unwrap() = Some(Box::new(move |paused| {
+                let _ = mute_mic_item_sync.set_checked(paused);
+            }));
+            *app.state::<AppState>().listening_menu_sync.lock().unwrap() = Some(Box::new(move |detector_state| {
+                let (status, action) = tray_listening_labels(detector_state);
+                let _ = listening_status_item.set_text(status);
+                let _ = toggle_listening_item.set_text(action);
+                // The microphone can only be paused while it's capturing
+                let _ = mute_mic_item.set_enabled(detector_state == Some(wake_word::DetectorState::Listening));
+            }));
+            let state_app = app.handle().clone();
+            app.listen_any("wake-word-state-changed", move |event| {
+                match serde_json::from_str::<wake_word::DetectorState>(event.payload()) {
+                    Ok(detector_state) => on_detector_state_changed(&state_app, detector_state),
+                    Err(e) => eprintln!("⚠️ Unexpected detector state {}: {}", event.payload(), e),
+                }
+            });
+            
+            if app.state::<AppState>().launched_at_login {
+                println!("🚀 Started at login");
+            }
+            
+            if let Some(window) = app.get_webview_window("main") {
+                // Stays hidden until the wake word, also when started at login
+                apply_ignore_cursor_events(&window, true).unwrap();
+                window.set_always_on_top(always_on_top).unwrap_or_else(|e| eprintln!("Failed to set always on top: {:?}", e));
+                corners::set_corner_style(&window, corner_style).unwrap_or_else(|e| eprintln!("⚠️ {}", e));
+                shadow::set_shadow(&window, window_shadow).unwrap_or_else(|e| eprintln!("⚠️ {}", e));
+            }
+            apply_session_opacity(app.handle(), false);
+            
+            let tray_status = app.state::<AppState>().tray_display.lock().unwrap().status;
+            let mut tray = TrayIconBuilder::with_id(TRAY_ID).menu(&menu);
+            match tauri::image::Image::from_bytes(tray_status.icon()) {
+                Ok(icon) => tray = tray.icon(icon),
+                Err(e) => eprintln!("⚠️ Failed to load the {:?} tray icon: {}", tray_status, e),
+            }
+            tray
+                .on_menu_event(move |app, event| {
+                    println!("Menu event received: {:?}", event);
+                    match event.id().as_ref() {
+                        "toggle_listening" => {
+                            println!("Listening menu item clicked");
+                            toggle_listening(app);
+                        }
+                        "mute_mic" => {
+                            println!("Mute Microphone menu item clicked");
+                            let paused = app.state::<AppState>().microphone_paused.load(Ordering::SeqCst);
+                            if let Err(e) = apply_microphone_paused(app, !paused) {
+                                eprintln!("Failed to toggle the microphone: {}", e);
+                                // Undo the check the click toggled
+                                sync_microphone_paused(app, paused);
+                            }
+                        }
+                        "show" => {
+                            println!("Show menu item clicked");
+                            show_from_tray(app);
+                        }
+                        "hide" => {
+                            println!("Hide menu item clicked");
+                            hide_main_window(app);
+                        }
+                        "dnd" => {
+                            println!("Do Not Disturb menu item clicked");
+                            let enabled = app.state::<AppState>().do_not_disturb.load(Ordering::SeqCst);
+                            apply_do_not_disturb(app, !enabled);
+                        }
+                        "on_top" => {
+                            println!("Always on Top menu item clicked");
+                            let enabled = app.state::<AppState>().config.lock().unwrap().always_on_top;
+                            if let Err(e) = apply_always_on_top(app, !enabled) {
+                                eprintln!("Failed to toggle always on top: {}", e);
+                            }
+                        }
+                        "pin" => {
+                            println!("Pin window menu item clicked");
+                            let pinned = app.state::<AppState>().window_pinned.load(Ordering::SeqCst);
+                            apply_window_pinned(app, !pinned);
+                        }
+                        "autostart" => {
+                            println!("Start with Windows menu item clicked");
+                            // The click already toggled the check
+                            let enabled = autostart::is_enabled().unwrap_or(false);
+                            let _ = apply_autostart(app, !enabled);
+                        }
+                        "reset_position" => {
+                            println!("Reset position menu item clicked");
+                            if let Err(e) = reset_window_position(app.clone()) {
+                                eprintln!("Failed to reset window position: {}", e);
+                            }
+                        }
+                        "settings" => {
+                            println!("Settings menu item clicked");
+                            // Creating a window from an event handler deadlocks on Windows
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = open_settings(&app) {
+                                    eprintln!("❌ {}", e);
+                                }
+                            });
+                        }
+                        "quit" => {
+                            println!("Quit menu item clicked");
+                            quit_app(app.clone());
+                        }
+                        _ => {}
+                    }
+                })
+                .on_tray_icon_event(|tray, event| {
+                    match event {
+                        // Only show window on left click
+                        TrayIconEvent::Click { button: tauri::tray::MouseButton::Left, .. } => {
+                            println!("Tray icon left clicked");
+                            show_from_tray(tray.app_handle());
+                        }
+                        TrayIconEvent::DoubleClick { .. } => {
+                            println!("Tray icon double-clicked");
+                            show_from_tray(tray.app_handle());
+                        }
+                        _ => {}
+                    }
+                })
+                .build(app)
+                .unwrap();
+            
+            // A hotkey another app already holds shouldn't stop startup
+            match register_hotkeys(app.handle(), &hotkeys) {
+                Ok(()) => update_tray_tooltip(app.handle(), Some(&hotkeys)),
+                Err(error) => {
+                    eprintln!("⚠️ {}", error);
+                    update_tray_tooltip(app.handle(), None);
+                    send_event(
+                        app.handle(),
+                        "hotkey-registration-failed",
+                        events::HotkeyRegistrationFailedPayload { error },
+                        Delivery::Reliable,
+                    );
+                }
+            }
+            
+            theme::start_theme_watcher(app.handle().clone());
+            volume::start_volume_watcher(app.handle().clone());
+            start_idle_watcher(app.handle().clone());
+            start_display_watcher(app.handle().clone());
+            
+            if app.state::<AppState>().config.lock().unwrap().hover_interaction {
+                start_hover_watcher(app.handle());
+            }
+            
+            // Follow the foreground app so its phrases are recognized in sessions
+            let handle = app.handle().clone();
+            context::start_context_watcher(app.handle().clone(), move |context| {
+                if let Some(detector) = handle.state::<AppState>().wake_word_detector.lock().unwrap().as_ref() {
+                    detector.set_active_context(context);
+                }
+            });
+            
+            // Hide the main window after setup is complete
+            if let Some(window) = app.get_webview_window("main") {
+                // Give the window a moment to initialize before hiding
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                    window.hide().unwrap();
+                });
+            }
+            
+            Ok(())
+        })
+        .invoke_handler(invoke_handler())
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| match event {
+            // Closing from the OS (log off, shutdown, taskkill) ends up here
+            // without quit_app, so it gets the same shutdown
+            tauri::RunEvent::ExitRequested { .. } => shut_down(app_handle),
+            tauri::RunEvent::Exit => {
+                shut_down(app_handle);
+                let _ = app_handle.global_shortcut().unregister_all();
+            }
+            _ => {}
+        });
 }
@@ -0,0 +1,141 @@
+// metrics.rs - Per-session speech metrics (words per minute, pauses)
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+// Silences longer than this between recognized phrases count as a pause
+pub const DEFAULT_PAUSE_THRESHOLD: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeechMetrics {
+    pub words_per_minute: f32,
+    pub total_words: u32,
+    pub pause_count: u32,
+    pub session_duration_secs: u64,
+    // Outbound event counters since startup: superseded partials and reliable
+    // events lost to a full buffer
+    pub coalesced_events: u64,
+    pub dropped_events: u64,
+}
+
+impl SpeechMetrics {
+    pub fn with_event_counts(mut self, coalesced: u64, dropped: u64) -> Self {
+        self.coalesced_events = coalesced;
+        self.dropped_events = dropped;
+        self
+    }
+}
+
+pub struct SpeechMetricsTracker {
+    session_id: u64,
+    session_start: Instant,
+    last_speech: Option<Instant>,
+    total_words: u32,
+    pause_count: u32,
+    pause_threshold: Duration,
+}
+
+impl SpeechMetricsTracker {
+    pub fn new(pause_threshold: Duration) -> Self {
+        Self {
+            session_id: 0,
+            session_start: Instant::now(),
+            last_speech: None,
+            total_words: 0,
+            pause_count: 0,
+            pause_threshold,
+        }
+    }
+
+    // Start counting from scratch for a new speech session and return its id
+    pub fn reset(&mut self) -> u64 {
+        self.session_id += 1;
+        self.session_start = Instant::now();
+        self.last_speech = None;
+        self.total_words = 0;
+        self.pause_count = 0;
+        self.session_id
+    }
+
+    pub fn session_id(&self) -> u64 {
+        self.session_id
+    }
+
+    pub fn set_pause_threshold(&mut self, pause_threshold: Duration) {
+        self.pause_threshold = pause_threshold;
+    }
+
+    // Record a recognized phrase. The gap since the previous phrase counts as
+    // a pause if it exceeds the threshold; the wait before the first phrase
+    // doesn't.
+    pub fn record_phrase(&mut self, text: &str) {
+        self.record_phrase_at(text, Instant::now());
+    }
+
+    fn record_phrase_at(&mut self, text: &str, now: Instant) {
+        if self.last_speech.is_some_and(|previous| now.saturating_duration_since(previous) > self.pause_threshold) {
+            self.pause_count += 1;
+        }
+        self.last_speech = Some(now);
+        self.total_words += text.split_whitespace().count() as u32;
+    }
+
+    pub fn snapshot(&self) -> SpeechMetrics {
+        let elapsed = self.session_start.elapsed();
+        let minutes = elapsed.as_secs_f32() / 60.0;
+        let words_per_minute = if minutes > 0.0 {
+            self.total_words as f32 / minutes
+        } else {
+            0.0
+        };
+
+        SpeechMetrics {
+            words_per_minute,
+            total_words: self.total_words,
+            pause_count: self.pause_count,
+            session_duration_secs: elapsed.as_secs(),
+            coalesced_events: 0,
+            dropped_events: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_before_the_first_phrase_isnt_a_pause() {
+        let mut tracker = SpeechMetricsTracker::new(DEFAULT_PAUSE_THRESHOLD);
+        let start = Instant::now();
+        tracker.record_phrase_at("hello there", start + Duration::from_secs(5));
+        let metrics = tracker.snapshot();
+        assert_eq!(metrics.pause_count, 0);
+        assert_eq!(metrics.total_words, 2);
+    }
+
+    #[test]
+    fn gaps_over_the_threshold_are_pauses() {
+        let mut tracker = SpeechMetricsTracker::new(DEFAULT_PAUSE_THRESHOLD);
+        let start = Instant::now();
+        tracker.record_phrase_at("one", start);
+        tracker.record_phrase_at("two", start + Duration::from_millis(500));
+        tracker.record_phrase_at("three", start + Duration::from_millis(2_000));
+        tracker.record_phrase_at("four", start + Duration::from_millis(3_000));
+        assert_eq!(tracker.snapshot().pause_count, 1);
+    }
+
+    #[test]
+    fn reset_starts_a_new_session() {
+        let mut tracker = SpeechMetricsTracker::new(DEFAULT_PAUSE_THRESHOLD);
+        let start = Instant::now();
+        tracker.record_phrase_at("one", start);
+        tracker.record_phrase_at("two", start + Duration::from_secs(3));
+        assert_eq!(tracker.reset(), 1);
+
+        tracker.record_phrase_at("again", start + Duration::from_secs(10));
+        let metrics = tracker.snapshot();
+        assert_eq!(metrics.pause_count, 0);
+        assert_eq!(metrics.total_words, 1);
+        assert_eq!(tracker.reset(), 2);
+    }
+}
@@ -0,0 +1,82 @@
+// models.rs - Whisper.cpp models that can be downloaded for local recognition
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ModelInfo {
+    pub name: &'static str,
+    pub url: String,
+    pub size_bytes: u64,
+    // Whether the file is already in the models directory
+    pub downloaded: bool,
+}
+
+// Name and download size of each multilingual ggml model, smallest first
+const MODELS: &[(&str, u64)] = &[
+    ("tiny", 77_691_713),
+    ("base", 147_951_465),
+    ("small", 487_601_967),
+    ("medium", 1_533_763_059),
+];
+
+pub fn file_name(name: &str) -> String {
+    format!("ggml-{}.bin", name)
+}
+
+pub fn is_known(name: &str) -> bool {
+    MODELS.iter().any(|(known, _)| *known == name)
+}
+
+pub fn list(models_dir: &Path) -> Vec<ModelInfo> {
+    MODELS
+        .iter()
+        .map(|&(name, size_bytes)| ModelInfo {
+            name,
+            url: format!("{}/{}", MODEL_BASE_URL, file_name(name)),
+            size_bytes,
+            downloaded: models_dir.join(file_name(name)).is_file(),
+        })
+        .collect()
+}
+
+// Stream `url` to `dest`, calling `progress(bytes_downloaded, total_bytes)`
+// after each chunk. The data goes to a .part file that only replaces `dest`
+// once complete, so an interrupted download never looks like a model.
+pub async fn download(url: &str, dest: &Path, mut progress: impl FnMut(u64, Option<u64>)) -> Result<PathBuf> {
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let partial = dest.with_extension("part");
+
+    let mut response = reqwest::get(url).await?.error_for_status()?;
+    let total = response.content_length();
+    let mut file = fs::File::create(&partial)?;
+    let mut downloaded = 0u64;
+    let outcome: Result<()> = async {
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk)?;
+            downloaded += chunk.len() as u64;
+            progress(downloaded, total);
+        }
+        file.flush()?;
+        Ok(())
+    }
+    .await;
+    drop(file);
+
+    if let Err(e) = outcome {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    if total.is_some_and(|total| total != downloaded) {
+        let _ = fs::remove_file(&partial);
+        return Err(anyhow::anyhow!("Download ended after {} of {} bytes", downloaded, total.unwrap_or(0)));
+    }
+    fs::rename(&partial, dest)?;
+    Ok(dest.to_path_buf())
+}
@@ -0,0 +1,628 @@
+// normalize.rs - Turn spoken numbers, durations, times and percentages into digits
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum EntityValue {
+    Cardinal(u64),
+    Ordinal(u64),
+    Duration { seconds: u64 },
+    // 24-hour when "am"/"pm" was spoken, otherwise the hour as said
+    TimeOfDay { hour: u32, minute: u32 },
+    Percentage(u64),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Entity {
+    // Byte range of the entity in the normalized text
+    pub start: usize,
+    pub end: usize,
+    pub value: EntityValue,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Normalized {
+    pub text: String,
+    pub entities: Vec<Entity>,
+}
+
+const UNITS: &[&str] = &[
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen",
+    "nineteen",
+];
+
+// Index + 2 is the tens digit
+const TENS: &[&str] = &[
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const ORDINAL_UNITS: &[&str] = &[
+    "zeroth", "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth",
+    "ninth", "tenth", "eleventh", "twelfth", "thirteenth", "fourteenth", "fifteenth",
+    "sixteenth", "seventeenth", "eighteenth", "nineteenth",
+];
+
+const ORDINAL_TENS: &[&str] = &[
+    "twentieth", "thirtieth", "fortieth", "fiftieth", "sixtieth", "seventieth", "eightieth",
+    "ninetieth",
+];
+
+const SCALES: &[(&str, &str, u64)] = &[
+    ("thousand", "thousandth", 1_000),
+    ("million", "millionth", 1_000_000),
+];
+
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("second", 1),
+    ("seconds", 1),
+    ("minute", 60),
+    ("minutes", 60),
+    ("hour", 3600),
+    ("hours", 3600),
+];
+
+// Words that are only read as numbers right before a unit or "percent":
+// "one" is too often a pronoun, and "to"/"too" are usually just themselves
+const AMBIGUOUS: &[(&str, u64)] = &[("one", 1), ("to", 2), ("too", 2)];
+
+// A whitespace-separated token, split into leading punctuation, the word
+// itself (lowercased for matching) and trailing punctuation
+struct Token<'a> {
+    start: usize,
+    end: usize,
+    lead: &'a str,
+    core: String,
+    trail: &'a str,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                let word = &text[s..i];
+                let core_start = word.find(char::is_alphanumeric).unwrap_or(word.len());
+                let core_end = word
+                    .rfind(char::is_alphanumeric)
+                    .map_or(core_start, |e| e + word[e..].chars().next().map_or(1, char::len_utf8));
+                tokens.push(Token {
+                    start: s,
+                    end: i,
+                    lead: &word[..core_start],
+                    core: word[core_start..core_end].to_lowercase(),
+                    trail: &word[core_end..],
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Part {
+    None,
+    Unit,
+    Tens,
+    Hundred,
+    Scale,
+}
+
+struct NumberMatch {
+    value: u64,
+    // Number of tokens consumed
+    len: usize,
+    ordinal: bool,
+}
+
+fn position(table: &[&str], word: &str) -> Option<u64> {
+    table.iter().position(|w| *w == word).map(|i| i as u64)
+}
+
+// Parse the longest number phrase at the start of `words`: "a hundred and
+// five", "twenty-five", "two million three hundred thousand", "forty second"
+fn parse_number(words: &[&str]) -> Option<NumberMatch> {
+    if let Some(digits) = words.first().filter(|w| w.chars().all(|c| c.is_ascii_digit())) {
+        return digits.parse().ok().map(|value| NumberMatch { value, len: 1, ordinal: false });
+    }
+
+    let mut total = 0;
+    let mut current = 0;
+    let mut last_scale = u64::MAX;
+    let mut previous = Part::None;
+    let mut matched = None;
+
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        let next = words.get(i + 1).copied().unwrap_or("");
+        let next_is_scale = next == "hundred" || SCALES.iter().any(|(scale, _, _)| *scale == next);
+
+        // "a hundred", "a thousand"
+        if word == "a" && previous == Part::None && next_is_scale {
+            current = 1;
+            previous = Part::Unit;
+            i += 1;
+            continue;
+        }
+        // "a hundred and five", "two thousand and twenty"
+        if word == "and"
+            && matches!(previous, Part::Hundred | Part::Scale)
+            && (position(UNITS, next).is_some_and(|n| n > 0) || position(TENS, next).is_some())
+        {
+            i += 1;
+            continue;
+        }
+
+        if let Some(n) = position(UNITS, word) {
+            let fits = match previous {
+                Part::None => true,
+                Part::Tens => n > 0 && n < 10,
+                Part::Hundred | Part::Scale => n > 0,
+                Part::Unit => false,
+            };
+            if !fits {
+                break;
+            }
+            current += n;
+            previous = Part::Unit;
+        } else if let Some(n) = position(TENS, word) {
+            if matches!(previous, Part::Unit | Part::Tens) {
+                break;
+            }
+            current += (n + 2) * 10;
+            previous = Part::Tens;
+        } else if word == "hundred" {
+            // "nineteen hundred" and "twenty five hundred" work, "two hundred five hundred" doesn't
+            if !matches!(previous, Part::Unit | Part::Tens) || current >= 100 {
+                break;
+            }
+            current *= 100;
+            previous = Part::Hundred;
+        } else if let Some(&(_, _, scale)) = SCALES.iter().find(|(w, _, _)| *w == word) {
+            if current == 0 || scale >= last_scale || previous == Part::None {
+                break;
+            }
+            total += current * scale;
+            current = 0;
+            last_scale = scale;
+            previous = Part::Scale;
+        } else if let Some(value) = parse_ordinal_end(word, previous, current, total, last_scale) {
+            // An ordinal always ends the number
+            return Some(NumberMatch { value, len: i + 1, ordinal: true });
+        } else {
+            break;
+        }
+
+        i += 1;
+        matched = Some(NumberMatch { value: total + current, len: i, ordinal: false });
+    }
+
+    matched
+}
+
+// The value of a number ending in the ordinal `word`, if it can end one there
+fn parse_ordinal_end(word: &str, previous: Part, current: u64, total: u64, last_scale: u64) -> Option<u64> {
+    if let Some(n) = position(ORDINAL_UNITS, word) {
+        let fits = match previous {
+            Part::None => true,
+            Part::Tens => n > 0 && n < 10,
+            Part::Hundred | Part::Scale => n > 0,
+            Part::Unit => false,
+        };
+        return fits.then_some(total + current + n);
+    }
+    if let Some(n) = position(ORDINAL_TENS, word) {
+        let fits = !matches!(previous, Part::Unit | Part::Tens);
+        return fits.then_some(total + current + (n + 2) * 10);
+    }
+    if word == "hundredth" {
+        let fits = matches!(previous, Part::Unit | Part::Tens) && current < 100;
+        return fits.then_some(total + current * 100);
+    }
+    if let Some(&(_, _, scale)) = SCALES.iter().find(|(_, w, _)| *w == word) {
+        let fits = current != 0 && scale < last_scale && previous != Part::None;
+        return fits.then_some(total + current * scale);
+    }
+    None
+}
+
+fn ordinal_suffix(value: u64) -> &'static str {
+    match (value % 10, value % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+fn duration_unit(word: &str) -> Option<u64> {
+    DURATION_UNITS.iter().find(|(w, _)| *w == word).map(|(_, seconds)| *seconds)
+}
+
+fn meridiem(word: &str) -> Option<bool> {
+    match word {
+        "am" | "a.m" => Some(false),
+        "pm" | "p.m" => Some(true),
+        _ => None,
+    }
+}
+
+// What a number at a given position turned out to be, and how many tokens
+// (including the number itself) it covers
+struct Conversion {
+    len: usize,
+    replacement: String,
+    value: EntityValue,
+}
+
+// Everything after a cardinal number: units, "percent", times of day
+fn classify(tokens: &[Token], number_end: usize, value: u64) -> Option<Conversion> {
+    let word = |i: usize| tokens.get(i).map_or("", |t| t.core.as_str());
+    // Only look past the number if nothing punctuates it off
+    let joined = |i: usize| i > 0 && tokens[i - 1].trail.is_empty() && tokens.get(i).is_some_and(|t| t.lead.is_empty());
+
+    if joined(number_end) && word(number_end) == "percent" {
+        return Some(Conversion {
+            len: number_end + 1,
+            replacement: format!("{}%", value),
+            value: EntityValue::Percentage(value),
+        });
+    }
+    if joined(number_end) && word(number_end) == "per" && joined(number_end + 1) && word(number_end + 1) == "cent" {
+        return Some(Conversion {
+            len: number_end + 2,
+            replacement: format!("{}%", value),
+            value: EntityValue::Percentage(value),
+        });
+    }
+
+    if joined(number_end) {
+        // Spoken digits can be any size; one too big for seconds stays a number
+        if let Some(mut seconds) = duration_unit(word(number_end)).and_then(|unit| value.checked_mul(unit)) {
+            let mut parts = vec![format!("{} {}", value, tokens[number_end].core)];
+            let mut end = number_end + 1;
+
+            // "one hour and thirty minutes"
+            while joined(end) && word(end) == "and" && joined(end + 1) {
+                let rest: Vec<&str> = tokens[end + 1..].iter().map(|t| t.core.as_str()).collect();
+                let Some(number) = parse_number(&rest).filter(|n| !n.ordinal) else {
+                    break;
+                };
+                let unit_index = end + 1 + number.len;
+                let Some(next_unit) = duration_unit(word(unit_index)).filter(|_| joined(unit_index)) else {
+                    break;
+                };
+                let Some(total) = number.value.checked_mul(next_unit).and_then(|part| seconds.checked_add(part)) else {
+                    break;
+                };
+                seconds = total;
+                parts.push(format!("{} {}", number.value, tokens[unit_index].core));
+                end = unit_index + 1;
+            }
+
+            return Some(Conversion {
+                len: end,
+                replacement: parts.join(" and "),
+                value: EntityValue::Duration { seconds },
+            });
+        }
+    }
+
+    if (1..=12).contains(&value) && joined(number_end) {
+        if word(number_end) == "o'clock" {
+            return Some(Conversion {
+                len: number_end + 1,
+                replacement: format!("{}:00", value),
+                value: EntityValue::TimeOfDay { hour: value as u32, minute: 0 },
+            });
+        }
+
+        // "five pm", "seven thirty am", "nine oh five pm"
+        let (minute, minute_len) = parse_minutes(tokens, number_end);
+        let meridiem_index = number_end + minute_len;
+        if let Some(pm) = meridiem(word(meridiem_index)).filter(|_| joined(meridiem_index)) {
+            let hour = value as u32 % 12 + if pm { 12 } else { 0 };
+            let suffix = if pm { "PM" } else { "AM" };
+            let replacement = match minute_len {
+                0 => format!("{} {}", value, suffix),
+                _ => format!("{}:{:02} {}", value, minute, suffix),
+            };
+            return Some(Conversion {
+                len: meridiem_index + 1,
+                replacement,
+                value: EntityValue::TimeOfDay { hour, minute },
+            });
+        }
+    }
+
+    None
+}
+
+// Minutes spoken after an hour: "thirty", "forty five", "oh five"
+fn parse_minutes(tokens: &[Token], start: usize) -> (u32, usize) {
+    let joined = |i: usize| tokens.get(i).is_some_and(|t| t.lead.is_empty()) && tokens[i - 1].trail.is_empty();
+    if !joined(start) {
+        return (0, 0);
+    }
+    let rest: Vec<&str> = tokens[start..].iter().map(|t| t.core.as_str()).collect();
+
+    if rest[0] == "oh" && joined(start + 1) {
+        if let Some(n) = rest.get(1).and_then(|w| position(UNITS, w)).filter(|n| (1..10).contains(n)) {
+            return (n as u32, 2);
+        }
+        return (0, 0);
+    }
+
+    match parse_number(&rest) {
+        Some(number) if !number.ordinal && (10..60).contains(&number.value) && number.len <= 2 => {
+            (number.value as u32, number.len)
+        }
+        _ => (0, 0),
+    }
+}
+
+// Replace spoken numbers in `text` with digits and collect what they mean
+pub fn normalize(text: &str) -> Normalized {
+    let tokens = tokenize(text);
+    let mut output = String::with_capacity(text.len());
+    let mut entities = Vec::new();
+    let mut copied_to = 0;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let Some(conversion) = convert_at(&tokens, i) else {
+            i += 1;
+            continue;
+        };
+
+        let first = &tokens[i];
+        let last = &tokens[i + conversion.len - 1];
+        output.push_str(&text[copied_to..first.start]);
+        output.push_str(first.lead);
+        let start = output.len();
+        output.push_str(&conversion.replacement);
+        entities.push(Entity {
+            start,
+            end: output.len(),
+            value: conversion.value,
+        });
+        output.push_str(last.trail);
+        copied_to = last.end;
+        i += conversion.len;
+    }
+    output.push_str(&text[copied_to..]);
+
+    Normalized { text: output, entities }
+}
+
+fn convert_at(tokens: &[Token], i: usize) -> Option<Conversion> {
+    // Number words have to run together; punctuation ends the phrase, so
+    // "one, two" stays two numbers
+    let mut words = Vec::new();
+    for (offset, token) in tokens[i..].iter().enumerate() {
+        if offset > 0 && !token.lead.is_empty() {
+            break;
+        }
+        words.push(token.core.as_str());
+        if !token.trail.is_empty() || token.core.contains('-') {
+            break;
+        }
+    }
+
+    // "twenty-five" is one token but several number words
+    if let Some(core) = words.first().filter(|w| w.contains('-')) {
+        let parts: Vec<&str> = core.split('-').collect();
+        return match parse_number(&parts) {
+            Some(number) if number.len == parts.len() => Some(number_conversion(tokens, i, 1, number.value, number.ordinal)),
+            _ => None,
+        };
+    }
+
+    if let Some(&(_, value)) = AMBIGUOUS.iter().find(|(w, _)| *w == tokens[i].core) {
+        // "one" still starts compounds like "one hundred"
+        if let Some(number) = parse_number(&words).filter(|n| n.len > 1) {
+            return Some(number_conversion(tokens, i, number.len, number.value, number.ordinal));
+        }
+        return classify(&tokens[i..], 1, value);
+    }
+
+    let number = parse_number(&words)?;
+    // Bare "first"/"second" are nearly always just words ("at first", "wait a second")
+    if number.ordinal && number.len == 1 && matches!(words[0], "first" | "second") {
+        return None;
+    }
+    // "twenty second" is a duration unless it's a date ("the twenty second of May")
+    if number.ordinal && number.len > 1 && words[number.len - 1] == "second" {
+        let is_date = (i > 0 && tokens[i - 1].core == "the") || tokens.get(i + number.len).is_some_and(|t| t.core == "of");
+        if !is_date {
+            let cardinal = parse_number(&words[..number.len - 1])?;
+            return classify(&tokens[i..], cardinal.len, cardinal.value);
+        }
+    }
+    Some(number_conversion(tokens, i, number.len, number.value, number.ordinal))
+}
+
+fn number_conversion(tokens: &[Token], i: usize, len: usize, value: u64, ordinal: bool) -> Conversion {
+    if ordinal {
+        return Conversion {
+            len,
+            replacement: format!("{}{}", value, ordinal_suffix(value)),
+            value: EntityValue::Ordinal(value),
+        };
+    }
+    classify(&tokens[i..], len, value).unwrap_or(Conversion {
+        len,
+        replacement: value.to_string(),
+        value: EntityValue::Cardinal(value),
+    })
+}
+
+// Words a dictated number can be made of, besides "negative" and "point"
+pub fn dictation_words() -> Vec<&'static str> {
+    UNITS.iter().chain(TENS).copied().chain(["hundred", "thousand"]).collect()
+}
+
+// The digits of a dictated number: "three point one four" is "3.14", "forty
+// two" is "42", "five five five one two" is "55512" and "point five" is "0.5".
+// Each number phrase adds its digits, so codes and phone numbers can be read
+// out in groups. None if `text` isn't only number words.
+pub fn dictated_number(text: &str) -> Option<String> {
+    let lowered = text.to_lowercase();
+    let mut words: Vec<&str> = lowered
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut number = String::new();
+    if words.first() == Some(&"negative") {
+        number.push('-');
+        words.remove(0);
+    }
+
+    let mut has_point = false;
+    let mut digits_since_point = 0;
+    let mut i = 0;
+    while i < words.len() {
+        if words[i] == "point" {
+            if has_point {
+                return None;
+            }
+            if !number.ends_with(|c: char| c.is_ascii_digit()) {
+                number.push('0');
+            }
+            number.push('.');
+            has_point = true;
+            digits_since_point = 0;
+            i += 1;
+            continue;
+        }
+        let phrase = parse_number(&words[i..]).filter(|phrase| !phrase.ordinal)?;
+        number.push_str(&phrase.value.to_string());
+        digits_since_point += 1;
+        i += phrase.len;
+    }
+
+    let complete = number.ends_with(|c: char| c.is_ascii_digit()) && (!has_point || digits_since_point > 0);
+    complete.then_some(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each case: spoken text, normalized text, entity values
+    fn check(cases: &[(&str, &str, &[EntityValue])]) {
+        for (spoken, text, values) in cases {
+            let normalized = normalize(spoken);
+            assert_eq!(normalized.text, *text, "{:?}", spoken);
+            let found: Vec<EntityValue> = normalized.entities.into_iter().map(|entity| entity.value).collect();
+            assert_eq!(found, *values, "{:?}", spoken);
+        }
+    }
+
+    #[test]
+    fn cardinals() {
+        use EntityValue::Cardinal;
+        check(&[
+            ("twenty five apples", "25 apples", &[Cardinal(25)]),
+            ("twenty-five", "25", &[Cardinal(25)]),
+            ("a hundred", "100", &[Cardinal(100)]),
+            ("one hundred and five", "105", &[Cardinal(105)]),
+            ("two million three hundred thousand", "2300000", &[Cardinal(2_300_000)]),
+            ("two to three", "2 to 3", &[Cardinal(2), Cardinal(3)]),
+        ]);
+    }
+
+    #[test]
+    fn ordinals() {
+        use EntityValue::{Duration, Ordinal};
+        check(&[
+            ("fifth place", "5th place", &[Ordinal(5)]),
+            ("the twenty first", "the 21st", &[Ordinal(21)]),
+            ("the forty second of May", "the 42nd of May", &[Ordinal(42)]),
+            // Without a date around it, it's forty of the unit
+            ("forty second", "40 second", &[Duration { seconds: 40 }]),
+        ]);
+    }
+
+    #[test]
+    fn durations() {
+        use EntityValue::Duration;
+        check(&[
+            ("ten seconds", "10 seconds", &[Duration { seconds: 10 }]),
+            ("one minute", "1 minute", &[Duration { seconds: 60 }]),
+            ("5 minutes", "5 minutes", &[Duration { seconds: 300 }]),
+            ("one hour and thirty minutes", "1 hour and 30 minutes", &[Duration { seconds: 5400 }]),
+        ]);
+    }
+
+    #[test]
+    fn percentages() {
+        use EntityValue::Percentage;
+        check(&[
+            ("fifty percent", "50%", &[Percentage(50)]),
+            ("fifty per cent", "50%", &[Percentage(50)]),
+            ("twenty-five percent", "25%", &[Percentage(25)]),
+        ]);
+    }
+
+    #[test]
+    fn ambiguous_words_are_left_alone() {
+        check(&[
+            ("one", "one", &[]),
+            ("I want one", "I want one", &[]),
+            ("go to the store", "go to the store", &[]),
+            ("me too", "me too", &[]),
+            ("at first", "at first", &[]),
+            ("wait a second", "wait a second", &[]),
+        ]);
+    }
+
+    #[test]
+    fn durations_too_long_for_seconds_stay_numbers() {
+        use EntityValue::{Cardinal, Duration};
+        check(&[
+            ("18446744073709551615 hours", "18446744073709551615 hours", &[Cardinal(u64::MAX)]),
+            (
+                "five hours and 18446744073709551615 seconds",
+                "5 hours and 18446744073709551615 seconds",
+                &[Duration { seconds: 18_000 }, Duration { seconds: u64::MAX }],
+            ),
+        ]);
+    }
+
+    #[test]
+    fn dictated_numbers() {
+        let cases = [
+            ("three point one four", Some("3.14")),
+            ("forty two", Some("42")),
+            ("Forty-two", Some("42")),
+            ("five five five one two", Some("55512")),
+            ("one hundred twenty three", Some("123")),
+            ("negative seven", Some("-7")),
+            ("negative three point five", Some("-3.5")),
+            ("point five", Some("0.5")),
+            ("negative point two five", Some("-0.25")),
+            // Nothing to read, or not finished
+            ("", None),
+            ("negative", None),
+            ("point", None),
+            ("three point", None),
+            ("three point one point four", None),
+            ("seven negative", None),
+            // Only number words
+            ("forty two apples", None),
+            ("the fifth", None),
+            ("fifth", None),
+        ];
+        for (spoken, digits) in cases {
+            assert_eq!(dictated_number(spoken).as_deref(), digits, "{:?}", spoken);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// opacity.rs - Whole-window translucency through layered window attributes
+use anyhow::Result;
+use tauri::{Runtime, WebviewWindow};
+use winapi::shared::windef::HWND;
+use winapi::um::winuser::{
+    GetWindowLongPtrW, SetLayeredWindowAttributes, SetWindowLongPtrW, GWL_EXSTYLE, LWA_ALPHA, WS_EX_LAYERED,
+};
+
+pub const MIN_OPACITY: f64 = 0.2;
+pub const MAX_OPACITY: f64 = 1.0;
+
+pub fn clamp(opacity: f64) -> f64 {
+    if opacity.is_nan() {
+        return MAX_OPACITY;
+    }
+    opacity.clamp(MIN_OPACITY, MAX_OPACITY)
+}
+
+// Make the window `opacity` opaque (0.2 - 1.0). Fails if the window can't be
+// made layered, which some GPU and compositor setups refuse.
+pub fn set_opacity<R: Runtime>(window: &WebviewWindow<R>, opacity: f64) -> Result<()> {
+    set_alpha(window, (clamp(opacity) * 255.0).round() as u8)
+}
+
+// Like set_opacity, but down to fully transparent, for fading in and out
+pub fn set_fade_opacity<R: Runtime>(window: &WebviewWindow<R>, opacity: f64) -> Result<()> {
+    let opacity = if opacity.is_nan() { MAX_OPACITY } else { opacity.clamp(0.0, MAX_OPACITY) };
+    set_alpha(window, (opacity * 255.0).round() as u8)
+}
+
+fn set_alpha<R: Runtime>(window: &WebviewWindow<R>, alpha: u8) -> Result<()> {
+    let hwnd = window.hwnd()?.0 as HWND;
+
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        if style & WS_EX_LAYERED as isize == 0 {
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED as isize);
+        }
+        if SetLayeredWindowAttributes(hwnd, 0, alpha, LWA_ALPHA) == 0 {
+            return Err(anyhow::anyhow!(
+                "Failed to set layered window attributes: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(())
+}
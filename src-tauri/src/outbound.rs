@@ -0,0 +1,160 @@
+// outbound.rs - Events waiting to reach the frontend, so a busy or reloading
+// webview neither loses transcripts nor slows down recognition
+use serde_json::Value;
+use std::collections::VecDeque;
+
+// Reliable events held while the frontend isn't ready
+pub const DEFAULT_RELIABLE_CAP: usize = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delivery {
+    // Only the newest pending event of the same name is kept (partials, metrics)
+    Latest,
+    // Delivered in order; the oldest is only dropped past the cap
+    Reliable,
+}
+
+pub struct OutboundQueue {
+    pending: VecDeque<(String, Value, Delivery)>,
+    // Set once the frontend's listeners are attached, cleared on reload
+    ready: bool,
+    reliable_cap: usize,
+    coalesced: u64,
+    dropped: u64,
+}
+
+impl OutboundQueue {
+    pub fn new(reliable_cap: usize) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            ready: false,
+            reliable_cap,
+            coalesced: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, event: &str, payload: Value, delivery: Delivery) {
+        match delivery {
+            Delivery::Latest => {
+                let superseded = self
+                    .pending
+                    .iter()
+                    .position(|(name, _, kind)| name == event && *kind == Delivery::Latest);
+                if let Some(index) = superseded {
+                    self.pending.remove(index);
+                    self.coalesced += 1;
+                }
+            }
+            Delivery::Reliable => {
+                let reliable = self.pending.iter().filter(|(_, _, kind)| *kind == Delivery::Reliable).count();
+                if reliable >= self.reliable_cap {
+                    if let Some(index) = self.pending.iter().position(|(_, _, kind)| *kind == Delivery::Reliable) {
+                        self.pending.remove(index);
+                        self.dropped += 1;
+                    }
+                }
+            }
+        }
+        self.pending.push_back((event.to_string(), payload, delivery));
+    }
+
+    pub fn set_ready(&mut self, ready: bool) {
+        self.ready = ready;
+    }
+
+    // Everything pending, oldest first, or nothing while the frontend isn't ready
+    pub fn drain(&mut self) -> Vec<(String, Value)> {
+        if !self.ready {
+            return Vec::new();
+        }
+        self.pending.drain(..).map(|(name, payload, _)| (name, payload)).collect()
+    }
+
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn names(events: &[(String, Value)]) -> Vec<&str> {
+        events.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn latest_events_coalesce_to_the_newest() {
+        let mut queue = OutboundQueue::new(DEFAULT_RELIABLE_CAP);
+        queue.set_ready(true);
+        queue.push("partial", json!("hel"), Delivery::Latest);
+        queue.push("final", json!("hi"), Delivery::Reliable);
+        queue.push("partial", json!("hello"), Delivery::Latest);
+        queue.push("partial", json!("hello there"), Delivery::Latest);
+        queue.push("metrics", json!(1), Delivery::Latest);
+
+        let events = queue.drain();
+        // The replacement goes to the back, behind what was already waiting
+        assert_eq!(names(&events), ["final", "partial", "metrics"]);
+        assert_eq!(events[1].1, json!("hello there"));
+        assert_eq!(queue.coalesced(), 2);
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[test]
+    fn reliable_events_never_coalesce() {
+        let mut queue = OutboundQueue::new(DEFAULT_RELIABLE_CAP);
+        queue.set_ready(true);
+        for text in ["one", "two", "three"] {
+            queue.push("final", json!(text), Delivery::Reliable);
+        }
+        // A latest event of the same name doesn't replace reliable ones either
+        queue.push("final", json!("four"), Delivery::Latest);
+
+        let payloads: Vec<Value> = queue.drain().into_iter().map(|(_, payload)| payload).collect();
+        assert_eq!(payloads, [json!("one"), json!("two"), json!("three"), json!("four")]);
+        assert_eq!(queue.coalesced(), 0);
+    }
+
+    #[test]
+    fn the_oldest_reliable_event_is_dropped_past_the_cap() {
+        let mut queue = OutboundQueue::new(2);
+        queue.push("partial", json!("a"), Delivery::Latest);
+        queue.push("final", json!(1), Delivery::Reliable);
+        queue.push("final", json!(2), Delivery::Reliable);
+        queue.push("final", json!(3), Delivery::Reliable);
+        queue.push("final", json!(4), Delivery::Reliable);
+        assert_eq!(queue.dropped(), 2);
+
+        queue.set_ready(true);
+        let events = queue.drain();
+        // Latest events don't count towards the cap and are never dropped for it
+        assert_eq!(names(&events), ["partial", "final", "final"]);
+        assert_eq!(events[1].1, json!(3));
+        assert_eq!(events[2].1, json!(4));
+    }
+
+    #[test]
+    fn events_wait_until_the_frontend_is_ready() {
+        let mut queue = OutboundQueue::new(DEFAULT_RELIABLE_CAP);
+        queue.push("final", json!("early"), Delivery::Reliable);
+        assert!(queue.drain().is_empty());
+
+        queue.set_ready(true);
+        assert_eq!(names(&queue.drain()), ["final"]);
+        assert!(queue.drain().is_empty());
+
+        // A reload holds events again until the listeners are back
+        queue.set_ready(false);
+        queue.push("final", json!("during reload"), Delivery::Reliable);
+        assert!(queue.drain().is_empty());
+        queue.set_ready(true);
+        assert_eq!(queue.drain(), [("final".to_string(), json!("during reload"))]);
+    }
+}
@@ -0,0 +1,344 @@
+// pipeline.rs - The audio path behind recognition: AudioCapture -> processors
+// -> VAD -> recognition backend, plus taps that see every processed frame
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::audio::{self, AudioCapture};
+use crate::speech_recognition::{EndpointingConfig, RecognitionBackend};
+
+// The backend is shared with the detector, which swaps and starts it per session
+pub type SharedBackend = Arc<Mutex<Box<dyn RecognitionBackend + Send>>>;
+
+// Gets every captured frame and its sample rate, speech or not
+type FrameTap = Arc<dyn Fn(&[i16], u32) + Send + Sync>;
+
+// A stage that changes captured audio in place before anything else sees it
+pub trait AudioProcessor: Send {
+    fn process(&mut self, frame: &mut [i16], sample_rate: u32);
+    // Forget any state built up from earlier audio
+    fn reset(&mut self) {}
+}
+
+type Processors = Arc<Mutex<Vec<Box<dyn AudioProcessor>>>>;
+
+pub const DEFAULT_VAD_THRESHOLD_RMS: f32 = 0.01;
+
+// Energy-based voice activity detection. Quiet frames right after speech
+// still count as speech for `hangover`, so pauses between words and soft
+// word endings reach the recognizer. An utterance is held back until it has
+// `min_speech` of sound, so shorter bursts never reach the recognizer, and
+// is cut off once `max_utterance` has been passed on.
+pub struct VadStage {
+    threshold_rms: f32,
+    hangover: Duration,
+    min_speech: Duration,
+    max_utterance: Option<Duration>,
+    hangover_left: Duration,
+    // Frames of an utterance that hasn't had min_speech of sound yet
+    held: Vec<Vec<i16>>,
+    speech: Duration,
+    // How much of the current utterance was passed on
+    passed: Duration,
+}
+
+impl VadStage {
+    pub fn new(threshold_rms: f32, hangover: Duration) -> Self {
+        Self {
+            threshold_rms,
+            hangover,
+            min_speech: Duration::ZERO,
+            max_utterance: None,
+            hangover_left: Duration::ZERO,
+            held: Vec::new(),
+            speech: Duration::ZERO,
+            passed: Duration::ZERO,
+        }
+    }
+
+    // Trailing silence is the hangover, so the recognizer hears all of it
+    pub fn from_endpointing(threshold_rms: f32, config: &EndpointingConfig) -> Self {
+        let mut vad = Self::new(threshold_rms, Duration::ZERO);
+        vad.set_endpointing(config);
+        vad
+    }
+
+    // Applies from the next utterance
+    pub fn set_endpointing(&mut self, config: &EndpointingConfig) {
+        self.hangover = Duration::from_millis(config.trailing_silence_ms);
+        self.min_speech = Duration::from_millis(config.min_speech_ms);
+        self.max_utterance = Some(Duration::from_millis(config.max_utterance_ms));
+    }
+
+    // Hands `pass` the frames that should go on as speech: this one, or
+    // held-back frames along with it once the utterance is long enough
+    pub fn process(&mut self, frame: &[i16], sample_rate: u32, mut pass: impl FnMut(&[i16])) {
+        let frame_duration = Duration::from_secs_f64(frame.len() as f64 / sample_rate.max(1) as f64);
+        if audio::rms(frame) >= self.threshold_rms {
+            self.hangover_left = self.hangover;
+            self.speech += frame_duration;
+        } else if self.hangover_left.is_zero() {
+            // The utterance, if any, is over
+            self.reset();
+            return;
+        } else {
+            self.hangover_left = self.hangover_left.saturating_sub(frame_duration);
+        }
+
+        if self.speech < self.min_speech {
+            self.held.push(frame.to_vec());
+            return;
+        }
+        let limit = self.max_utterance.unwrap_or(Duration::MAX);
+        let held = std::mem::take(&mut self.held);
+        for speech in held.iter().map(Vec::as_slice).chain(Some(frame)) {
+            if self.passed >= limit {
+                break;
+            }
+            self.passed += Duration::from_secs_f64(speech.len() as f64 / sample_rate.max(1) as f64);
+            pass(speech);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.hangover_left = Duration::ZERO;
+        self.held.clear();
+        self.speech = Duration::ZERO;
+        self.passed = Duration::ZERO;
+    }
+}
+
+pub struct SpeechPipeline {
+    capture: AudioCapture,
+    vad: Arc<Mutex<VadStage>>,
+    recognizer: SharedBackend,
+    // Microphones to capture from; more than one are mixed together
+    input_devices: Vec<Option<String>>,
+    // Run in order on every frame, before the taps and the VAD
+    processors: Processors,
+    taps: Vec<FrameTap>,
+    // Speech only reaches the recognizer while a session is running
+    feeding: Arc<AtomicBool>,
+    // Microseconds of speech fed to the recognizer
+    fed_audio_us: Arc<AtomicU64>,
+    running: bool,
+}
+
+impl SpeechPipeline {
+    pub fn new(capture: AudioCapture, vad: VadStage, recognizer: SharedBackend) -> Self {
+        Self {
+            capture,
+            vad: Arc::new(Mutex::new(vad)),
+            recognizer,
+            input_devices: Vec::new(),
+            processors: Arc::new(Mutex::new(Vec::new())),
+            taps: Vec::new(),
+            feeding: Arc::new(AtomicBool::new(false)),
+            fed_audio_us: Arc::new(AtomicU64::new(0)),
+            running: false,
+        }
+    }
+
+    // Applies from the next start()
+    pub fn set_input_devices(&mut self, devices: Vec<Option<String>>) {
+        self.input_devices = devices;
+    }
+
+    // Takes effect from the next frame, also while running
+    pub fn set_processors(&self, processors: Vec<Box<dyn AudioProcessor>>) {
+        *self.processors.lock().unwrap() = processors;
+    }
+
+    // Applies from the next start()
+    pub fn add_tap(&mut self, tap: impl Fn(&[i16], u32) + Send + Sync + 'static) {
+        self.taps.push(Arc::new(tap));
+    }
+
+    // Takes effect from the next utterance, also while running
+    pub fn set_endpointing(&self, config: &EndpointingConfig) {
+        self.vad.lock().unwrap().set_endpointing(config);
+    }
+
+    pub fn set_feeding(&self, feeding: bool) {
+        if !feeding {
+            self.vad.lock().unwrap().reset();
+        }
+        self.feeding.store(feeding, Ordering::SeqCst);
+    }
+
+    // How much speech reached the recognizer since the last call
+    pub fn take_fed_audio(&self) -> Duration {
+        Duration::from_micros(self.fed_audio_us.swap(0, Ordering::SeqCst))
+    }
+
+    // Start capturing and routing frames through the stages
+    pub fn start(&mut self) -> Result<()> {
+        if self.running {
+            return Ok(());
+        }
+        let processors = Arc::clone(&self.processors);
+        let taps = self.taps.clone();
+        let vad = Arc::clone(&self.vad);
+        let recognizer = Arc::clone(&self.recognizer);
+        let feeding = Arc::clone(&self.feeding);
+        let fed_audio_us = Arc::clone(&self.fed_audio_us);
+        let on_frame = move |mut frame: Vec<i16>, sample_rate: u32| {
+            for processor in processors.lock().unwrap().iter_mut() {
+                processor.process(&mut frame, sample_rate);
+            }
+            for tap in &taps {
+                tap(&frame, sample_rate);
+            }
+            if feeding.load(Ordering::SeqCst) {
+                vad.lock().unwrap().process(&frame, sample_rate, |speech| {
+                    recognizer.lock().unwrap().feed_audio(speech, sample_rate);
+                    fed_audio_us.fetch_add(speech.len() as u64 * 1_000_000 / sample_rate.max(1) as u64, Ordering::SeqCst);
+                });
+            }
+        };
+
+        if self.input_devices.len() > 1 {
+            let devices = self.input_devices.clone();
+            self.capture.start_multi_capture(devices, move |frame| on_frame(frame, audio::MIX_SAMPLE_RATE))?;
+        } else {
+            self.capture.start_capture_with_sample_rate(on_frame)?;
+        }
+        self.running = true;
+        Ok(())
+    }
+
+    // Stop and restart the flow of frames without reopening the device. False
+    // if the pipeline isn't running.
+    pub fn pause(&self) -> bool {
+        if !self.running || !self.capture.pause() {
+            return false;
+        }
+        self.vad.lock().unwrap().reset();
+        true
+    }
+
+    pub fn resume(&self) -> bool {
+        self.running && self.capture.resume()
+    }
+
+    // Stop the capture first so no frame is still on its way through, then
+    // clear the processors and the VAD. The recognizer belongs to the speech session and is
+    // stopped when that ends.
+    pub fn stop(&mut self) {
+        if !self.running {
+            return;
+        }
+        self.capture.stop_capture();
+        for processor in self.processors.lock().unwrap().iter_mut() {
+            processor.reset();
+        }
+        self.set_feeding(false);
+        self.running = false;
+    }
+}
+
+impl Drop for SpeechPipeline {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    // 10 ms frames
+    fn frame(amplitude: i16) -> Vec<i16> {
+        vec![amplitude; 160]
+    }
+
+    // How many frames the VAD passes on for `frame`
+    fn passed(vad: &mut VadStage, frame: &[i16]) -> usize {
+        let mut count = 0;
+        vad.process(frame, RATE, |_| count += 1);
+        count
+    }
+
+    fn endpointing(trailing_silence_ms: u64, min_speech_ms: u64, max_utterance_ms: u64) -> EndpointingConfig {
+        EndpointingConfig {
+            trailing_silence_ms,
+            max_utterance_ms,
+            min_speech_ms,
+        }
+    }
+
+    #[test]
+    fn vad_passes_speech_and_drops_silence() {
+        let mut vad = VadStage::new(0.01, Duration::ZERO);
+        assert_eq!(passed(&mut vad, &frame(0)), 0);
+        assert_eq!(passed(&mut vad, &frame(8_000)), 1);
+        assert_eq!(passed(&mut vad, &frame(10)), 0);
+    }
+
+    #[test]
+    fn vad_hangover_covers_short_pauses_only() {
+        let mut vad = VadStage::new(0.01, Duration::from_millis(30));
+        assert_eq!(passed(&mut vad, &frame(8_000)), 1);
+        for _ in 0..3 {
+            assert_eq!(passed(&mut vad, &frame(0)), 1);
+        }
+        assert_eq!(passed(&mut vad, &frame(0)), 0);
+
+        passed(&mut vad, &frame(8_000));
+        vad.reset();
+        assert_eq!(passed(&mut vad, &frame(0)), 0);
+    }
+
+    #[test]
+    fn vad_hangover_is_the_trailing_silence() {
+        let mut vad = VadStage::from_endpointing(0.01, &endpointing(800, 0, 15_000));
+        passed(&mut vad, &frame(8_000));
+        for _ in 0..80 {
+            assert_eq!(passed(&mut vad, &frame(0)), 1);
+        }
+        assert_eq!(passed(&mut vad, &frame(0)), 0);
+    }
+
+    #[test]
+    fn vad_holds_back_bursts_shorter_than_min_speech() {
+        let mut vad = VadStage::from_endpointing(0.01, &endpointing(20, 50, 15_000));
+        // A 30 ms click and its hangover never reach the recognizer
+        for _ in 0..3 {
+            assert_eq!(passed(&mut vad, &frame(8_000)), 0);
+        }
+        for _ in 0..4 {
+            assert_eq!(passed(&mut vad, &frame(0)), 0);
+        }
+
+        // Real speech comes out whole once it has lasted 50 ms
+        for _ in 0..4 {
+            assert_eq!(passed(&mut vad, &frame(8_000)), 0);
+        }
+        assert_eq!(passed(&mut vad, &frame(8_000)), 5);
+        assert_eq!(passed(&mut vad, &frame(8_000)), 1);
+    }
+
+    #[test]
+    fn vad_cuts_utterances_at_max_length_until_a_pause() {
+        let mut vad = VadStage::from_endpointing(0.01, &endpointing(20, 0, 100));
+        let fed: usize = (0..15).map(|_| passed(&mut vad, &frame(8_000))).sum();
+        assert_eq!(fed, 10);
+
+        // The pause ends the utterance, so the next one is heard again
+        for _ in 0..3 {
+            passed(&mut vad, &frame(0));
+        }
+        assert_eq!(passed(&mut vad, &frame(8_000)), 1);
+    }
+
+    #[test]
+    fn endpointing_changes_apply_to_a_running_vad() {
+        let mut vad = VadStage::new(0.01, Duration::ZERO);
+        vad.set_endpointing(&endpointing(20, 0, 30));
+        let fed: usize = (0..5).map(|_| passed(&mut vad, &frame(8_000))).sum();
+        assert_eq!(fed, 3);
+    }
+}
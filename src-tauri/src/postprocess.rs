@@ -0,0 +1,270 @@
+// postprocess.rs - Clean up final transcripts before they reach the frontend
+use serde::{Deserialize, Serialize};
+
+use crate::normalize::{self, Normalized};
+use crate::profanity;
+use crate::speech_recognition;
+use crate::vocabulary;
+
+// Each stage of the pipeline can be switched on or off independently
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessConfig {
+    pub collapse_whitespace: bool,
+    pub spoken_punctuation: bool,
+    pub sentence_case: bool,
+    pub terminal_punctuation: bool,
+    // Spoken numbers to digits, with the values reported as entities
+    pub normalize_numbers: bool,
+    pub profanity_filter: bool,
+    // Masked in addition to the built-in list
+    pub custom_profanity: Vec<String>,
+    // Near-misses of these phrases are corrected; managed by the vocabulary commands
+    pub vocabulary: Vec<String>,
+}
+
+impl Default for PostProcessConfig {
+    fn default() -> Self {
+        Self {
+            collapse_whitespace: true,
+            spoken_punctuation: true,
+            sentence_case: true,
+            terminal_punctuation: true,
+            normalize_numbers: false,
+            profanity_filter: false,
+            custom_profanity: Vec::new(),
+            vocabulary: Vec::new(),
+        }
+    }
+}
+
+// Spoken commands and what they turn into, longest phrases first so
+// "new paragraph" wins over anything starting with "new"
+const SPOKEN_PUNCTUATION: &[(&[&str], &str)] = &[
+    (&["new", "paragraph"], "\n\n"),
+    (&["new", "line"], "\n"),
+    (&["question", "mark"], "?"),
+    (&["exclamation", "mark"], "!"),
+    (&["exclamation", "point"], "!"),
+    (&["full", "stop"], "."),
+    (&["period"], "."),
+    (&["comma"], ","),
+    (&["colon"], ":"),
+    (&["semicolon"], ";"),
+];
+
+const SENTENCE_ENDINGS: &[char] = &['.', '?', '!'];
+
+// Run the enabled stages over a final transcript. Entities are only found
+// when number normalization is on.
+pub fn process(text: &str, config: &PostProcessConfig) -> Normalized {
+    let mut result = text.to_string();
+    if config.collapse_whitespace {
+        result = collapse_whitespace(&result);
+    }
+    if config.spoken_punctuation {
+        result = apply_spoken_punctuation(&result);
+    }
+    if config.sentence_case {
+        result = sentence_case(&result);
+    }
+    if config.terminal_punctuation {
+        result = add_terminal_punctuation(&result);
+    }
+    if !config.vocabulary.is_empty() {
+        result = vocabulary::correct(&result, &config.vocabulary);
+    }
+    if config.profanity_filter {
+        result = profanity::mask(&result, &config.custom_profanity);
+    }
+    // Last, so the entity spans match the final text
+    if config.normalize_numbers {
+        return normalize::normalize(&result);
+    }
+    Normalized {
+        text: result,
+        entities: Vec::new(),
+    }
+}
+
+// Alternatives are offered in place of the processed `primary`, so they get
+// the same cleanup and masking. That can make some of them the same again,
+// or the same as the primary; those are dropped.
+pub fn process_alternatives(primary: &str, alternatives: &[String], config: &PostProcessConfig) -> Vec<String> {
+    let processed = alternatives.iter().map(|alternative| process(alternative, config).text).collect();
+    speech_recognition::distinct_alternatives(primary, processed)
+}
+
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Replace spoken punctuation commands with the symbols they name. Marks attach
+// to the preceding word and repeated marks ("period period") collapse to one.
+pub fn apply_spoken_punctuation(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut output = String::new();
+    let mut i = 0;
+
+    while i < words.len() {
+        let command = SPOKEN_PUNCTUATION.iter().find(|(phrase, _)| {
+            phrase.len() <= words.len() - i
+                && phrase
+                    .iter()
+                    .zip(&words[i..])
+                    .all(|(expected, word)| word.eq_ignore_ascii_case(expected))
+        });
+
+        match command {
+            Some((phrase, mark)) => {
+                let trimmed_len = output.trim_end_matches(' ').len();
+                output.truncate(trimmed_len);
+                // Line breaks always apply; marks need a word to attach to
+                let is_break = mark.starts_with('\n');
+                if is_break || (!output.is_empty() && !output.ends_with(mark)) {
+                    output.push_str(mark);
+                }
+                i += phrase.len();
+            }
+            None => {
+                if !output.is_empty() && !output.ends_with('\n') {
+                    output.push(' ');
+                }
+                output.push_str(words[i]);
+                i += 1;
+            }
+        }
+    }
+
+    output
+}
+
+// Capitalize the first letter of every sentence
+pub fn sentence_case(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut capitalize_next = true;
+
+    for c in text.chars() {
+        if capitalize_next && c.is_alphabetic() {
+            output.extend(c.to_uppercase());
+            capitalize_next = false;
+        } else {
+            output.push(c);
+        }
+
+        if SENTENCE_ENDINGS.contains(&c) || c == '\n' {
+            capitalize_next = true;
+        } else if !c.is_whitespace() {
+            capitalize_next = false;
+        }
+    }
+
+    output
+}
+
+// End the utterance with a period unless it already ends a sentence
+pub fn add_terminal_punctuation(text: &str) -> String {
+    let trimmed = text.trim_end_matches(' ');
+    if trimmed.is_empty() || trimmed.ends_with('\n') || trimmed.ends_with(SENTENCE_ENDINGS) {
+        return trimmed.to_string();
+    }
+
+    // A dangling comma or colon at the end of an utterance becomes a period
+    let without_trailing = trimmed.trim_end_matches([',', ';', ':']);
+    format!("{}.", without_trailing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_whitespace() {
+        assert_eq!(collapse_whitespace("  hello \t there\n friend "), "hello there friend");
+    }
+
+    #[test]
+    fn spoken_punctuation_attaches_to_the_previous_word() {
+        assert_eq!(apply_spoken_punctuation("hello comma world period"), "hello, world.");
+        assert_eq!(apply_spoken_punctuation("is it done question mark"), "is it done?");
+        assert_eq!(apply_spoken_punctuation("wow Exclamation Point"), "wow!");
+        assert_eq!(apply_spoken_punctuation("note colon buy milk semicolon eggs full stop"), "note: buy milk; eggs.");
+    }
+
+    #[test]
+    fn repeated_marks_collapse() {
+        assert_eq!(apply_spoken_punctuation("done period period"), "done.");
+        assert_eq!(apply_spoken_punctuation("wait comma comma what"), "wait, what");
+    }
+
+    #[test]
+    fn marks_need_a_word_before_them() {
+        assert_eq!(apply_spoken_punctuation("period"), "");
+        assert_eq!(apply_spoken_punctuation("comma hello"), "hello");
+    }
+
+    #[test]
+    fn line_breaks_always_apply() {
+        assert_eq!(apply_spoken_punctuation("first new line second"), "first\nsecond");
+        assert_eq!(apply_spoken_punctuation("one new paragraph two"), "one\n\ntwo");
+        assert_eq!(apply_spoken_punctuation("new line new line"), "\n\n");
+    }
+
+    #[test]
+    fn capitalizes_after_sentence_endings_and_line_breaks() {
+        assert_eq!(sentence_case("hello. how are you? fine! ok"), "Hello. How are you? Fine! Ok");
+        assert_eq!(sentence_case("one\ntwo"), "One\nTwo");
+        // Commas and the middle of a sentence are left alone
+        assert_eq!(sentence_case("well, maybe iPhone"), "Well, maybe iPhone");
+        assert_eq!(sentence_case("3.5 apples"), "3.5 apples");
+    }
+
+    #[test]
+    fn terminal_punctuation() {
+        assert_eq!(add_terminal_punctuation("hello"), "hello.");
+        assert_eq!(add_terminal_punctuation("really?"), "really?");
+        assert_eq!(add_terminal_punctuation("and then, "), "and then.");
+        assert_eq!(add_terminal_punctuation("list:"), "list.");
+        assert_eq!(add_terminal_punctuation("end\n"), "end\n");
+        assert_eq!(add_terminal_punctuation(""), "");
+    }
+
+    #[test]
+    fn default_pipeline() {
+        let result = process("  what time is it question mark  tell me  ", &PostProcessConfig::default());
+        assert_eq!(result.text, "What time is it? Tell me.");
+        assert!(result.entities.is_empty());
+    }
+
+    #[test]
+    fn alternatives_are_processed_like_the_text() {
+        let config = PostProcessConfig { profanity_filter: true, ..PostProcessConfig::default() };
+        let primary = process("oh dam", &config).text;
+        let alternatives = ["oh damn", "oh dam", "Oh dam.", "oh  damn"].map(String::from);
+        assert_eq!(process_alternatives(&primary, &alternatives, &config), vec!["Oh ****."]);
+    }
+
+    #[test]
+    fn disabled_stages_are_skipped() {
+        let config = PostProcessConfig {
+            collapse_whitespace: false,
+            spoken_punctuation: false,
+            sentence_case: false,
+            terminal_punctuation: false,
+            ..PostProcessConfig::default()
+        };
+        assert_eq!(process("hello  period", &config).text, "hello  period");
+    }
+
+    #[test]
+    fn optional_stages() {
+        let config = PostProcessConfig {
+            normalize_numbers: true,
+            profanity_filter: true,
+            ..PostProcessConfig::default()
+        };
+        let result = process("twenty five damn apples", &config);
+        assert_eq!(result.text, "25 **** apples.");
+        assert_eq!(result.entities.len(), 1);
+    }
+}
@@ -0,0 +1,133 @@
+// profanity.rs - Mask profanity in transcripts shown on screen
+
+// Built-in words; users can add their own through the config
+const BUILT_IN_WORDS: &[&str] = &[
+    "arse", "arsehole", "ass", "asshole", "bastard", "bitch", "bitches", "bollocks", "bullshit",
+    "crap", "cunt", "cunts", "damn", "dick", "dickhead", "fuck", "fucked", "fucker", "fucking",
+    "fucks", "motherfucker", "piss", "pissed", "prick", "shit", "shits", "shitty", "slut",
+    "twat", "wanker", "whore",
+];
+
+// Characters commonly swapped in for letters
+const SUBSTITUTIONS: &[(char, char)] = &[
+    ('0', 'o'),
+    ('1', 'i'),
+    ('3', 'e'),
+    ('4', 'a'),
+    ('5', 's'),
+    ('$', 's'),
+    ('@', 'a'),
+];
+
+// Replace every profane word with asterisks. Only whole words are checked, so
+// "Scunthorpe" and "assessment" are left alone.
+pub fn mask(text: &str, extra_words: &[String]) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut word = String::new();
+
+    for c in text.chars() {
+        if is_word_char(c) {
+            word.push(c);
+        } else {
+            push_word(&mut output, &word, extra_words);
+            word.clear();
+            output.push(c);
+        }
+    }
+    push_word(&mut output, &word, extra_words);
+
+    output
+}
+
+fn push_word(output: &mut String, word: &str, extra_words: &[String]) {
+    // Symbols that stand in for letters can also be plain punctuation ("$5",
+    // "me@home"), so only look at the part between the outer letters
+    let start = word.find(char::is_alphanumeric).unwrap_or(word.len());
+    let end = word.rfind(char::is_alphanumeric).map_or(start, |i| i + 1);
+    let core = &word[start..end];
+
+    output.push_str(&word[..start]);
+    if is_profane(core, extra_words) {
+        output.extend(core.chars().map(|_| '*'));
+    } else {
+        output.push_str(core);
+    }
+    output.push_str(&word[end..]);
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\'' || SUBSTITUTIONS.iter().any(|(from, _)| *from == c)
+}
+
+fn is_profane(word: &str, extra_words: &[String]) -> bool {
+    // Substitutions only apply alongside real letters, so numbers like
+    // "455" aren't read as words
+    if !word.chars().any(char::is_alphabetic) {
+        return false;
+    }
+    let runs = letter_runs(word);
+    BUILT_IN_WORDS
+        .iter()
+        .copied()
+        .chain(extra_words.iter().map(String::as_str))
+        .any(|listed| matches_stretched(&runs, &letter_runs(listed)))
+}
+
+// Collapse a word into (letter, count) runs after undoing substitutions, so
+// "fuuuck" and "sh1t" line up with their listed spellings
+fn letter_runs(word: &str) -> Vec<(char, usize)> {
+    let mut runs: Vec<(char, usize)> = Vec::new();
+    for c in word.chars().filter(|c| *c != '\'') {
+        let c = SUBSTITUTIONS
+            .iter()
+            .find(|(from, _)| *from == c)
+            .map_or(c, |(_, to)| *to)
+            .to_ascii_lowercase();
+        match runs.last_mut() {
+            Some((last, count)) if *last == c => *count += 1,
+            _ => runs.push((c, 1)),
+        }
+    }
+    runs
+}
+
+// The word matches if it spells the listed word with letters repeated at least
+// as often as in the listing. "as" doesn't match "ass", but "asss" does.
+fn matches_stretched(word: &[(char, usize)], listed: &[(char, usize)]) -> bool {
+    word.len() == listed.len()
+        && word
+            .iter()
+            .zip(listed)
+            .all(|((c, count), (listed_c, listed_count))| c == listed_c && count >= listed_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masked(text: &str) -> String {
+        mask(text, &[])
+    }
+
+    #[test]
+    fn masks_listed_words_and_their_spellings() {
+        assert_eq!(masked("oh shit, that's it"), "oh ****, that's it");
+        assert_eq!(masked("what the FUUUCK"), "what the ******");
+        assert_eq!(masked("sh1t and a55"), "**** and ***");
+        assert_eq!(mask("darn it", &["darn".to_string()]), "**** it");
+    }
+
+    #[test]
+    fn numbers_are_left_alone() {
+        for text in ["455", "call 555 1234", "$455", "it costs $5", "0451", "3 a 5"] {
+            assert_eq!(masked(text), text);
+        }
+    }
+
+    #[test]
+    fn words_containing_listed_words_are_left_alone() {
+        for text in ["Scunthorpe", "class", "assess", "assessment", "passage", "cockpit", "shitake", "as", "me@home"] {
+            assert_eq!(masked(text), text);
+        }
+    }
+}
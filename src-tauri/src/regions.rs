@@ -0,0 +1,95 @@
+// regions.rs - Which parts of the window take mouse input. Everywhere else
+// clicks pass through to whatever is behind it.
+use serde::Deserialize;
+
+// How many polls in a row the cursor must stay on one side of a region edge
+// before click-through flips, so jitter along the edge doesn't flicker
+const SETTLE_POLLS: u32 = 2;
+
+// In logical pixels, relative to the top left of the window's client area
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+#[derive(Default)]
+pub struct InteractiveRegions {
+    regions: Vec<Rect>,
+    // Whether the window currently takes input, once decided
+    interactive: Option<bool>,
+    // A state the cursor has been in for `count` polls but isn't applied yet
+    pending: Option<(bool, u32)>,
+}
+
+impl InteractiveRegions {
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn set(&mut self, regions: Vec<Rect>) {
+        self.regions = regions;
+        self.interactive = None;
+        self.pending = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set(Vec::new());
+    }
+
+    // Feed the cursor position; returns whether the window should take input
+    // when that changes
+    pub fn update(&mut self, x: f64, y: f64) -> Option<bool> {
+        let inside = self.regions.iter().any(|region| region.contains(x, y));
+        if self.interactive == Some(inside) {
+            self.pending = None;
+            return None;
+        }
+        // The first decision is applied straight away
+        if self.interactive.is_none() {
+            self.interactive = Some(inside);
+            return Some(inside);
+        }
+        let count = match self.pending {
+            Some((state, count)) if state == inside => count + 1,
+            _ => 1,
+        };
+        if count < SETTLE_POLLS {
+            self.pending = Some((inside, count));
+            return None;
+        }
+        self.pending = None;
+        self.interactive = Some(inside);
+        Some(inside)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flips_only_after_the_cursor_settles() {
+        let mut regions = InteractiveRegions::default();
+        regions.set(vec![Rect { x: 10.0, y: 10.0, width: 100.0, height: 40.0 }]);
+        assert_eq!(regions.update(0.0, 0.0), Some(false));
+
+        // A single poll across the edge isn't enough
+        assert_eq!(regions.update(10.0, 20.0), None);
+        assert_eq!(regions.update(9.0, 20.0), None);
+        assert_eq!(regions.update(10.0, 20.0), None);
+        assert_eq!(regions.update(50.0, 20.0), Some(true));
+        assert_eq!(regions.update(50.0, 20.0), None);
+
+        assert_eq!(regions.update(110.0, 20.0), None);
+        assert_eq!(regions.update(200.0, 20.0), Some(false));
+    }
+}
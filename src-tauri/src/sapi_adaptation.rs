@@ -0,0 +1,221 @@
+// sapi_adaptation.rs - Adapt the user's SAPI speech profile to recorded
+// training phrases. Each recording is played into an in-process recognizer in
+// training mode, listening only for its prompt the way the Windows training
+// wizard does, then the engine adapts the profile from what it heard.
+use anyhow::Result;
+use std::ptr;
+use std::time::{Duration, Instant};
+use winapi::shared::guiddef::GUID;
+use winapi::shared::minwindef::{FALSE, TRUE};
+use winapi::shared::winerror::{FAILED, HRESULT, RPC_E_CHANGED_MODE, S_FALSE};
+use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize, CLSCTX_ALL};
+use winapi::um::objbase::COINIT_MULTITHREADED;
+use winapi::um::sapi51::{
+    ISpRecoContext, ISpRecoGrammar, ISpRecognizer, ISpStream, SPEVENT, SPEI_END_SR_STREAM,
+    SPEI_FALSE_RECOGNITION, SPEI_RECOGNITION, SPET_LPARAM_IS_OBJECT, SPET_LPARAM_IS_POINTER,
+    SPET_LPARAM_IS_STRING, SPET_LPARAM_IS_TOKEN, SPFEI, SPFM_OPEN_READONLY, SPRAF_Active,
+    SPRAF_TopLevel, SPRST_ACTIVE, SPRST_INACTIVE, SPRS_ACTIVE, SPSTATEHANDLE, SPWT_LEXICAL,
+};
+use winapi::um::sapi53::ISpRecognizer2;
+use winapi::um::unknwnbase::IUnknown;
+use winapi::Interface;
+
+// winapi declares these as statics in sapi.lib, which isn't linked
+const CLSID_SP_INPROC_RECOGNIZER: GUID = GUID {
+    Data1: 0x41b89b6b,
+    Data2: 0x9399,
+    Data3: 0x11d2,
+    Data4: [0x96, 0x23, 0x00, 0xc0, 0x4f, 0x8e, 0xe6, 0x28],
+};
+const CLSID_SP_STREAM: GUID = GUID {
+    Data1: 0x715d9c59,
+    Data2: 0x4442,
+    Data3: 0x11d2,
+    Data4: [0x96, 0x05, 0x00, 0xc0, 0x4f, 0x8e, 0xe6, 0x28],
+};
+
+// Recordings are read from disk faster than real time, so a phrase that
+// takes longer than this has stalled the engine
+const PHRASE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Releases the COM interface when dropped
+struct Com<T: Interface>(*mut T);
+
+impl<T: Interface> Drop for Com<T> {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { (*(self.0 as *mut IUnknown)).Release() };
+        }
+    }
+}
+
+fn check(hr: HRESULT, what: &str) -> Result<()> {
+    if FAILED(hr) {
+        return Err(anyhow::anyhow!("{} failed (HRESULT {:#010x})", what, hr));
+    }
+    Ok(())
+}
+
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(Some(0)).collect()
+}
+
+// A recorded phrase and the prompt that was read for it
+pub struct RecordedPhrase<'a> {
+    pub prompt: &'a str,
+    pub path: &'a str,
+}
+
+// Train on each recording and adapt the profile from the ones SAPI heard as
+// their prompt. Returns how many that was; with none, nothing is adapted.
+// Blocks until every recording has been played through.
+pub fn adapt_to_recordings(phrases: &[RecordedPhrase]) -> Result<usize> {
+    unsafe {
+        let hr = CoInitializeEx(ptr::null_mut(), COINIT_MULTITHREADED);
+        // A thread already set up as single-threaded works too, it just isn't ours to uninitialize
+        let initialized = !FAILED(hr);
+        if !initialized && hr != RPC_E_CHANGED_MODE {
+            check(hr, "CoInitializeEx")?;
+        }
+        let result = train(phrases);
+        if initialized {
+            CoUninitialize();
+        }
+        result
+    }
+}
+
+unsafe fn train(phrases: &[RecordedPhrase]) -> Result<usize> {
+    let mut recognizer: *mut ISpRecognizer = ptr::null_mut();
+    check(
+        CoCreateInstance(
+            &CLSID_SP_INPROC_RECOGNIZER,
+            ptr::null_mut(),
+            CLSCTX_ALL,
+            &ISpRecognizer::uuidof(),
+            &mut recognizer as *mut _ as *mut _,
+        ),
+        "Creating the SAPI recognizer",
+    )?;
+    let recognizer = Com(recognizer);
+
+    let mut trainer: *mut ISpRecognizer2 = ptr::null_mut();
+    check(
+        (*recognizer.0).QueryInterface(&ISpRecognizer2::uuidof(), &mut trainer as *mut _ as *mut _),
+        "Getting SAPI 5.3 training support",
+    )?;
+    let trainer = Com(trainer);
+
+    let mut context: *mut ISpRecoContext = ptr::null_mut();
+    check((*recognizer.0).CreateRecoContext(&mut context), "Creating the recognition context")?;
+    let context = Com(context);
+    check((*context.0).SetNotifyWin32Event(), "Setting up recognition events")?;
+    let interest = SPFEI(SPEI_RECOGNITION) | SPFEI(SPEI_FALSE_RECOGNITION) | SPFEI(SPEI_END_SR_STREAM);
+    check((*context.0).SetInterest(interest, interest), "Choosing recognition events")?;
+
+    check((*trainer.0).SetTrainingState(TRUE, TRUE), "Starting training")?;
+    let mut trained = 0;
+    let mut outcome = Ok(());
+    for (index, phrase) in phrases.iter().enumerate() {
+        match train_phrase(&recognizer, &context, index as u64 + 1, phrase) {
+            Ok(true) => trained += 1,
+            Ok(false) => println!("🎓 SAPI didn't hear \"{}\" in {}, skipping it", phrase.prompt, phrase.path),
+            Err(e) => {
+                outcome = Err(e);
+                break;
+            }
+        }
+    }
+
+    // Adapt from what was heard, or throw the training data away
+    let adapt = outcome.is_ok() && trained > 0;
+    check((*trainer.0).SetTrainingState(FALSE, if adapt { TRUE } else { FALSE }), "Adapting the speech profile")?;
+    outcome.map(|()| trained)
+}
+
+// Play one recording against a grammar of just its prompt; true if SAPI
+// recognized it
+unsafe fn train_phrase(recognizer: &Com<ISpRecognizer>, context: &Com<ISpRecoContext>, grammar_id: u64, phrase: &RecordedPhrase) -> Result<bool> {
+    let mut grammar: *mut ISpRecoGrammar = ptr::null_mut();
+    check((*context.0).CreateGrammer(grammar_id, &mut grammar), "Creating the prompt grammar")?;
+    let grammar = Com(grammar);
+    let mut rule: SPSTATEHANDLE = ptr::null_mut();
+    check(
+        (*grammar.0).GetRule(ptr::null(), 1, SPRAF_TopLevel | SPRAF_Active, TRUE, &mut rule),
+        "Creating the prompt rule",
+    )?;
+    let prompt = wide(phrase.prompt);
+    let separators = wide(" ");
+    check(
+        (*grammar.0).AddWordTransition(rule, ptr::null_mut(), prompt.as_ptr(), separators.as_ptr(), SPWT_LEXICAL, 1.0, ptr::null()),
+        "Adding the prompt",
+    )?;
+    check((*grammar.0).Commit(0), "Compiling the prompt grammar")?;
+    check((*grammar.0).SetRuleState(ptr::null(), ptr::null_mut(), SPRS_ACTIVE), "Activating the prompt grammar")?;
+
+    let mut stream: *mut ISpStream = ptr::null_mut();
+    check(
+        CoCreateInstance(
+            &CLSID_SP_STREAM,
+            ptr::null_mut(),
+            CLSCTX_ALL,
+            &ISpStream::uuidof(),
+            &mut stream as *mut _ as *mut _,
+        ),
+        "Creating the audio stream",
+    )?;
+    let stream = Com(stream);
+    let path = wide(phrase.path);
+    // No format given, so it's read from the WAV header
+    check(
+        (*stream.0).BindToFile(path.as_ptr(), SPFM_OPEN_READONLY, ptr::null(), ptr::null(), 0),
+        &format!("Opening {}", phrase.path),
+    )?;
+    check((*recognizer.0).SetInput(stream.0 as *mut IUnknown, TRUE), "Feeding the recording to SAPI")?;
+    check((*recognizer.0).SetRecoState(SPRST_ACTIVE), "Starting recognition")?;
+
+    let recognized = wait_for_stream_end(context, phrase.path);
+    // Stop before the next phrase swaps the input
+    (*recognizer.0).SetRecoState(SPRST_INACTIVE);
+    recognized
+}
+
+// Drain events until SAPI reaches the end of the recording; true if it was
+// recognized along the way
+unsafe fn wait_for_stream_end(context: &Com<ISpRecoContext>, path: &str) -> Result<bool> {
+    let deadline = Instant::now() + PHRASE_TIMEOUT;
+    let mut recognized = false;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let hr = (*context.0).WaitForNotifyEvent(remaining.as_millis() as u32);
+        check(hr, "Waiting for SAPI")?;
+        if hr == S_FALSE {
+            return Err(anyhow::anyhow!("SAPI didn't finish {} within {} s", path, PHRASE_TIMEOUT.as_secs()));
+        }
+
+        let mut event: SPEVENT = std::mem::zeroed();
+        let mut fetched = 0;
+        while (*context.0).GetEvents(1, &mut event, &mut fetched) == 0 && fetched == 1 {
+            let id = event.eEventId();
+            free_event(&event);
+            if id == SPEI_RECOGNITION {
+                recognized = true;
+            } else if id == SPEI_END_SR_STREAM {
+                return Ok(recognized);
+            }
+        }
+    }
+}
+
+// What SpClearEvent does: events own their lParam
+unsafe fn free_event(event: &SPEVENT) {
+    match event.elParamType() {
+        SPET_LPARAM_IS_TOKEN | SPET_LPARAM_IS_OBJECT if event.lParam != 0 => {
+            (*(event.lParam as *mut IUnknown)).Release();
+        }
+        SPET_LPARAM_IS_POINTER | SPET_LPARAM_IS_STRING if event.lParam != 0 => {
+            CoTaskMemFree(event.lParam as *mut _);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,66 @@
+// sapi_health.rs - Whether Windows speech recognition is installed and usable,
+// so a broken install gets a clear message instead of a bare COM error
+use sapi_lite::stt::Recognizer;
+use serde::Serialize;
+use winreg::enums::HKEY_LOCAL_MACHINE;
+use winreg::RegKey;
+
+// Under HKEY_LOCAL_MACHINE; every installed recognizer registers a token here
+const RECOGNIZERS_KEY: &str = r"SOFTWARE\Microsoft\Speech\Recognizers";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SapiHealthReport {
+    // At least one recognizer is registered
+    pub installed: bool,
+    // SAPI initialized and a recognizer could be created
+    pub can_initialize: bool,
+    pub recognizers_available: u32,
+    // Version of the default recognizer, if it reports one
+    pub version: Option<String>,
+    pub error: Option<String>,
+    pub registry_path: String,
+}
+
+// The default token is stored as a full registry path; only its part under
+// HKEY_LOCAL_MACHINE can be read back
+fn default_recognizer_version(machine: &RegKey) -> Option<String> {
+    let token: String = machine.open_subkey(RECOGNIZERS_KEY).ok()?.get_value("DefaultTokenId").ok()?;
+    let token = token.strip_prefix(r"HKEY_LOCAL_MACHINE\")?;
+    machine.open_subkey(format!(r"{}\Attributes", token)).ok()?.get_value("Version").ok()
+}
+
+// Check the registry, then initialize SAPI and create a recognizer, releasing
+// both again straight away
+pub fn check() -> SapiHealthReport {
+    let machine = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let recognizers_available = machine
+        .open_subkey(format!(r"{}\Tokens", RECOGNIZERS_KEY))
+        .and_then(|tokens| tokens.query_info())
+        .map_or(0, |info| info.sub_keys);
+    let mut report = SapiHealthReport {
+        installed: recognizers_available > 0,
+        can_initialize: false,
+        recognizers_available,
+        version: default_recognizer_version(&machine),
+        error: None,
+        registry_path: format!(r"HKEY_LOCAL_MACHINE\{}", RECOGNIZERS_KEY),
+    };
+
+    match sapi_lite::initialize() {
+        Ok(()) => {
+            match Recognizer::new() {
+                Ok(recognizer) => {
+                    drop(recognizer);
+                    report.can_initialize = true;
+                }
+                Err(e) => report.error = Some(format!("Failed to create recognizer: {:?}", e)),
+            }
+            sapi_lite::finalize();
+        }
+        Err(e) => report.error = Some(format!("Failed to initialize SAPI: {:?}", e)),
+    }
+    if report.error.is_none() && !report.installed {
+        report.error = Some(format!("No speech recognizers registered under {}", report.registry_path));
+    }
+    report
+}
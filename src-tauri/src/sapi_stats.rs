@@ -0,0 +1,120 @@
+// sapi_stats.rs - Counts of what SAPI recognized, for diagnostics. SAPI keeps
+// no such statistics itself, so the recognition loop records them here.
+use serde::Serialize;
+use std::time::Instant;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SapiStats {
+    // Phrases SAPI returned
+    pub total_utterances: u64,
+    // Ones acted on: a wake word that fired or a session phrase
+    pub accepted: u64,
+    // Ignored wake words (do not disturb, too quiet) and phrases nothing used
+    pub rejected: u64,
+    // Over the phrases that had a confidence; None if none did
+    pub average_confidence: Option<f32>,
+    // How long recognition has been running, 0 while stopped
+    pub uptime_secs: u64,
+    // HRESULT of the most recent failed SAPI call
+    pub last_error_code: Option<u32>,
+}
+
+#[derive(Default)]
+pub struct SapiStatsTracker {
+    started: Option<Instant>,
+    accepted: u64,
+    rejected: u64,
+    confidence_sum: f64,
+    confidence_count: u64,
+    last_error_code: Option<u32>,
+}
+
+impl SapiStatsTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&mut self, now: Instant) {
+        self.started = Some(now);
+    }
+
+    pub fn stop(&mut self) {
+        self.started = None;
+    }
+
+    pub fn record_phrase(&mut self, accepted: bool, confidence: Option<f32>) {
+        if accepted {
+            self.accepted += 1;
+        } else {
+            self.rejected += 1;
+        }
+        if let Some(confidence) = confidence {
+            self.confidence_sum += confidence as f64;
+            self.confidence_count += 1;
+        }
+    }
+
+    pub fn record_error(&mut self, code: u32) {
+        self.last_error_code = Some(code);
+    }
+
+    // Clears the counts and the last error; uptime keeps running
+    pub fn reset(&mut self) {
+        *self = Self { started: self.started, ..Self::default() };
+    }
+
+    pub fn snapshot(&self, now: Instant) -> SapiStats {
+        SapiStats {
+            total_utterances: self.accepted + self.rejected,
+            accepted: self.accepted,
+            rejected: self.rejected,
+            average_confidence: (self.confidence_count > 0).then(|| (self.confidence_sum / self.confidence_count as f64) as f32),
+            uptime_secs: self.started.map_or(0, |started| now.saturating_duration_since(started).as_secs()),
+            last_error_code: self.last_error_code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn counts_phrases_and_averages_known_confidences() {
+        let start = Instant::now();
+        let mut tracker = SapiStatsTracker::new();
+        tracker.start(start);
+        tracker.record_phrase(true, Some(1.0));
+        tracker.record_phrase(true, Some(0.5));
+        tracker.record_phrase(false, None);
+        tracker.record_error(0x8004_5003);
+
+        let stats = tracker.snapshot(start + Duration::from_secs(90));
+        assert_eq!(stats.total_utterances, 3);
+        assert_eq!(stats.accepted, 2);
+        assert_eq!(stats.rejected, 1);
+        assert_eq!(stats.average_confidence, Some(0.75));
+        assert_eq!(stats.uptime_secs, 90);
+        assert_eq!(stats.last_error_code, Some(0x8004_5003));
+    }
+
+    #[test]
+    fn reset_keeps_the_uptime() {
+        let start = Instant::now();
+        let mut tracker = SapiStatsTracker::new();
+        tracker.start(start);
+        tracker.record_phrase(true, Some(1.0));
+        tracker.record_error(1);
+        tracker.reset();
+
+        let stats = tracker.snapshot(start + Duration::from_secs(5));
+        assert_eq!(stats.total_utterances, 0);
+        assert_eq!(stats.average_confidence, None);
+        assert_eq!(stats.last_error_code, None);
+        assert_eq!(stats.uptime_secs, 5);
+
+        tracker.stop();
+        assert_eq!(tracker.snapshot(start + Duration::from_secs(5)).uptime_secs, 0);
+    }
+}
@@ -0,0 +1,86 @@
+// screenshot.rs - Capture the window's region of the screen to a PNG, for bug reports
+use anyhow::Result;
+use std::fs::File;
+use std::io::BufWriter;
+use std::mem;
+use std::path::Path;
+use std::ptr;
+use tauri::{Runtime, WebviewWindow};
+use winapi::shared::windef::HDC;
+use winapi::um::wingdi::{
+    BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits, SelectObject, BITMAPINFO,
+    BITMAPINFOHEADER, BI_RGB, CAPTUREBLT, DIB_RGB_COLORS, SRCCOPY,
+};
+use winapi::um::winuser::{GetDC, ReleaseDC};
+
+// Copy what's on screen where the window is, so overlapping windows and the
+// transparent parts look the way the user saw them
+pub fn capture_window<R: Runtime>(window: &WebviewWindow<R>, path: &Path) -> Result<()> {
+    let position = window.outer_position()?;
+    let size = window.outer_size()?;
+    if size.width == 0 || size.height == 0 {
+        return Err(anyhow::anyhow!("The window has no size to capture"));
+    }
+    let pixels = capture_screen(position.x, position.y, size.width, size.height)?;
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, size.width, size.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(())
+}
+
+// RGBA pixels of a screen rectangle, top row first
+fn capture_screen(x: i32, y: i32, width: u32, height: u32) -> Result<Vec<u8>> {
+    unsafe {
+        let screen = GetDC(ptr::null_mut());
+        if screen.is_null() {
+            return Err(anyhow::anyhow!("Failed to get the screen device context"));
+        }
+        let result = copy_from(screen, x, y, width, height);
+        ReleaseDC(ptr::null_mut(), screen);
+        result
+    }
+}
+
+unsafe fn copy_from(screen: HDC, x: i32, y: i32, width: u32, height: u32) -> Result<Vec<u8>> {
+    let memory = CreateCompatibleDC(screen);
+    let bitmap = CreateCompatibleBitmap(screen, width as i32, height as i32);
+    if memory.is_null() || bitmap.is_null() {
+        DeleteObject(bitmap as _);
+        DeleteDC(memory);
+        return Err(anyhow::anyhow!("Failed to create a bitmap to capture into"));
+    }
+    let previous = SelectObject(memory, bitmap as _);
+
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    let copied = BitBlt(memory, 0, 0, width as i32, height as i32, screen, x, y, SRCCOPY | CAPTUREBLT) != 0;
+    let mut info: BITMAPINFO = mem::zeroed();
+    info.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as u32;
+    info.bmiHeader.biWidth = width as i32;
+    // Negative height asks for the top row first
+    info.bmiHeader.biHeight = -(height as i32);
+    info.bmiHeader.biPlanes = 1;
+    info.bmiHeader.biBitCount = 32;
+    info.bmiHeader.biCompression = BI_RGB;
+    let rows = if copied {
+        GetDIBits(memory, bitmap, 0, height, pixels.as_mut_ptr() as _, &mut info, DIB_RGB_COLORS)
+    } else {
+        0
+    };
+
+    SelectObject(memory, previous);
+    DeleteObject(bitmap as _);
+    DeleteDC(memory);
+    if rows == 0 {
+        return Err(anyhow::anyhow!("Failed to copy the screen: {}", std::io::Error::last_os_error()));
+    }
+
+    // GDI gives BGRA with an undefined alpha byte
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+        pixel[3] = 255;
+    }
+    Ok(pixels)
+}
@@ -0,0 +1,474 @@
+// session.rs - Orchestrates speech sessions from wake word to stop phrase or timeout
+use crate::speech_recognition::RecognitionResult;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+
+// A session with no recognized speech for this long is closed
+pub const DEFAULT_SESSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+// The session timeout in settings, in seconds; 0 never times out
+pub fn timeout_from_secs(seconds: u64) -> Option<Duration> {
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+pub const DEFAULT_STOP_PHRASES: &[&str] = &["stop listening", "goodbye", "bye jackson"];
+
+#[derive(Debug)]
+pub enum SessionEvent {
+    Open,
+    Close,
+    Result(RecognitionResult),
+    // None never times out
+    SetTimeout(Option<Duration>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    // Closed from outside (window hidden, detection stopped)
+    Requested,
+    StopPhrase,
+    Timeout,
+    // The wake word fired again while a session was active
+    BargeIn,
+}
+
+// Where the orchestrator's decisions go. The app implementation emits the
+// frontend events; tests can record the calls instead.
+pub trait SessionSink: Send + 'static {
+    fn session_opened(&mut self, session_id: u64);
+    // Results outside a session (e.g. constrained phrases) have no session id
+    fn result(&mut self, session_id: Option<u64>, result: RecognitionResult);
+    fn session_closed(&mut self, session_id: u64, reason: CloseReason);
+}
+
+// While the assistant's own voice is playing, recognition results are dropped
+// so it can't transcribe itself or match a stop phrase in its reply
+#[derive(Default)]
+struct OutputGate {
+    playing_until: Mutex<Option<Instant>>,
+    suppressed_results: AtomicU64,
+}
+
+// Cheap to clone; everything that produces session input holds one
+#[derive(Clone)]
+pub struct SessionHandle {
+    sender: UnboundedSender<SessionEvent>,
+    output_gate: Arc<OutputGate>,
+    // While the microphone is muted nothing heard is acted on, whichever
+    // recognizer heard it
+    microphone_paused: Arc<AtomicBool>,
+    // While recognition is constrained, only these phrases get through; empty
+    // lets everything through
+    constraints: Arc<Mutex<Vec<String>>>,
+}
+
+impl SessionHandle {
+    // Set by whatever plays audio back. The flag clears itself after
+    // `max_duration` in case the call that ends playback never arrives.
+    pub fn set_output_playing(&self, playing: bool, max_duration: Duration) {
+        let until = playing.then(|| Instant::now() + max_duration);
+        *self.output_gate.playing_until.lock().unwrap() = until;
+    }
+
+    pub fn output_playing(&self) -> bool {
+        let mut playing_until = self.output_gate.playing_until.lock().unwrap();
+        match *playing_until {
+            Some(until) if Instant::now() >= until => {
+                println!("⏱️ Output playing flag expired");
+                *playing_until = None;
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    // Results dropped while output was playing, since startup
+    pub fn suppressed_results(&self) -> u64 {
+        self.output_gate.suppressed_results.load(Ordering::SeqCst)
+    }
+
+    pub fn set_microphone_paused(&self, paused: bool) {
+        self.microphone_paused.store(paused, Ordering::SeqCst);
+    }
+
+    pub fn microphone_paused(&self) -> bool {
+        self.microphone_paused.load(Ordering::SeqCst)
+    }
+
+    pub fn open(&self) {
+        let _ = self.sender.send(SessionEvent::Open);
+    }
+
+    pub fn close(&self) {
+        let _ = self.sender.send(SessionEvent::Close);
+    }
+
+    // A running session gets the new timeout from now
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        let _ = self.sender.send(SessionEvent::SetTimeout(timeout));
+    }
+
+    pub fn set_constraints(&self, phrases: Vec<String>) {
+        *self.constraints.lock().unwrap() = phrases;
+    }
+
+    // Tag the result with the constraint it matches, word for word. False if
+    // constraints are set and it matches none of them, as with free speech
+    // from the browser or SAPI's session grammars.
+    fn constrain(&self, result: &mut RecognitionResult) -> bool {
+        let constraints = self.constraints.lock().unwrap();
+        if constraints.is_empty() || result.constraint_index.is_some() {
+            return true;
+        }
+        let heard = words(&result.raw_text);
+        result.constraint_index = constraints.iter().position(|phrase| words(phrase) == heard);
+        result.constraint_index.is_some()
+    }
+
+    pub fn push_result(&self, mut result: RecognitionResult) {
+        if !self.constrain(&mut result) {
+            println!("🔒 Dropped \"{}\", it isn't one of the constraint phrases", result.raw_text);
+            return;
+        }
+        if self.microphone_paused() {
+            println!("🔇 Dropped \"{}\" while the microphone is paused", result.raw_text);
+            return;
+        }
+        if self.output_playing() {
+            self.output_gate.suppressed_results.fetch_add(1, Ordering::SeqCst);
+            println!("🔇 Suppressed \"{}\" while output is playing", result.raw_text);
+            return;
+        }
+        let _ = self.sender.send(SessionEvent::Result(result));
+    }
+}
+
+pub fn channel() -> (SessionHandle, UnboundedReceiver<SessionEvent>) {
+    let (sender, receiver) = unbounded_channel();
+    let handle = SessionHandle {
+        sender,
+        output_gate: Arc::new(OutputGate::default()),
+        microphone_paused: Arc::new(AtomicBool::new(false)),
+        constraints: Arc::new(Mutex::new(Vec::new())),
+    };
+    (handle, receiver)
+}
+
+pub struct SessionOrchestrator<S: SessionSink> {
+    receiver: UnboundedReceiver<SessionEvent>,
+    sink: S,
+    timeout: Option<Duration>,
+    stop_phrases: Vec<String>,
+    active_session: Option<u64>,
+    next_session_id: u64,
+    deadline: Option<Instant>,
+}
+
+impl<S: SessionSink> SessionOrchestrator<S> {
+    pub fn new(
+        receiver: UnboundedReceiver<SessionEvent>,
+        sink: S,
+        timeout: Option<Duration>,
+        stop_phrases: Vec<String>,
+    ) -> Self {
+        Self {
+            receiver,
+            sink,
+            timeout,
+            stop_phrases,
+            active_session: None,
+            next_session_id: 1,
+            deadline: None,
+        }
+    }
+
+    // Process events until every SessionHandle is dropped
+    pub async fn run(mut self) {
+        loop {
+            let event = match self.deadline {
+                Some(deadline) => tokio::select! {
+                    event = self.receiver.recv() => event,
+                    _ = tokio::time::sleep_until(deadline) => {
+                        self.close(CloseReason::Timeout);
+                        continue;
+                    }
+                },
+                None => self.receiver.recv().await,
+            };
+
+            match event {
+                Some(event) => self.handle(event),
+                None => break,
+            }
+        }
+
+        self.close(CloseReason::Requested);
+    }
+
+    pub fn handle(&mut self, event: SessionEvent) {
+        match event {
+            SessionEvent::Open => {
+                if self.active_session.is_some() {
+                    self.close(CloseReason::BargeIn);
+                }
+                let session_id = self.next_session_id;
+                self.next_session_id += 1;
+                self.active_session = Some(session_id);
+                self.deadline = self.next_deadline();
+                println!("🟢 Speech session {} opened", session_id);
+                self.sink.session_opened(session_id);
+            }
+            SessionEvent::Close => self.close(CloseReason::Requested),
+            SessionEvent::Result(result) => {
+                let Some(session_id) = self.active_session else {
+                    self.sink.result(None, result);
+                    return;
+                };
+
+                let is_stop_phrase = self.is_stop_phrase(&result.raw_text);
+                self.deadline = self.next_deadline();
+                self.sink.result(Some(session_id), result);
+                if is_stop_phrase {
+                    self.close(CloseReason::StopPhrase);
+                }
+            }
+            SessionEvent::SetTimeout(timeout) => {
+                self.timeout = timeout;
+                if self.active_session.is_some() {
+                    self.deadline = self.next_deadline();
+                }
+            }
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    // The phrase's words must appear together in the utterance: "goodbye"
+    // stops on "ok, goodbye" but not on "their goodbyes"
+    fn is_stop_phrase(&self, text: &str) -> bool {
+        let text = words(text);
+        self.stop_phrases.iter().any(|phrase| {
+            let phrase = words(phrase);
+            !phrase.is_empty() && text.windows(phrase.len()).any(|window| window == phrase.as_slice())
+        })
+    }
+
+    fn close(&mut self, reason: CloseReason) {
+        if let Some(session_id) = self.active_session.take() {
+            self.deadline = None;
+            println!("🔴 Speech session {} closed ({:?})", session_id, reason);
+            self.sink.session_closed(session_id, reason);
+        }
+    }
+}
+
+// Lowercase words, without punctuation
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Call {
+        Opened(u64),
+        Result(Option<u64>, String),
+        Closed(u64, CloseReason),
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        calls: Arc<Mutex<Vec<Call>>>,
+    }
+
+    impl SessionSink for RecordingSink {
+        fn session_opened(&mut self, session_id: u64) {
+            self.calls.lock().unwrap().push(Call::Opened(session_id));
+        }
+
+        fn result(&mut self, session_id: Option<u64>, result: RecognitionResult) {
+            self.calls.lock().unwrap().push(Call::Result(session_id, result.raw_text));
+        }
+
+        fn session_closed(&mut self, session_id: u64, reason: CloseReason) {
+            self.calls.lock().unwrap().push(Call::Closed(session_id, reason));
+        }
+    }
+
+    fn result(text: &str) -> RecognitionResult {
+        RecognitionResult {
+            text: text.to_string(),
+            raw_text: text.to_string(),
+            constraint_index: None,
+            confidence: Some(0.9),
+            source: "test".to_string(),
+            entities: Vec::new(),
+            fallbacks: 0,
+            alternatives: Vec::new(),
+        }
+    }
+
+    // Runs an orchestrator with the default stop phrases; returns its handle,
+    // the recorded calls and the task
+    fn start(timeout: Option<Duration>) -> (SessionHandle, Arc<Mutex<Vec<Call>>>, tokio::task::JoinHandle<()>) {
+        let (handle, receiver) = channel();
+        let sink = RecordingSink::default();
+        let calls = sink.calls.clone();
+        let stop_phrases = DEFAULT_STOP_PHRASES.iter().map(|phrase| phrase.to_string()).collect();
+        let task = tokio::spawn(SessionOrchestrator::new(receiver, sink, timeout, stop_phrases).run());
+        (handle, calls, task)
+    }
+
+    // Dropping the handle ends the run once every event is handled
+    async fn finish(handle: SessionHandle, task: tokio::task::JoinHandle<()>) {
+        drop(handle);
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stop_phrase_closes_the_session() {
+        let (handle, calls, task) = start(None);
+        handle.open();
+        handle.push_result(result("what time is it"));
+        handle.push_result(result("OK, goodbye."));
+        handle.push_result(result("after"));
+        finish(handle, task).await;
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                Call::Opened(1),
+                Call::Result(Some(1), "what time is it".into()),
+                Call::Result(Some(1), "OK, goodbye.".into()),
+                Call::Closed(1, CloseReason::StopPhrase),
+                Call::Result(None, "after".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stop_phrases_match_whole_words() {
+        let (handle, calls, task) = start(None);
+        handle.open();
+        handle.push_result(result("they said their goodbyes"));
+        handle.push_result(result("stop listening to that song"));
+        finish(handle, task).await;
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls[1], Call::Result(Some(1), "they said their goodbyes".into()));
+        assert_eq!(calls[2], Call::Result(Some(1), "stop listening to that song".into()));
+        assert_eq!(calls[3], Call::Closed(1, CloseReason::StopPhrase));
+    }
+
+    #[tokio::test]
+    async fn silence_times_the_session_out() {
+        let (handle, calls, task) = start(Some(Duration::from_millis(50)));
+        handle.open();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(*calls.lock().unwrap(), [Call::Opened(1), Call::Closed(1, CloseReason::Timeout)]);
+        finish(handle, task).await;
+    }
+
+    #[tokio::test]
+    async fn results_push_the_timeout_back() {
+        let (handle, calls, task) = start(Some(Duration::from_millis(300)));
+        handle.open();
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            handle.push_result(result("still talking"));
+        }
+        assert!(!calls.lock().unwrap().contains(&Call::Closed(1, CloseReason::Timeout)));
+        finish(handle, task).await;
+        assert_eq!(calls.lock().unwrap().last(), Some(&Call::Closed(1, CloseReason::Requested)));
+    }
+
+    #[tokio::test]
+    async fn wake_word_during_a_session_barges_in() {
+        let (handle, calls, task) = start(None);
+        handle.open();
+        handle.open();
+        handle.close();
+        finish(handle, task).await;
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                Call::Opened(1),
+                Call::Closed(1, CloseReason::BargeIn),
+                Call::Opened(2),
+                Call::Closed(2, CloseReason::Requested),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn nothing_is_heard_while_the_microphone_is_paused() {
+        let (handle, calls, task) = start(None);
+        handle.open();
+        // Clones share the flag, as the detector's handle does with the app's
+        handle.clone().set_microphone_paused(true);
+        assert!(handle.microphone_paused());
+        handle.push_result(result("goodbye"));
+        handle.set_microphone_paused(false);
+        handle.push_result(result("hello"));
+        finish(handle, task).await;
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [Call::Opened(1), Call::Result(Some(1), "hello".into()), Call::Closed(1, CloseReason::Requested)]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_constraint_phrases_get_through_while_constrained() {
+        let (handle, calls, task) = start(None);
+        handle.set_constraints(vec!["yes".to_string(), "no thanks".to_string()]);
+        let mut matched = result("No, thanks!");
+        assert!(handle.constrain(&mut matched));
+        assert_eq!(matched.constraint_index, Some(1));
+        handle.push_result(result("yes please"));
+        handle.push_result(result("No, thanks!"));
+        // Tagged by the recognizer's own constraint grammar
+        let mut tagged = result("yeah");
+        tagged.constraint_index = Some(0);
+        handle.push_result(tagged);
+        handle.set_constraints(Vec::new());
+        handle.push_result(result("anything"));
+        finish(handle, task).await;
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                Call::Result(None, "No, thanks!".into()),
+                Call::Result(None, "yeah".into()),
+                Call::Result(None, "anything".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn results_are_dropped_while_output_plays() {
+        let (handle, calls, task) = start(None);
+        handle.open();
+        handle.set_output_playing(true, Duration::from_secs(10));
+        handle.push_result(result("goodbye"));
+        handle.set_output_playing(false, Duration::from_secs(10));
+        assert_eq!(handle.suppressed_results(), 1);
+        finish(handle, task).await;
+
+        assert_eq!(*calls.lock().unwrap(), [Call::Opened(1), Call::Closed(1, CloseReason::Requested)]);
+    }
+}
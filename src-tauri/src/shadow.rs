@@ -0,0 +1,25 @@
+// shadow.rs - The DWM drop shadow, which looks wrong around a transparent overlay
+use anyhow::Result;
+use std::mem;
+use tauri::{Runtime, WebviewWindow};
+use winapi::shared::windef::HWND;
+use winapi::shared::winerror::FAILED;
+use winapi::um::dwmapi::{DwmSetWindowAttribute, DWMNCRP_DISABLED, DWMNCRP_ENABLED, DWMWA_NCRENDERING_POLICY};
+
+// Non-client rendering draws the shadow, so turning it off removes it
+pub fn set_shadow<R: Runtime>(window: &WebviewWindow<R>, enabled: bool) -> Result<()> {
+    let policy = if enabled { DWMNCRP_ENABLED } else { DWMNCRP_DISABLED };
+    let hwnd = window.hwnd()?.0 as HWND;
+    let hr = unsafe {
+        DwmSetWindowAttribute(
+            hwnd,
+            DWMWA_NCRENDERING_POLICY,
+            &policy as *const u32 as *const _,
+            mem::size_of::<u32>() as u32,
+        )
+    };
+    if FAILED(hr) {
+        return Err(anyhow::anyhow!("Failed to set the window shadow (HRESULT {:#010x})", hr));
+    }
+    Ok(())
+}
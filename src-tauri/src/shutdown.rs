@@ -0,0 +1,102 @@
+// shutdown.rs - Stopping everything in order before the process exits, so the
+// recognition thread is out of SAPI and the microphone is released instead
+// of being left for the OS to clean up
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// Longest the worker threads are waited for before exiting anyway
+pub const JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+// The steps of a shutdown, in the order they run. The app implements them on
+// its state; tests use fakes.
+pub trait ShutdownSteps {
+    // Tell the frontend, so it can flush what it has
+    fn notify(&mut self);
+    // Ask the recognition thread to stop and close any session
+    fn stop_detection(&mut self);
+    // Close the capture and playback streams
+    fn stop_audio(&mut self);
+    // False if the workers were still running after `timeout`
+    fn join_workers(&mut self, timeout: Duration) -> bool;
+    fn persist(&mut self);
+}
+
+// Runs the steps the first time it's called; quitting goes through here and
+// then through the run loop's exit events, which find it done. Returns
+// whether this call did the shutdown.
+pub fn run(started: &AtomicBool, steps: &mut impl ShutdownSteps) -> bool {
+    if started.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+    println!("👋 Shutting down...");
+    steps.notify();
+    steps.stop_detection();
+    steps.stop_audio();
+    if !steps.join_workers(JOIN_TIMEOUT) {
+        eprintln!("⚠️ Worker threads still running after {} ms, exiting anyway", JOIN_TIMEOUT.as_millis());
+    }
+    // Settings are saved even if a thread hung
+    steps.persist();
+    println!("👋 Shutdown complete");
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordedSteps {
+        calls: Vec<&'static str>,
+        workers_finish: bool,
+    }
+
+    impl ShutdownSteps for RecordedSteps {
+        fn notify(&mut self) {
+            self.calls.push("notify");
+        }
+
+        fn stop_detection(&mut self) {
+            self.calls.push("stop_detection");
+        }
+
+        fn stop_audio(&mut self) {
+            self.calls.push("stop_audio");
+        }
+
+        fn join_workers(&mut self, timeout: Duration) -> bool {
+            assert_eq!(timeout, JOIN_TIMEOUT);
+            self.calls.push("join_workers");
+            self.workers_finish
+        }
+
+        fn persist(&mut self) {
+            self.calls.push("persist");
+        }
+    }
+
+    #[test]
+    fn steps_run_in_order() {
+        let started = AtomicBool::new(false);
+        let mut steps = RecordedSteps { workers_finish: true, ..Default::default() };
+        assert!(run(&started, &mut steps));
+        assert_eq!(steps.calls, ["notify", "stop_detection", "stop_audio", "join_workers", "persist"]);
+    }
+
+    #[test]
+    fn runs_only_once() {
+        let started = AtomicBool::new(false);
+        let mut steps = RecordedSteps { workers_finish: true, ..Default::default() };
+        run(&started, &mut steps);
+        assert!(!run(&started, &mut steps));
+        assert_eq!(steps.calls.len(), 5);
+    }
+
+    #[test]
+    fn persists_even_if_a_thread_hangs() {
+        let started = AtomicBool::new(false);
+        let mut steps = RecordedSteps::default();
+        run(&started, &mut steps);
+        assert_eq!(steps.calls.last(), Some(&"persist"));
+    }
+}
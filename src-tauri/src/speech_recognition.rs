@@ -1,26 +1,414 @@
-// speech_recognition.rs - Minimal placeholder since we're using Web Speech API
+// speech_recognition.rs - Pluggable recognition backends for the continuous speech path
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
-pub struct SpeechRecognizer {
-    // Empty struct - all speech recognition is handled by Web Speech API in frontend
+use crate::normalize::Entity;
+
+// A final recognition result delivered on the continuous speech path
+#[derive(Clone, Debug, Serialize)]
+pub struct RecognitionResult {
+    pub text: String,
+    // The recognizer's output before post-processing. Cleared before results
+    // reach the frontend unless raw transcripts are kept, and left out then.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub raw_text: String,
+    // Index into the active constraint phrase list, if recognition was constrained
+    pub constraint_index: Option<usize>,
+    // Not every backend reports a confidence score
+    pub confidence: Option<f32>,
+    // Name of the backend that produced the result
+    pub source: String,
+    // Numbers, durations, times and percentages found in `text`
+    pub entities: Vec<Entity>,
+    // Backends in the fallback chain that were tried before `source`
+    pub fallbacks: u32,
+    // Other hypotheses for the utterance, best first, for picking a
+    // correction without speaking again. Empty where the backend only
+    // reports its best guess (sapi_lite doesn't expose SAPI's alternates).
+    pub alternatives: Vec<String>,
+}
+
+// How many alternatives a result carries at most
+pub const MAX_ALTERNATIVES: usize = 5;
+
+// A final result as the correction UI needs it
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TranscriptionResult {
+    pub primary: String,
+    pub alternatives: Vec<String>,
+    pub confidence: Option<f32>,
+}
+
+impl RecognitionResult {
+    pub fn transcription(&self) -> TranscriptionResult {
+        TranscriptionResult {
+            primary: self.text.clone(),
+            alternatives: self.alternatives.clone(),
+            confidence: self.confidence,
+        }
+    }
+}
+
+// Up to MAX_ALTERNATIVES distinct, non-empty hypotheses other than `primary`
+pub fn distinct_alternatives(primary: &str, hypotheses: Vec<String>) -> Vec<String> {
+    let mut alternatives: Vec<String> = Vec::new();
+    for hypothesis in hypotheses {
+        let hypothesis = hypothesis.trim().to_string();
+        let duplicate = hypothesis.eq_ignore_ascii_case(primary.trim())
+            || alternatives.iter().any(|alternative| alternative.eq_ignore_ascii_case(&hypothesis));
+        if !hypothesis.is_empty() && !duplicate {
+            alternatives.push(hypothesis);
+        }
+    }
+    alternatives.truncate(MAX_ALTERNATIVES);
+    alternatives
+}
+
+// Recognition failures reported to the frontend as "speech-error" events
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpeechError {
+    BackendUnavailable(String),
+    ModelMissing(String),
+    AudioUnavailable(String),
+    Timeout(String),
+    Internal(String),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeechErrorPayload {
+    pub kind: &'static str,
+    pub message: String,
+    pub recoverable: bool,
 }
 
-impl SpeechRecognizer {
-    pub fn new() -> Result<Self> {
-        println!("⚠️ Speech recognition start_listening called - should use Web Speech API instead");
-        Ok(SpeechRecognizer {})
+impl SpeechError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SpeechError::BackendUnavailable(_) => "backend_unavailable",
+            SpeechError::ModelMissing(_) => "model_missing",
+            SpeechError::AudioUnavailable(_) => "audio_unavailable",
+            SpeechError::Timeout(_) => "timeout",
+            SpeechError::Internal(_) => "internal",
+        }
     }
-   
-    pub fn start_listening(&self, _callback: impl Fn(String) + Send + Sync + 'static) -> Result<()> {
-        println!("⚠️ Speech recognition start_listening called - should use Web Speech API instead");
+
+    // Whether retrying the same operation can reasonably succeed; anything
+    // else needs the user (or a different backend) to fix it
+    pub fn recoverable(&self) -> bool {
+        matches!(self, SpeechError::AudioUnavailable(_) | SpeechError::Timeout(_))
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            SpeechError::BackendUnavailable(detail) => format!("Speech recognition is unavailable: {}", detail),
+            SpeechError::ModelMissing(detail) => format!("Speech model is missing: {}", detail),
+            SpeechError::AudioUnavailable(detail) => format!("Microphone is unavailable: {}", detail),
+            SpeechError::Timeout(detail) => format!("Speech recognition timed out: {}", detail),
+            SpeechError::Internal(detail) => format!("Speech recognition failed: {}", detail),
+        }
+    }
+
+    pub fn payload(&self) -> SpeechErrorPayload {
+        SpeechErrorPayload {
+            kind: self.kind(),
+            message: self.message(),
+            recoverable: self.recoverable(),
+        }
+    }
+}
+
+impl std::fmt::Display for SpeechError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for SpeechError {}
+
+// When an utterance counts as finished. Backends apply as much of this as
+// their APIs allow.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndpointingConfig {
+    // Silence after speech that finalizes the utterance
+    pub trailing_silence_ms: u64,
+    // Utterances are cut off after this long
+    pub max_utterance_ms: u64,
+    // Shorter bursts of sound are not treated as an utterance
+    pub min_speech_ms: u64,
+}
+
+impl Default for EndpointingConfig {
+    fn default() -> Self {
+        Self {
+            trailing_silence_ms: 800,
+            max_utterance_ms: 15_000,
+            min_speech_ms: 200,
+        }
+    }
+}
+
+impl EndpointingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.trailing_silence_ms == 0 {
+            return Err("Trailing silence must be greater than 0 ms".to_string());
+        }
+        if self.max_utterance_ms <= self.min_speech_ms {
+            return Err("Maximum utterance length must exceed the minimum speech duration".to_string());
+        }
         Ok(())
     }
-   
-    pub fn stop_listening(&self) {
-        // Empty implementation
+}
+
+pub type ResultCallback = Box<dyn Fn(RecognitionResult) + Send + Sync>;
+
+// A speech recognition engine that can be swapped in at runtime
+pub trait RecognitionBackend {
+    fn name(&self) -> &'static str;
+    fn start(&self, callback: ResultCallback) -> Result<()>;
+    fn stop(&self);
+    // Backends that don't capture audio themselves receive it here
+    fn feed_audio(&self, samples: &[i16], sample_rate: u32);
+    #[allow(dead_code)]
+    fn set_language(&self, language: &str) -> Result<()>;
+    fn set_endpointing(&self, config: &EndpointingConfig);
+    // Phrases to favor, applied from the next start()
+    fn set_vocabulary(&self, phrases: &[String]);
+    // Recognize a finished recording (mono) in one go, for re-transcription
+    fn transcribe(&self, samples: &[i16], sample_rate: u32) -> Result<RecognitionResult>;
+}
+
+pub const DEFAULT_BACKEND: &str = "webspeech";
+
+// Every backend create_backend can build. "whisper" is only recognized to
+// report why it can't run.
+pub const BACKENDS: &[&str] = &["webspeech", "sapi"];
+
+// Backends whose transcribe() works on stored audio, the only ones a fallback
+// chain can run. Both live backends only hear the microphone.
+pub const TRANSCRIBING_BACKENDS: &[&str] = &[];
+
+// The backends by type, for commands that take one instead of a name
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeechBackendType {
+    WebSpeech,
+    Sapi,
+    Whisper,
+}
+
+impl SpeechBackendType {
+    pub fn name(self) -> &'static str {
+        match self {
+            SpeechBackendType::WebSpeech => "webspeech",
+            SpeechBackendType::Sapi => "sapi",
+            SpeechBackendType::Whisper => "whisper",
+        }
     }
 }
 
-// Make SpeechRecognizer thread-safe
-unsafe impl Send for SpeechRecognizer {}
-unsafe impl Sync for SpeechRecognizer {}
\ No newline at end of file
+// Settings some backends are built with
+#[derive(Clone, Debug, Default)]
+pub struct BackendOptions {
+    // The active Whisper model file, if one is downloaded
+    pub whisper_model: Option<PathBuf>,
+}
+
+// Build the backend with the given name (as stored in settings)
+pub fn create_backend(name: &str, options: &BackendOptions) -> Result<Box<dyn RecognitionBackend + Send>> {
+    match name {
+        "webspeech" => Ok(Box::new(WebSpeechDelegator::new())),
+        "sapi" => Ok(Box::new(SapiSpeechRecognizer)),
+        "whisper" => Err(whisper_unavailable(options.whisper_model.as_deref()).into()),
+        other => Err(anyhow::anyhow!("Unknown recognition backend: {}", other)),
+    }
+}
+
+// Speech recognition is handled by the Web Speech API in the frontend, so this
+// backend only forwards to it and never produces results itself
+pub struct WebSpeechDelegator {}
+
+impl WebSpeechDelegator {
+    pub fn new() -> Self {
+        WebSpeechDelegator {}
+    }
+}
+
+impl RecognitionBackend for WebSpeechDelegator {
+    fn name(&self) -> &'static str {
+        "webspeech"
+    }
+
+    fn start(&self, _callback: ResultCallback) -> Result<()> {
+        println!("ℹ️ Web Speech backend selected - recognition runs in the frontend");
+        Ok(())
+    }
+
+    fn stop(&self) {
+        // Nothing to stop, the frontend owns the recognizer
+    }
+
+    fn feed_audio(&self, _samples: &[i16], _sample_rate: u32) {
+        // The browser captures its own audio
+    }
+
+    fn set_language(&self, _language: &str) -> Result<()> {
+        // The frontend sets `recognition.lang` itself
+        Ok(())
+    }
+
+    fn set_endpointing(&self, _config: &EndpointingConfig) {
+        // The Web Speech API has no endpointing knobs; the frontend applies the
+        // values to its own silence timers from the "endpointing-config-changed" event
+    }
+
+    fn set_vocabulary(&self, _phrases: &[String]) {
+        // The frontend adds the phrases to its SpeechGrammarList from the
+        // "vocabulary-changed" event
+    }
+
+    fn transcribe(&self, _samples: &[i16], _sample_rate: u32) -> Result<RecognitionResult> {
+        // The browser recognizer only listens to the live microphone
+        Err(SpeechError::BackendUnavailable("The webspeech backend can't transcribe stored audio".to_string()).into())
+    }
+}
+
+// Recognition by the wake word detector's own SAPI context. sapi_lite has no
+// dictation grammar, so during a session only the vocabulary, context and
+// constraint phrases are recognized; the detector loop delivers those, and
+// this backend just stands in for it.
+pub struct SapiSpeechRecognizer;
+
+impl RecognitionBackend for SapiSpeechRecognizer {
+    fn name(&self) -> &'static str {
+        "sapi"
+    }
+
+    fn start(&self, _callback: ResultCallback) -> Result<()> {
+        println!("ℹ️ SAPI backend selected - only vocabulary, context and constraint phrases are recognized");
+        Ok(())
+    }
+
+    fn stop(&self) {
+        // The detector loop unloads the session grammars itself
+    }
+
+    fn feed_audio(&self, _samples: &[i16], _sample_rate: u32) {
+        // SAPI reads the microphone itself
+    }
+
+    fn set_language(&self, _language: &str) -> Result<()> {
+        Err(SpeechError::BackendUnavailable("SAPI uses the recognizer language set in Windows".to_string()).into())
+    }
+
+    fn set_endpointing(&self, _config: &EndpointingConfig) {
+        // SAPI endpoints phrases on its own
+    }
+
+    fn set_vocabulary(&self, _phrases: &[String]) {
+        // The detector loads the vocabulary grammar for each session
+    }
+
+    fn transcribe(&self, _samples: &[i16], _sample_rate: u32) -> Result<RecognitionResult> {
+        Err(SpeechError::BackendUnavailable("The sapi backend can't transcribe stored audio".to_string()).into())
+    }
+}
+
+// No Whisper runtime ships with the app yet, so the whisper backend can't be
+// created. Asking for it says what's missing instead of registering a
+// recognizer that produces nothing.
+fn whisper_unavailable(model: Option<&Path>) -> SpeechError {
+    match model {
+        Some(model) if model.is_file() => {
+            SpeechError::BackendUnavailable(format!("no Whisper runtime is available to load {}", model.display()))
+        }
+        Some(model) => SpeechError::ModelMissing(format!("Whisper model {} not found", model.display())),
+        None => SpeechError::ModelMissing("no Whisper model is installed".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternatives_leave_out_the_primary_and_repeats() {
+        let hypotheses = ["Open the door", "open the door", "open the doors", "", "Open the doors", "a", "b", "c", "d", "e"];
+        let alternatives = distinct_alternatives("open the door", hypotheses.iter().map(|text| text.to_string()).collect());
+        assert_eq!(alternatives, vec!["open the doors", "a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn cleared_raw_text_is_left_out() {
+        let mut result = RecognitionResult {
+            text: "well ****".to_string(),
+            raw_text: "well damn".to_string(),
+            constraint_index: None,
+            confidence: None,
+            source: "webspeech".to_string(),
+            entities: Vec::new(),
+            fallbacks: 0,
+            alternatives: Vec::new(),
+        };
+        assert_eq!(serde_json::to_value(&result).unwrap()["raw_text"], "well damn");
+        result.raw_text.clear();
+        assert!(serde_json::to_value(&result).unwrap().get("raw_text").is_none());
+    }
+
+    #[test]
+    fn speech_error_payload_shape() {
+        let payload = serde_json::to_value(SpeechError::AudioUnavailable("unplugged".to_string()).payload()).unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "kind": "audio_unavailable",
+                "message": "Microphone is unavailable: unplugged",
+                "recoverable": true,
+            })
+        );
+
+        let missing = SpeechError::ModelMissing("no Whisper model is installed".to_string()).payload();
+        assert_eq!(missing.kind, "model_missing");
+        assert!(!missing.recoverable);
+    }
+
+    // The kind of SpeechError a call failed with
+    fn error_kind<T>(result: Result<T>) -> Option<&'static str> {
+        result.err()?.downcast_ref::<SpeechError>().map(SpeechError::kind)
+    }
+
+    #[test]
+    fn whisper_says_what_is_missing() {
+        let options = |model: Option<PathBuf>| BackendOptions { whisper_model: model };
+        assert_eq!(error_kind(create_backend("whisper", &options(None))), Some("model_missing"));
+
+        let model = std::env::temp_dir().join(format!("jackson-test-whisper-{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&model);
+        assert_eq!(error_kind(create_backend("whisper", &options(Some(model.clone())))), Some("model_missing"));
+
+        // Downloaded, but there's nothing to run it with
+        std::fs::write(&model, b"ggml").unwrap();
+        let unavailable = error_kind(create_backend("whisper", &options(Some(model.clone()))));
+        std::fs::remove_file(&model).unwrap();
+        assert_eq!(unavailable, Some("backend_unavailable"));
+    }
+
+    #[test]
+    fn live_backends_cant_transcribe_stored_audio() {
+        let options = BackendOptions::default();
+        for name in BACKENDS {
+            let backend = create_backend(name, &options).unwrap();
+            assert_eq!(error_kind(backend.transcribe(&[0; 160], 16_000)), Some("backend_unavailable"), "{}", name);
+        }
+        assert!(TRANSCRIBING_BACKENDS.iter().all(|name| BACKENDS.contains(name)));
+    }
+
+    #[test]
+    fn unknown_backends_are_rejected() {
+        let options = BackendOptions::default();
+        let error = create_backend("vosk", &options).err().unwrap();
+        assert!(error.to_string().contains("vosk"));
+        assert!(error.downcast_ref::<SpeechError>().is_none());
+    }
+}
@@ -0,0 +1,50 @@
+// theme.rs - Detect the Windows light/dark app theme and report changes
+use crate::events::ThemePayload;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use winreg::enums::HKEY_CURRENT_USER;
+use winreg::RegKey;
+
+const PERSONALIZE_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SystemTheme {
+    Dark,
+    Light,
+    Unknown,
+}
+
+pub fn detect_system_theme() -> SystemTheme {
+    let key = match RegKey::predef(HKEY_CURRENT_USER).open_subkey(PERSONALIZE_KEY) {
+        Ok(key) => key,
+        Err(_) => return SystemTheme::Unknown,
+    };
+
+    match key.get_value::<u32, _>("AppsUseLightTheme") {
+        Ok(0) => SystemTheme::Dark,
+        Ok(_) => SystemTheme::Light,
+        Err(_) => SystemTheme::Unknown,
+    }
+}
+
+// Emit the current theme, then poll the registry every 5 seconds and emit
+// "system-theme-changed" whenever the user toggles it
+pub fn start_theme_watcher<R: Runtime>(app: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        let mut current = detect_system_theme();
+        println!("🎨 System theme: {:?}", current);
+        let _ = app.emit("system-theme", ThemePayload { theme: current });
+
+        loop {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            let theme = detect_system_theme();
+            if theme != current {
+                println!("🎨 System theme changed: {:?} -> {:?}", current, theme);
+                current = theme;
+                let _ = app.emit("system-theme-changed", ThemePayload { theme });
+            }
+        }
+    });
+}
@@ -0,0 +1,251 @@
+// training.rs - Recording prompted phrases for speech training. Audio is kept
+// from the first speech until the speaker has been silent for a while
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::audio;
+use crate::speech_recognition::EndpointingConfig;
+
+// Frames louder than this count as speech
+pub const TRAINING_SPEECH_RMS: f32 = 0.01;
+// Give up if nothing is said within this long
+pub const NO_SPEECH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// What became of phrases handed to submit_training_phrases
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TrainingSubmission {
+    pub phrases: usize,
+    // How many SAPI heard as their prompt and trained on
+    pub trained: usize,
+    pub total_duration_ms: u64,
+    // Whether the speech profile was adapted, which takes at least one
+    // trained phrase
+    pub adapted: bool,
+    pub message: String,
+}
+
+pub fn submission(phrases: usize, trained: usize, total_duration_ms: u64) -> TrainingSubmission {
+    let message = if trained == 0 {
+        "SAPI didn't recognize any of the phrases as their prompts, so the speech profile wasn't changed. Try recording them again more clearly.".to_string()
+    } else if trained < phrases {
+        format!("Adapted the speech profile with {} of {} phrases; the rest weren't recognized as their prompts", trained, phrases)
+    } else {
+        format!("Adapted the speech profile with all {} phrases", phrases)
+    };
+    TrainingSubmission {
+        phrases,
+        trained,
+        total_duration_ms,
+        adapted: trained > 0,
+        message,
+    }
+}
+
+// Submitted paths must be recordings made this run, each given once; returns
+// their indices in `recorded`
+pub fn select_submitted(paths: &[String], recorded: &[String]) -> Result<Vec<usize>, String> {
+    if paths.is_empty() {
+        return Err("No training phrases were submitted".to_string());
+    }
+    let mut seen = HashSet::new();
+    paths
+        .iter()
+        .map(|path| {
+            if !seen.insert(path) {
+                return Err(format!("Training phrase {} was submitted twice", path));
+            }
+            recorded
+                .iter()
+                .position(|recording| recording == path)
+                .ok_or_else(|| format!("{} isn't a training phrase recorded by record_training_phrase", path))
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecorderState {
+    // Still waiting for the first speech
+    Waiting,
+    Recording,
+    // Trailing silence or the length limit ended the phrase
+    Finished,
+    // Nothing was said before the timeout
+    NoSpeech,
+}
+
+pub struct PhraseRecorder {
+    threshold_rms: f32,
+    trailing_silence: Duration,
+    max_duration: Duration,
+    min_speech: Duration,
+    samples: Vec<i16>,
+    sample_rate: u32,
+    waited: Duration,
+    recorded: Duration,
+    speech: Duration,
+    silence: Duration,
+    state: RecorderState,
+}
+
+impl PhraseRecorder {
+    pub fn new(threshold_rms: f32, endpointing: &EndpointingConfig) -> Self {
+        Self {
+            threshold_rms,
+            trailing_silence: Duration::from_millis(endpointing.trailing_silence_ms),
+            max_duration: Duration::from_millis(endpointing.max_utterance_ms),
+            min_speech: Duration::from_millis(endpointing.min_speech_ms),
+            samples: Vec::new(),
+            sample_rate: 0,
+            waited: Duration::ZERO,
+            recorded: Duration::ZERO,
+            speech: Duration::ZERO,
+            silence: Duration::ZERO,
+            state: RecorderState::Waiting,
+        }
+    }
+
+    pub fn push(&mut self, frame: &[i16], sample_rate: u32) -> RecorderState {
+        if frame.is_empty() || sample_rate == 0 {
+            return self.state;
+        }
+        let length = Duration::from_secs_f64(frame.len() as f64 / sample_rate as f64);
+        let loud = audio::rms(frame) >= self.threshold_rms;
+        match self.state {
+            RecorderState::Waiting => {
+                self.waited += length;
+                if loud {
+                    self.state = RecorderState::Recording;
+                    self.record(frame, sample_rate, length, loud);
+                } else if self.waited >= NO_SPEECH_TIMEOUT {
+                    self.state = RecorderState::NoSpeech;
+                }
+            }
+            RecorderState::Recording => {
+                self.record(frame, sample_rate, length, loud);
+                if self.recorded >= self.max_duration {
+                    self.state = RecorderState::Finished;
+                } else if self.silence >= self.trailing_silence {
+                    // A short burst of noise is not a phrase, keep waiting
+                    self.state = if self.speech >= self.min_speech {
+                        RecorderState::Finished
+                    } else {
+                        self.reset();
+                        RecorderState::Waiting
+                    };
+                }
+            }
+            RecorderState::Finished | RecorderState::NoSpeech => {}
+        }
+        self.state
+    }
+
+    fn record(&mut self, frame: &[i16], sample_rate: u32, length: Duration, loud: bool) {
+        self.samples.extend_from_slice(frame);
+        self.sample_rate = sample_rate;
+        self.recorded += length;
+        if loud {
+            self.speech += length;
+            self.silence = Duration::ZERO;
+        } else {
+            self.silence += length;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.samples.clear();
+        self.recorded = Duration::ZERO;
+        self.speech = Duration::ZERO;
+        self.silence = Duration::ZERO;
+    }
+
+    // The recorded audio and its sample rate
+    pub fn into_recording(self) -> (Vec<i16>, u32) {
+        (self.samples, self.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    // 100 ms frames
+    fn loud() -> Vec<i16> {
+        vec![8_000; 1_600]
+    }
+
+    fn quiet() -> Vec<i16> {
+        vec![0; 1_600]
+    }
+
+    fn endpointing() -> EndpointingConfig {
+        EndpointingConfig { trailing_silence_ms: 300, max_utterance_ms: 2_000, min_speech_ms: 200 }
+    }
+
+    #[test]
+    fn finishes_after_trailing_silence() {
+        let mut recorder = PhraseRecorder::new(TRAINING_SPEECH_RMS, &endpointing());
+        assert_eq!(recorder.push(&quiet(), RATE), RecorderState::Waiting);
+        for _ in 0..3 {
+            assert_eq!(recorder.push(&loud(), RATE), RecorderState::Recording);
+        }
+        assert_eq!(recorder.push(&quiet(), RATE), RecorderState::Recording);
+        assert_eq!(recorder.push(&quiet(), RATE), RecorderState::Recording);
+        assert_eq!(recorder.push(&quiet(), RATE), RecorderState::Finished);
+
+        // Leading silence is dropped, trailing silence is kept
+        let (samples, rate) = recorder.into_recording();
+        assert_eq!(rate, RATE);
+        assert_eq!(samples.len(), 6 * 1_600);
+    }
+
+    #[test]
+    fn ignores_short_bursts() {
+        let mut recorder = PhraseRecorder::new(TRAINING_SPEECH_RMS, &endpointing());
+        recorder.push(&loud(), RATE);
+        for _ in 0..3 {
+            recorder.push(&quiet(), RATE);
+        }
+        assert_eq!(recorder.push(&quiet(), RATE), RecorderState::Waiting);
+        assert!(recorder.into_recording().0.is_empty());
+    }
+
+    #[test]
+    fn stops_at_the_length_limit() {
+        let mut recorder = PhraseRecorder::new(TRAINING_SPEECH_RMS, &endpointing());
+        let states: Vec<_> = (0..20).map(|_| recorder.push(&loud(), RATE)).collect();
+        assert_eq!(states[18], RecorderState::Recording);
+        assert_eq!(states[19], RecorderState::Finished);
+    }
+
+    #[test]
+    fn submissions_must_be_recorded_phrases() {
+        let recorded = vec!["a.wav".to_string(), "b.wav".to_string()];
+        let submit = |paths: &[&str]| select_submitted(&paths.iter().map(|p| p.to_string()).collect::<Vec<_>>(), &recorded);
+        assert_eq!(submit(&["b.wav", "a.wav"]), Ok(vec![1, 0]));
+        assert!(submit(&[]).is_err());
+        assert!(submit(&["a.wav", "a.wav"]).is_err());
+        assert!(submit(&["c.wav"]).is_err());
+    }
+
+    #[test]
+    fn adapted_only_with_a_trained_phrase() {
+        assert!(!submission(3, 0, 6_000).adapted);
+        let partial = submission(3, 2, 6_000);
+        assert!(partial.adapted);
+        assert!(partial.message.contains("2 of 3"));
+        assert!(submission(3, 3, 6_000).adapted);
+    }
+
+    #[test]
+    fn gives_up_without_speech() {
+        let mut recorder = PhraseRecorder::new(TRAINING_SPEECH_RMS, &endpointing());
+        let frames = (NO_SPEECH_TIMEOUT.as_millis() / 100) as usize;
+        for _ in 1..frames {
+            assert_eq!(recorder.push(&quiet(), RATE), RecorderState::Waiting);
+        }
+        assert_eq!(recorder.push(&quiet(), RATE), RecorderState::NoSpeech);
+    }
+}
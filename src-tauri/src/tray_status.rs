@@ -0,0 +1,111 @@
+// tray_status.rs - The tray icon and tooltip for what Jackson is doing, all
+// mapped here so the two can't drift apart
+use crate::wake_word::{DetectorState, WAKE_PHRASE};
+
+pub const APP_NAME: &str = "Jackson Assistant";
+// Windows cuts tray tooltips off after this many UTF-16 units
+const MAX_TOOLTIP_UNITS: usize = 127;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrayStatus {
+    Idle,
+    Listening,
+    Session,
+    Muted,
+    Error,
+}
+
+impl TrayStatus {
+    // No detector means SAPI failed to initialize
+    pub fn from_state(detector: Option<DetectorState>, session_active: bool, microphone_paused: bool) -> Self {
+        match detector {
+            None | Some(DetectorState::Error) => TrayStatus::Error,
+            Some(DetectorState::Idle) => TrayStatus::Idle,
+            Some(_) if microphone_paused => TrayStatus::Muted,
+            Some(_) if session_active => TrayStatus::Session,
+            Some(DetectorState::Starting | DetectorState::Listening | DetectorState::Retrying) => TrayStatus::Listening,
+        }
+    }
+
+    pub fn icon(self) -> &'static [u8] {
+        match self {
+            TrayStatus::Idle => include_bytes!("../icons/tray/idle.png"),
+            TrayStatus::Listening => include_bytes!("../icons/tray/listening.png"),
+            TrayStatus::Session => include_bytes!("../icons/tray/session.png"),
+            TrayStatus::Muted => include_bytes!("../icons/tray/muted.png"),
+            TrayStatus::Error => include_bytes!("../icons/tray/error.png"),
+        }
+    }
+
+    fn detail(self) -> String {
+        match self {
+            TrayStatus::Idle => "Not listening".to_string(),
+            TrayStatus::Listening => format!("Listening for '{}'", WAKE_PHRASE),
+            TrayStatus::Session => "Listening…".to_string(),
+            TrayStatus::Muted => "Listening paused (microphone muted)".to_string(),
+            TrayStatus::Error => "Speech recognition unavailable".to_string(),
+        }
+    }
+}
+
+// The title (app name and hotkey), what's going on, then the microphone, so a
+// long device name is what gets cut off at the length limit
+pub fn tooltip(status: TrayStatus, title: &str, microphone: Option<&str>) -> String {
+    let mut text = format!("{}\n{}", title, status.detail());
+    if let Some(microphone) = microphone.filter(|_| status != TrayStatus::Error) {
+        text.push_str(&format!("\nMicrophone: {}", microphone));
+    }
+    truncate_tooltip(text)
+}
+
+fn truncate_tooltip(text: String) -> String {
+    if text.encode_utf16().count() <= MAX_TOOLTIP_UNITS {
+        return text;
+    }
+    let mut truncated = String::new();
+    let mut units = 0;
+    for c in text.chars() {
+        // Leave room for the ellipsis
+        if units + c.len_utf16() > MAX_TOOLTIP_UNITS - 1 {
+            break;
+        }
+        units += c.len_utf16();
+        truncated.push(c);
+    }
+    format!("{}…", truncated.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_win_over_everything() {
+        assert_eq!(TrayStatus::from_state(None, true, true), TrayStatus::Error);
+        assert_eq!(TrayStatus::from_state(Some(DetectorState::Error), true, false), TrayStatus::Error);
+    }
+
+    #[test]
+    fn muted_wins_over_a_session() {
+        assert_eq!(TrayStatus::from_state(Some(DetectorState::Listening), true, true), TrayStatus::Muted);
+        assert_eq!(TrayStatus::from_state(Some(DetectorState::Listening), true, false), TrayStatus::Session);
+        assert_eq!(TrayStatus::from_state(Some(DetectorState::Retrying), false, false), TrayStatus::Listening);
+        assert_eq!(TrayStatus::from_state(Some(DetectorState::Idle), false, true), TrayStatus::Idle);
+    }
+
+    #[test]
+    fn tooltip_lists_the_details() {
+        let text = tooltip(TrayStatus::Listening, "Jackson Assistant (Ctrl+Shift+J)", Some("USB Mic"));
+        assert_eq!(text, "Jackson Assistant (Ctrl+Shift+J)\nListening for 'Hey Jackson'\nMicrophone: USB Mic");
+        assert_eq!(tooltip(TrayStatus::Error, APP_NAME, Some("USB Mic")), "Jackson Assistant\nSpeech recognition unavailable");
+    }
+
+    #[test]
+    fn long_tooltips_are_cut_at_the_limit() {
+        let microphone = "Microphone Array (Realtek® High Definition Audio) connected through a USB docking station 🎤🎤🎤🎤🎤🎤";
+        let text = tooltip(TrayStatus::Session, APP_NAME, Some(microphone));
+        assert_eq!(text.encode_utf16().count(), MAX_TOOLTIP_UNITS);
+        assert!(text.ends_with('…'));
+        assert!(text.starts_with("Jackson Assistant\nListening…\nMicrophone: Microphone Array"));
+    }
+}
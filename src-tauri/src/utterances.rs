@@ -0,0 +1,182 @@
+// utterances.rs - Audio of recent utterances, kept in memory for playback and
+// optionally saved to disk as WAV clips
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// Stored audio is mono at this rate, what offline models expect
+pub const STORED_SAMPLE_RATE: u32 = 16_000;
+
+// Roughly the last two minutes of speech
+pub const DEFAULT_MAX_STORED_AUDIO: Duration = Duration::from_secs(120);
+
+// Utterance audio keyed by transcript id, oldest evicted first once the total
+// goes over the limit
+pub struct UtteranceAudioStore {
+    clips: VecDeque<(u64, Vec<i16>)>,
+    max_samples: usize,
+    total_samples: usize,
+}
+
+impl UtteranceAudioStore {
+    pub fn new(max_audio: Duration) -> Self {
+        Self {
+            clips: VecDeque::new(),
+            max_samples: (max_audio.as_secs_f64() * STORED_SAMPLE_RATE as f64) as usize,
+            total_samples: 0,
+        }
+    }
+
+    // Samples must be at STORED_SAMPLE_RATE. A clip longer than the whole
+    // limit keeps only its end.
+    pub fn insert(&mut self, transcript_id: u64, mut samples: Vec<i16>) {
+        if samples.len() > self.max_samples {
+            samples.drain(..samples.len() - self.max_samples);
+        }
+        self.total_samples += samples.len();
+        self.clips.push_back((transcript_id, samples));
+
+        while self.total_samples > self.max_samples {
+            match self.clips.pop_front() {
+                Some((_, evicted)) => self.total_samples -= evicted.len(),
+                None => break,
+            }
+        }
+    }
+
+    pub fn get(&self, transcript_id: u64) -> Option<&[i16]> {
+        self.clips
+            .iter()
+            .find(|(id, _)| *id == transcript_id)
+            .map(|(_, samples)| samples.as_slice())
+    }
+
+    pub fn clear(&mut self) {
+        self.clips.clear();
+        self.total_samples = 0;
+    }
+}
+
+// Write 16-bit mono PCM as a WAV file, creating parent directories
+pub fn write_wav(path: &Path, samples: &[i16], sample_rate: u32) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let data_len = (samples.len() * 2) as u32;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    bytes.extend_from_slice(&2u16.to_le_bytes()); // block align
+    bytes.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    let mut file = fs::File::create(path)?;
+    file.write_all(&bytes)?;
+    file.sync_all()
+}
+
+// Delete the oldest clips under `dir` until they take at most `max_bytes`,
+// then remove session folders left empty. Returns how many clips were deleted.
+pub fn enforce_retention(dir: &Path, max_bytes: u64) -> io::Result<usize> {
+    let mut clips: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+    for session_dir in fs::read_dir(dir)? {
+        let session_dir = session_dir?.path();
+        if !session_dir.is_dir() {
+            continue;
+        }
+        for clip in fs::read_dir(&session_dir)? {
+            let clip = clip?;
+            let metadata = clip.metadata()?;
+            if metadata.is_file() {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                clips.push((modified, metadata.len(), clip.path()));
+            }
+        }
+    }
+
+    let mut total: u64 = clips.iter().map(|(_, size, _)| size).sum();
+    clips.sort_by_key(|(modified, _, _)| *modified);
+    let mut deleted = 0;
+    for (_, size, path) in clips {
+        if total <= max_bytes {
+            break;
+        }
+        fs::remove_file(&path)?;
+        total -= size;
+        deleted += 1;
+        if let Some(session_dir) = path.parent() {
+            // Only succeeds once the folder is empty
+            let _ = fs::remove_dir(session_dir);
+        }
+    }
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A store holding `seconds` of audio, and a clip of that length
+    fn store(seconds: u64) -> UtteranceAudioStore {
+        UtteranceAudioStore::new(Duration::from_secs(seconds))
+    }
+
+    fn clip(seconds: f64, value: i16) -> Vec<i16> {
+        vec![value; (seconds * STORED_SAMPLE_RATE as f64) as usize]
+    }
+
+    #[test]
+    fn oldest_clips_are_evicted_once_over_the_limit() {
+        let mut store = store(3);
+        store.insert(1, clip(1.0, 1));
+        store.insert(2, clip(1.0, 2));
+        store.insert(3, clip(1.0, 3));
+        assert!(store.get(1).is_some());
+
+        store.insert(4, clip(1.5, 4));
+        assert_eq!(store.get(1), None);
+        assert_eq!(store.get(2), None);
+        assert_eq!(store.get(3).map(<[i16]>::len), Some(STORED_SAMPLE_RATE as usize));
+        assert_eq!(store.get(4).map(<[i16]>::len), Some(STORED_SAMPLE_RATE as usize * 3 / 2));
+        assert_eq!(store.total_samples, STORED_SAMPLE_RATE as usize * 5 / 2);
+    }
+
+    #[test]
+    fn an_oversized_clip_keeps_only_its_end() {
+        let mut store = store(2);
+        store.insert(1, clip(1.0, 1));
+        let mut long = clip(1.0, 7);
+        long.extend(clip(2.0, 8));
+        store.insert(2, long);
+
+        // The new clip fills the store on its own, so the old one goes
+        assert_eq!(store.get(1), None);
+        let kept = store.get(2).unwrap();
+        assert_eq!(kept.len(), STORED_SAMPLE_RATE as usize * 2);
+        assert!(kept.iter().all(|&sample| sample == 8));
+        assert_eq!(store.total_samples, kept.len());
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let mut store = store(2);
+        store.insert(1, clip(0.5, 1));
+        store.clear();
+        assert_eq!(store.get(1), None);
+        assert_eq!(store.total_samples, 0);
+        store.insert(2, clip(2.0, 2));
+        assert!(store.get(2).is_some());
+    }
+}
@@ -0,0 +1,195 @@
+// vocabulary.rs - Correct near-misses of custom vocabulary phrases in final transcripts
+use std::cmp::Reverse;
+
+// Phrases shorter than this (letters and digits only) must match exactly,
+// since a single edit changes too much of them
+const MIN_FUZZY_LEN: usize = 5;
+
+// One edit is allowed per this many characters of the phrase
+const CHARS_PER_EDIT: usize = 5;
+
+struct Target<'a> {
+    phrase: &'a str,
+    word_count: usize,
+    squashed: String,
+}
+
+// Replace words that are close to a vocabulary phrase with the phrase as
+// written, e.g. "asta tide" or "astatyde" becomes "Astatide". Punctuation
+// around the replaced words is kept.
+pub fn correct(text: &str, phrases: &[String]) -> String {
+    let mut targets: Vec<Target> = phrases
+        .iter()
+        .map(|phrase| Target {
+            phrase: phrase.trim(),
+            word_count: phrase.split_whitespace().count(),
+            squashed: squash(phrase),
+        })
+        .filter(|target| !target.squashed.is_empty())
+        .collect();
+    // Longer phrases first so "Astatide Labs" wins over "Astatide"
+    targets.sort_by_key(|target| Reverse(target.word_count));
+
+    let words = word_spans(text);
+    let mut output = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < words.len() {
+        match find_match(text, &words[i..], &targets) {
+            Some((phrase, len)) => {
+                let (start, _) = core_span(text, words[i]);
+                let (_, end) = core_span(text, words[i + len - 1]);
+                output.push_str(&text[copied..start]);
+                output.push_str(phrase);
+                copied = end;
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+
+    output.push_str(&text[copied..]);
+    output
+}
+
+// The first phrase the words starting here are a near-miss of, and how many
+// words it replaces
+fn find_match<'a>(text: &str, words: &[(usize, usize)], targets: &[Target<'a>]) -> Option<(&'a str, usize)> {
+    targets.iter().find_map(|target| {
+        // Recognizers often split an unfamiliar word in two, or merge two.
+        // Those must spell the phrase exactly, or "tour I" would pass for
+        // "Tauri" and "Jack's son" for "Jackson".
+        let sizes = [target.word_count, target.word_count + 1, target.word_count - 1];
+        sizes
+            .into_iter()
+            .filter(|&len| len > 0 && len <= words.len())
+            .find(|&len| {
+                let window = &words[..len];
+                let first = squash(&text[window[0].0..window[0].1]);
+                let last = squash(&text[window[len - 1].0..window[len - 1].1]);
+                if first.is_empty() || last.is_empty() {
+                    return false;
+                }
+                let candidate: String = window.iter().map(|&(start, end)| squash(&text[start..end])).collect();
+                if len == target.word_count {
+                    is_near_miss(&candidate, &target.squashed)
+                } else {
+                    candidate == target.squashed
+                }
+            })
+            .map(|len| (target.phrase, len))
+    })
+}
+
+fn is_near_miss(candidate: &str, phrase: &str) -> bool {
+    let len = phrase.chars().count();
+    if len < MIN_FUZZY_LEN {
+        return candidate == phrase;
+    }
+    // Inflections like "Astatides" are left alone
+    if candidate != phrase && candidate.starts_with(phrase) {
+        return false;
+    }
+    edit_distance(candidate, phrase) <= len / CHARS_PER_EDIT
+}
+
+// Lowercased letters and digits only, so spacing, case and punctuation don't
+// count as differences
+fn squash(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Byte ranges of the whitespace-separated words
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(word_start)) => {
+                spans.push((word_start, index));
+                start = None;
+            }
+            (false, None) => start = Some(index),
+            _ => {}
+        }
+    }
+    if let Some(word_start) = start {
+        spans.push((word_start, text.len()));
+    }
+    spans
+}
+
+// A word's range without its leading and trailing punctuation
+fn core_span(text: &str, (start, end): (usize, usize)) -> (usize, usize) {
+    let word = &text[start..end];
+    let is_punctuation = |c: char| !c.is_alphanumeric();
+    let leading = word.len() - word.trim_start_matches(is_punctuation).len();
+    let trailing = word.len() - word.trim_end_matches(is_punctuation).len();
+    (start + leading, end - trailing)
+}
+
+// Levenshtein distance over chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrects_near_misses_and_leaves_the_rest() {
+        let vocabulary: Vec<String> = ["Tauri", "Jackson", "Astatide", "Astatide Labs", "Rust"]
+            .iter()
+            .map(|phrase| phrase.to_string())
+            .collect();
+        let cases = [
+            // Near misses of a single word
+            ("talk to jakson", "talk to Jackson"),
+            ("built with taury", "built with Tauri"),
+            ("astatyde rocks", "Astatide rocks"),
+            // Split words and merged phrases spelled exactly
+            ("asta tide rocks", "Astatide rocks"),
+            ("Jack son said hi", "Jackson said hi"),
+            ("asta tide labs", "Astatide Labs"),
+            ("astatidelabs shipped", "Astatide Labs shipped"),
+            // Punctuation around the words is kept
+            ("\"astatide labs.\"", "\"Astatide Labs.\""),
+            // Split words that only come close are left alone
+            ("On tour I saw it", "On tour I saw it"),
+            ("Jack's son is here", "Jack's son is here"),
+            ("jack sun", "jack sun"),
+            // Inflections, short phrases and unrelated words too
+            ("Astatides are here", "Astatides are here"),
+            ("take a rest", "take a rest"),
+            ("rust", "Rust"),
+            ("the tower is tall", "the tower is tall"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(correct(input, &vocabulary), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn empty_vocabulary_changes_nothing() {
+        assert_eq!(correct("astatyde rocks", &[]), "astatyde rocks");
+        assert_eq!(correct("", &["Tauri".to_string()]), "");
+    }
+}
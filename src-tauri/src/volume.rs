@@ -0,0 +1,133 @@
+// volume.rs - Read and mute the default output device through Core Audio, and
+// report changes made outside the app
+use anyhow::Result;
+use serde::Serialize;
+use std::ptr;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use winapi::shared::minwindef::{BOOL, FALSE, TRUE};
+use winapi::shared::winerror::{FAILED, HRESULT, RPC_E_CHANGED_MODE};
+use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_ALL};
+use winapi::um::endpointvolume::IAudioEndpointVolume;
+use winapi::um::mmdeviceapi::{eConsole, eRender, IMMDevice, IMMDeviceEnumerator, MMDeviceEnumerator};
+use winapi::um::objbase::COINIT_MULTITHREADED;
+use winapi::um::unknwnbase::IUnknown;
+use winapi::{Class, Interface};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct SystemVolumeInfo {
+    // 0.0 to 1.0
+    pub master_volume: f32,
+    pub is_muted: bool,
+}
+
+// Releases the COM interface when dropped
+struct Com<T: Interface>(*mut T);
+
+impl<T: Interface> Drop for Com<T> {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { (*(self.0 as *mut IUnknown)).Release() };
+        }
+    }
+}
+
+fn check(hr: HRESULT, what: &str) -> Result<()> {
+    if FAILED(hr) {
+        return Err(anyhow::anyhow!("{} failed (HRESULT {:#010x})", what, hr));
+    }
+    Ok(())
+}
+
+// Run `f` with the default output device's volume control. COM is set up for
+// the call, since polling may land on any runtime thread.
+fn with_endpoint_volume<T>(f: impl FnOnce(&IAudioEndpointVolume) -> Result<T>) -> Result<T> {
+    unsafe {
+        let hr = CoInitializeEx(ptr::null_mut(), COINIT_MULTITHREADED);
+        // A thread already set up as single-threaded works too, it just isn't ours to uninitialize
+        let initialized = !FAILED(hr);
+        if !initialized && hr != RPC_E_CHANGED_MODE {
+            check(hr, "CoInitializeEx")?;
+        }
+
+        let result = (|| {
+            let mut enumerator: *mut IMMDeviceEnumerator = ptr::null_mut();
+            check(
+                CoCreateInstance(
+                    &MMDeviceEnumerator::uuidof(),
+                    ptr::null_mut(),
+                    CLSCTX_ALL,
+                    &IMMDeviceEnumerator::uuidof(),
+                    &mut enumerator as *mut _ as *mut _,
+                ),
+                "Creating the device enumerator",
+            )?;
+            let enumerator = Com(enumerator);
+
+            let mut device: *mut IMMDevice = ptr::null_mut();
+            check(
+                (*enumerator.0).GetDefaultAudioEndpoint(eRender, eConsole, &mut device),
+                "Getting the default output device",
+            )?;
+            let device = Com(device);
+
+            let mut volume: *mut IAudioEndpointVolume = ptr::null_mut();
+            check(
+                (*device.0).Activate(
+                    &IAudioEndpointVolume::uuidof(),
+                    CLSCTX_ALL,
+                    ptr::null_mut(),
+                    &mut volume as *mut _ as *mut _,
+                ),
+                "Activating the volume control",
+            )?;
+            let volume = Com(volume);
+            f(&*volume.0)
+        })();
+
+        if initialized {
+            CoUninitialize();
+        }
+        result
+    }
+}
+
+pub fn system_volume() -> Result<SystemVolumeInfo> {
+    with_endpoint_volume(|endpoint| unsafe {
+        let mut level = 0.0f32;
+        check(endpoint.GetMasterVolumeLevelScalar(&mut level), "Reading the volume")?;
+        let mut muted: BOOL = FALSE;
+        check(endpoint.GetMute(&mut muted), "Reading the mute state")?;
+        Ok(SystemVolumeInfo {
+            master_volume: level,
+            is_muted: muted != FALSE,
+        })
+    })
+}
+
+pub fn set_mute(muted: bool) -> Result<()> {
+    with_endpoint_volume(|endpoint| unsafe {
+        check(endpoint.SetMute(if muted { TRUE } else { FALSE }, ptr::null()), "Setting the mute state")
+    })
+}
+
+// Poll the output volume every second and emit "system-volume-changed"
+// whenever it or the mute state changes, including changes made outside the app
+pub fn start_volume_watcher<R: Runtime>(app: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        let mut current = system_volume().ok();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let Ok(info) = system_volume() else {
+                continue;
+            };
+            if current != Some(info) {
+                println!("🔈 System volume {:.0}%{}", info.master_volume * 100.0, if info.is_muted { " (muted)" } else { "" });
+                current = Some(info);
+                let _ = app.emit("system-volume-changed", info);
+            }
+        }
+    });
+}
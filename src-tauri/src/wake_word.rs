@@ -1,18 +1,138 @@
 // wake_word.rs - Updated to only detect "Hey Jackson" precisely
-use crate::audio::AudioCapture;
+use crate::audio::{self, AudioCapture, ChannelRouting, PreTriggerBuffer};
+use crate::agc::AutoGainControl;
+use crate::gate::{EnergyGate, EnergyGateFilter};
+use crate::sapi_stats::{SapiStats, SapiStatsTracker};
+use crate::pipeline::{AudioProcessor, SharedBackend, SpeechPipeline, VadStage, DEFAULT_VAD_THRESHOLD_RMS};
+use crate::echo::EchoMonitor;
+use crate::events::{CaptureDeviceErrorPayload, EchoDetectedPayload, WakeWordRejectedPayload};
+use crate::normalize;
+use crate::session::SessionHandle;
+use crate::utterances::{DEFAULT_MAX_STORED_AUDIO, STORED_SAMPLE_RATE};
+use crate::speech_recognition::{
+    EndpointingConfig, RecognitionBackend, RecognitionResult, SpeechError, WebSpeechDelegator,
+};
 use anyhow::Result;
-use sapi_lite::stt::{Recognizer, Rule, SyncContext};
+use crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use sapi_lite::stt::{Grammar, Phrase, Recognizer, Rule, RuleArena, SemanticValue, SyncContext};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
-use tauri::AppHandle;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Runtime};
+
+// Consecutive recognition errors tolerated before giving up on the loop
+const MAX_CONSECUTIVE_ERRORS: u32 = 10;
+
+// After the nth error in a row recognition waits this long times 2^(n - 1),
+// up to the maximum, so a dropped-out device isn't polled in a tight loop
+const RECOGNIZE_ERROR_DELAY: Duration = Duration::from_millis(100);
+const MAX_RECOGNIZE_ERROR_DELAY: Duration = Duration::from_secs(5);
+
+// How much audio is kept from before a detection
+const PRE_TRIGGER_WINDOW: Duration = Duration::from_secs(2);
+
+// The tail of the pre-trigger buffer whose energy a wake word has to reach
+const WAKE_ENERGY_WINDOW: Duration = Duration::from_millis(500);
+
+// Semantic tags on the alternatives of the session-only grammars
+const VOCABULARY_TAG: &str = "vocabulary";
+const CONTEXT_TAG: &str = "context";
+const NUMBER_TAG: &str = "number";
+
+// SAPI waits on its own event, which a channel can't interrupt, so
+// recognize() is called in slices this long and the shutdown channel is
+// checked in between
+const RECOGNIZE_SLICE: Duration = Duration::from_millis(50);
+
+// Longest run of number words one dictated number can have
+const MAX_DICTATED_WORDS: usize = 32;
+
+// The phrase the SAPI grammar listens for
+pub const WAKE_PHRASE: &str = "Hey Jackson";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectorState {
+    Idle,
+    Starting,
+    Listening,
+    // A recoverable error happened; the loop keeps trying
+    Retrying,
+    // A non-recoverable error stopped the detector
+    Error,
+}
+
+impl DetectorState {
+    // Started and not stopped, even if it's recovering from an error
+    pub fn is_active(self) -> bool {
+        matches!(self, DetectorState::Starting | DetectorState::Listening | DetectorState::Retrying)
+    }
+}
+
+// What the detection callback is told about a wake word
+#[derive(Clone, Debug)]
+pub struct WakeWordDetection {
+    pub keyword_index: usize,
+    pub phrase: String,
+    // sapi_lite doesn't expose SAPI's confidence, and the wake word grammar
+    // only accepts exact matches, so this is always 1.0 for now
+    pub confidence: f32,
+    pub audio_energy_rms: f32,
+}
 
 // Windows Speech Recognition-based wake word detector using sapi_lite
 pub struct WakeWordDetector {
     is_listening_for_wake_word: Arc<Mutex<bool>>,
+    // The recognition thread, joined on stop so SAPI objects aren't in use
+    // when SAPI is finalized
+    listener: Mutex<Option<thread::JoinHandle<()>>>,
+    // Tells the recognition thread to stop without waiting for the flag
+    shutdown_sender: Mutex<Option<Sender<()>>>,
+    // The callback given to start_listening, while listening
+    wake_callback: Mutex<Option<WakeCallback>>,
     recognizer: Arc<Mutex<Option<Recognizer>>>,
-    audio_capture: Arc<Mutex<Option<AudioCapture>>>,
-    app_handle: Arc<Mutex<Option<AppHandle>>>,
+    // Captures the microphone alongside SAPI and feeds sessions' backends
+    pipeline: Mutex<Option<SpeechPipeline>>,
+    emitter: Arc<Mutex<Option<EventEmitter>>>,
+    session: Option<SessionHandle>,
+    constraints: Arc<Mutex<Vec<String>>>,
+    constraints_changed: Arc<AtomicBool>,
+    vocabulary: Arc<Mutex<Vec<String>>>,
+    // The vocabulary and context grammars are only loaded while a speech session runs
+    session_grammars_active: Arc<AtomicBool>,
+    vocabulary_changed: Arc<AtomicBool>,
+    // Phrases per context name, and the context of the foreground app
+    context_grammars: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    active_context: Arc<Mutex<Option<String>>>,
+    context_changed: Arc<AtomicBool>,
+    recognition_backend: SharedBackend,
+    do_not_disturb: Arc<AtomicBool>,
+    state: Arc<Mutex<DetectorState>>,
+    pre_trigger: Arc<Mutex<PreTriggerBuffer>>,
+    min_wake_energy_rms: Arc<Mutex<f32>>,
+    // Set while number dictation is on; gets each dictated number as digits
+    number_input: Arc<Mutex<Option<NumberCallback>>>,
+    number_input_changed: Arc<AtomicBool>,
+    // Microphones captured alongside SAPI; more than one are mixed together
+    input_devices: Mutex<Vec<Option<String>>>,
+    channel_routing: Mutex<ChannelRouting>,
+    // Level automatic gain control brings captured audio to; None leaves it as is
+    agc_target_rms: Mutex<Option<f32>>,
+    // Shapes what the VAD passes on to the backend
+    endpointing: Mutex<EndpointingConfig>,
+    // SAPI is only polled while captured audio is louder than this; None polls always
+    energy_gate_rms: Mutex<Option<f32>>,
+    energy_gate: EnergyGate,
+    sapi_stats: Arc<Mutex<SapiStatsTracker>>,
+    // Compares the capture with our playback; wake words heard in echo are ignored
+    echo: EchoMonitor,
+    record_utterance_audio: AtomicBool,
+    // Audio since the last result, recorded during sessions when enabled
+    utterance_audio: Arc<Mutex<Option<PreTriggerBuffer>>>,
 }
 
 impl WakeWordDetector {
@@ -22,40 +142,404 @@ impl WakeWordDetector {
         // Initialize SAPI
         sapi_lite::initialize().map_err(|e| anyhow::anyhow!("Failed to initialize SAPI: {:?}", e))?;
         
-        // Create a recognizer for wake word detection
-        let recognizer = Recognizer::new()
-            .map_err(|e| anyhow::anyhow!("Failed to create recognizer: {:?}", e))?;
+        // Create a recognizer for wake word detection; SAPI is finalized again
+        // on failure so a retry starts from scratch
+        let recognizer = Recognizer::new().map_err(|e| {
+            sapi_lite::finalize();
+            anyhow::anyhow!("Failed to create recognizer: {:?}", e)
+        })?;
         
         println!("✅ SAPI wake word detector initialized successfully!");
         
         Ok(WakeWordDetector {
             is_listening_for_wake_word: Arc::new(Mutex::new(false)),
+            listener: Mutex::new(None),
+            shutdown_sender: Mutex::new(None),
+            wake_callback: Mutex::new(None),
             recognizer: Arc::new(Mutex::new(Some(recognizer))),
-            audio_capture: Arc::new(Mutex::new(None)),
-            app_handle: Arc::new(Mutex::new(None)),
+            pipeline: Mutex::new(None),
+            emitter: Arc::new(Mutex::new(None)),
+            session: None,
+            constraints: Arc::new(Mutex::new(Vec::new())),
+            constraints_changed: Arc::new(AtomicBool::new(false)),
+            vocabulary: Arc::new(Mutex::new(Vec::new())),
+            session_grammars_active: Arc::new(AtomicBool::new(false)),
+            vocabulary_changed: Arc::new(AtomicBool::new(false)),
+            context_grammars: Arc::new(Mutex::new(HashMap::new())),
+            active_context: Arc::new(Mutex::new(None)),
+            context_changed: Arc::new(AtomicBool::new(false)),
+            recognition_backend: Arc::new(Mutex::new(Box::new(WebSpeechDelegator::new()))),
+            do_not_disturb: Arc::new(AtomicBool::new(false)),
+            state: Arc::new(Mutex::new(DetectorState::Idle)),
+            pre_trigger: Arc::new(Mutex::new(PreTriggerBuffer::new(PRE_TRIGGER_WINDOW))),
+            min_wake_energy_rms: Arc::new(Mutex::new(0.0)),
+            number_input: Arc::new(Mutex::new(None)),
+            number_input_changed: Arc::new(AtomicBool::new(false)),
+            input_devices: Mutex::new(Vec::new()),
+            channel_routing: Mutex::new(ChannelRouting::default()),
+            agc_target_rms: Mutex::new(None),
+            endpointing: Mutex::new(EndpointingConfig::default()),
+            energy_gate_rms: Mutex::new(None),
+            energy_gate: EnergyGate::new(),
+            sapi_stats: Arc::new(Mutex::new(SapiStatsTracker::new())),
+            echo: EchoMonitor::new(),
+            record_utterance_audio: AtomicBool::new(false),
+            utterance_audio: Arc::new(Mutex::new(None)),
         })
     }
     
+    // SAPI can fail to start while another process holds the audio device,
+    // so try up to max_attempts times, waiting base_delay_ms, then twice
+    // that, and so on between attempts
+    pub fn new_with_retry(max_attempts: u32, base_delay_ms: u64) -> Result<Self> {
+        retry_with_backoff(max_attempts, Duration::from_millis(base_delay_ms), thread::sleep, Self::new)
+    }
+    
     // Set the app handle for emitting events
-    pub fn set_app_handle(&self, app_handle: AppHandle) {
-        *self.app_handle.lock().unwrap() = Some(app_handle);
+    pub fn set_app_handle<R: Runtime>(&self, app_handle: AppHandle<R>) {
+        *self.emitter.lock().unwrap() = Some(Box::new(move |event, payload| {
+            let _ = app_handle.emit(event, payload);
+        }));
+    }
+    
+    pub fn state(&self) -> DetectorState {
+        *self.state.lock().unwrap()
+    }
+    
+    // Share the app's do-not-disturb flag; while it is set, detections are
+    // logged but the wake word callback is not called
+    pub fn set_do_not_disturb_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.do_not_disturb = flag;
+    }
+    
+    // Share the app's echo monitor, which also hears the playback
+    pub fn set_echo_monitor(&mut self, echo: EchoMonitor) {
+        self.echo = echo;
+    }
+    
+    // Wake words whose recent audio is quieter than this are treated as
+    // background noise (TV, music). 0 disables the check.
+    pub fn set_min_wake_energy(&self, rms: f32) {
+        *self.min_wake_energy_rms.lock().unwrap() = rms;
+    }
+    
+    // Devices to capture from (None is the default device). Takes effect the
+    // next time listening starts.
+    pub fn set_input_devices(&self, devices: Vec<Option<String>>) {
+        *self.input_devices.lock().unwrap() = devices;
+    }
+    
+    // Takes effect the next time listening starts, like the input devices
+    pub fn set_channel_routing(&self, routing: ChannelRouting) {
+        *self.channel_routing.lock().unwrap() = routing;
+    }
+    
+    // Turn automatic gain control on with the given target RMS, or off.
+    // Applies right away when listening.
+    pub fn set_agc(&self, target_rms: Option<f32>) {
+        *self.agc_target_rms.lock().unwrap() = target_rms;
+        if let Some(pipeline) = self.pipeline.lock().unwrap().as_ref() {
+            pipeline.set_processors(self.processors());
+        }
+    }
+    
+    // Skip SAPI while the captured audio stays below `threshold_rms`, or poll it
+    // always with None. Applies right away when listening.
+    pub fn set_energy_gate(&self, threshold_rms: Option<f32>) {
+        *self.energy_gate_rms.lock().unwrap() = threshold_rms;
+        self.energy_gate.reset();
+        if let Some(pipeline) = self.pipeline.lock().unwrap().as_ref() {
+            pipeline.set_processors(self.processors());
+        }
+    }
+    
+    pub fn sapi_stats(&self) -> SapiStats {
+        self.sapi_stats.lock().unwrap().snapshot(Instant::now())
+    }
+    
+    pub fn reset_sapi_stats(&self) {
+        self.sapi_stats.lock().unwrap().reset();
+    }
+    
+    fn processors(&self) -> Vec<Box<dyn AudioProcessor>> {
+        let mut processors: Vec<Box<dyn AudioProcessor>> = Vec::new();
+        // Ahead of the AGC, which would lift the background above the threshold
+        if let Some(threshold_rms) = *self.energy_gate_rms.lock().unwrap() {
+            processors.push(Box::new(EnergyGateFilter::new(self.energy_gate.clone(), threshold_rms)));
+        }
+        if let Some(target_rms) = *self.agc_target_rms.lock().unwrap() {
+            processors.push(Box::new(AutoGainControl::new(target_rms)));
+        }
+        processors
+    }
+    
+    // The detector opens a session on the wake word and pushes everything
+    // recognized on the continuous speech path to the orchestrator
+    pub fn set_session_handle(&mut self, session: SessionHandle) {
+        session.set_constraints(self.constraints.lock().unwrap().clone());
+        self.session = Some(session);
+    }
+    
+    // Swap the backend used for the continuous speech path. The caller is
+    // responsible for ending any active session first.
+    pub fn set_backend(&self, backend: Box<dyn RecognitionBackend + Send>) {
+        let mut backend_guard = self.recognition_backend.lock().unwrap();
+        backend_guard.stop();
+        println!("🔁 Recognition backend: {} -> {}", backend_guard.name(), backend.name());
+        *backend_guard = backend;
+    }
+    
+    // Sets the VAD that feeds the backend, and the backend itself. The SAPI
+    // wake word loop only matches a fixed phrase, and sapi_lite doesn't expose
+    // the engine's timeout properties.
+    pub fn set_endpointing(&self, config: &EndpointingConfig) {
+        *self.endpointing.lock().unwrap() = config.clone();
+        if let Some(pipeline) = self.pipeline.lock().unwrap().as_ref() {
+            pipeline.set_endpointing(config);
+        }
+        self.recognition_backend.lock().unwrap().set_endpointing(config);
     }
     
-    pub fn start_listening(&self, callback: impl Fn(usize) + Send + Sync + 'static) {
+    pub fn backend_name(&self) -> &'static str {
+        self.recognition_backend.lock().unwrap().name()
+    }
+    
+    // Phrases to favor, such as names the recognizer keeps getting wrong. They
+    // apply from the next speech session.
+    pub fn set_vocabulary(&self, phrases: &[String]) {
+        *self.vocabulary.lock().unwrap() = phrases.to_vec();
+    }
+    
+    // Phrases to recognize while each context (e.g. "browser") is active
+    pub fn set_context_grammars(&self, grammars: HashMap<String, Vec<String>>) {
+        *self.context_grammars.lock().unwrap() = grammars;
+        self.context_changed.store(true, Ordering::SeqCst);
+    }
+    
+    // Switch to the grammar of the given context, or none
+    pub fn set_active_context(&self, context: Option<&str>) {
+        let mut active_context = self.active_context.lock().unwrap();
+        if active_context.as_deref() != context {
+            println!("🪟 Recognition context: {}", context.unwrap_or("none"));
+            *active_context = context.map(str::to_string);
+            self.context_changed.store(true, Ordering::SeqCst);
+        }
+    }
+    
+    // Record session audio so utterances can be played back or saved.
+    // Takes effect from the next session.
+    pub fn set_record_utterance_audio(&self, enabled: bool) {
+        self.record_utterance_audio.store(enabled, Ordering::SeqCst);
+        if !enabled {
+            *self.utterance_audio.lock().unwrap() = None;
+        }
+    }
+    
+    // Audio recorded since the last call, as mono 16 kHz, or None when
+    // recording is off or nothing was captured
+    pub fn take_utterance_audio(&self) -> Option<Vec<i16>> {
+        let mut guard = self.utterance_audio.lock().unwrap();
+        let buffer = guard.as_mut()?;
+        let samples = buffer.recent(DEFAULT_MAX_STORED_AUDIO);
+        let sample_rate = buffer.sample_rate();
+        buffer.clear();
+        drop(guard);
+        
+        if samples.is_empty() {
+            return None;
+        }
+        Some(audio::resample(&samples, sample_rate, STORED_SAMPLE_RATE))
+    }
+    
+    // Start the recognition backend for a speech session, forwarding its
+    // results to the session orchestrator
+    pub fn start_speech_recognition(&self) -> Result<()> {
+        if self.record_utterance_audio.load(Ordering::SeqCst) {
+            *self.utterance_audio.lock().unwrap() = Some(PreTriggerBuffer::new(DEFAULT_MAX_STORED_AUDIO));
+        }
+        let session = self.session.clone();
+        let vocabulary = self.vocabulary.lock().unwrap().clone();
+        self.session_grammars_active.store(true, Ordering::SeqCst);
+        self.vocabulary_changed.store(true, Ordering::SeqCst);
+        self.context_changed.store(true, Ordering::SeqCst);
+        
+        let backend = self.recognition_backend.lock().unwrap();
+        backend.set_vocabulary(&vocabulary);
+        backend.start(Box::new(move |result| {
+            if let Some(session) = &session {
+                session.push_result(result);
+            }
+        }))?;
+        drop(backend);
+        if let Some(pipeline) = self.pipeline.lock().unwrap().as_ref() {
+            pipeline.take_fed_audio();
+            pipeline.set_feeding(true);
+        }
+        Ok(())
+    }
+    
+    // Stop the microphone reaching the speech backends, e.g. while a reply is
+    // spoken, keeping the stream open. SAPI reads the microphone itself, so
+    // the recognition loop drops what it hears while the session handle says
+    // the microphone is paused.
+    pub fn pause_microphone(&self) {
+        if let Some(session) = &self.session {
+            session.set_microphone_paused(true);
+        }
+        if !self.pipeline.lock().unwrap().as_ref().is_some_and(|pipeline| pipeline.pause()) {
+            println!("ℹ️ No capture to pause, only recognition results are dropped");
+        }
+        println!("🔇 Microphone paused");
+    }
+    
+    pub fn resume_microphone(&self) {
+        if let Some(session) = &self.session {
+            session.set_microphone_paused(false);
+        }
+        if let Some(pipeline) = self.pipeline.lock().unwrap().as_ref() {
+            pipeline.resume();
+        }
+        println!("🎤 Microphone resumed");
+    }
+    
+    // Speech the backend was fed during the current or last session
+    pub fn session_audio(&self) -> Duration {
+        self.pipeline
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(Duration::ZERO, |pipeline| pipeline.take_fed_audio())
+    }
+    
+    pub fn stop_speech_recognition(&self) {
+        if let Some(pipeline) = self.pipeline.lock().unwrap().as_ref() {
+            pipeline.set_feeding(false);
+        }
+        *self.utterance_audio.lock().unwrap() = None;
+        self.session_grammars_active.store(false, Ordering::SeqCst);
+        self.vocabulary_changed.store(true, Ordering::SeqCst);
+        self.context_changed.store(true, Ordering::SeqCst);
+        self.recognition_backend.lock().unwrap().stop();
+    }
+    
+    // Switch to number dictation: spoken digits and numbers ("three point one
+    // four", "forty two") are recognized and passed to `callback` as a numeric
+    // string like "3.14". Needs wake word detection to be running.
+    pub fn start_number_input(&self, callback: impl Fn(String) + Send + Sync + 'static) -> Result<()> {
+        if !*self.is_listening_for_wake_word.lock().unwrap() {
+            return Err(anyhow::anyhow!("Wake word detection isn't running"));
+        }
+        *self.number_input.lock().unwrap() = Some(Arc::new(callback));
+        self.number_input_changed.store(true, Ordering::SeqCst);
+        println!("🔢 Number dictation started");
+        Ok(())
+    }
+    
+    pub fn stop_number_input(&self) {
+        if self.number_input.lock().unwrap().take().is_some() {
+            self.number_input_changed.store(true, Ordering::SeqCst);
+            println!("🔢 Number dictation stopped");
+        }
+    }
+    
+    // Constrain recognition to the given phrases until cleared. The recognition
+    // thread picks up the change on its next loop iteration; the session drops
+    // anything else right away, whichever recognizer heard it.
+    pub fn set_constraints(&self, phrases: Vec<String>) {
+        if let Some(session) = &self.session {
+            session.set_constraints(phrases.clone());
+        }
+        *self.constraints.lock().unwrap() = phrases;
+        self.constraints_changed.store(true, Ordering::SeqCst);
+    }
+    
+    pub fn clear_constraints(&self) {
+        self.set_constraints(Vec::new());
+    }
+    
+    // Returns a receiver that gets a message once the grammar is enabled and the
+    // recognition loop is running. The sender is dropped if setup fails.
+    pub fn start_listening(&self, callback: impl Fn(WakeWordDetection) + Send + Sync + 'static) -> Receiver<()> {
+        let (ready_sender, ready_receiver) = bounded(1);
+        
         let mut is_listening_guard = self.is_listening_for_wake_word.lock().unwrap();
         if *is_listening_guard {
             println!("⚠️ Already listening for wake word, ignoring start request");
-            return;
+            let _ = ready_sender.send(());
+            return ready_receiver;
         }
         *is_listening_guard = true;
         drop(is_listening_guard);
+        set_state(&self.state, &self.emitter, DetectorState::Starting);
+        
+        // SAPI reads the microphone itself, so capture it alongside to know how
+        // loud the audio behind a detection was, and to feed backends that
+        // take their audio from us
+        // Virtual cables often run at 44.1 or 96 kHz; the backends get 16 kHz
+        let mut capture = AudioCapture::new()
+            .with_channel_routing(self.channel_routing.lock().unwrap().clone())
+            .auto_resample_to(audio::MIX_SAMPLE_RATE);
+        let emitter = Arc::clone(&self.emitter);
+        capture.on_device_error(move |device| {
+            emit(&emitter, "capture-device-error", CaptureDeviceErrorPayload { device });
+        });
+        let vad = VadStage::from_endpointing(DEFAULT_VAD_THRESHOLD_RMS, &self.endpointing.lock().unwrap());
+        let mut pipeline = SpeechPipeline::new(capture, vad, Arc::clone(&self.recognition_backend));
+        pipeline.set_input_devices(self.input_devices.lock().unwrap().clone());
+        pipeline.set_processors(self.processors());
+        let pre_trigger = Arc::clone(&self.pre_trigger);
+        let utterance_audio = Arc::clone(&self.utterance_audio);
+        pre_trigger.lock().unwrap().clear();
+        self.energy_gate.reset();
+        // The microphone may have changed, and with it the echo
+        self.echo.reset();
+        let echo = self.echo.clone();
+        let echo_emitter = Arc::clone(&self.emitter);
+        pipeline.add_tap(move |frame, sample_rate| {
+            pre_trigger.lock().unwrap().push(frame, sample_rate);
+            if let Some(buffer) = utterance_audio.lock().unwrap().as_mut() {
+                buffer.push(frame, sample_rate);
+            }
+            if let Some(level) = echo.push_capture(frame, sample_rate) {
+                println!("🔁 Microphone is picking up playback (level {:.2})", level);
+                emit(&echo_emitter, "echo-detected", EchoDetectedPayload { level });
+            }
+        });
+        match pipeline.start() {
+            Ok(()) => *self.pipeline.lock().unwrap() = Some(pipeline),
+            Err(e) => eprintln!("⚠️ Audio capture unavailable, skipping wake word energy check: {}", e),
+        }
         
         let is_listening_for_wake_word = Arc::clone(&self.is_listening_for_wake_word);
         let recognizer = Arc::clone(&self.recognizer);
-        let callback = Arc::new(callback);
+        let callback: WakeCallback = Arc::new(callback);
+        *self.wake_callback.lock().unwrap() = Some(Arc::clone(&callback));
+        let session = self.session.clone();
+        let constraints = Arc::clone(&self.constraints);
+        let constraints_changed = Arc::clone(&self.constraints_changed);
+        let vocabulary = Arc::clone(&self.vocabulary);
+        let session_grammars_active = Arc::clone(&self.session_grammars_active);
+        let vocabulary_changed = Arc::clone(&self.vocabulary_changed);
+        let context_grammars = Arc::clone(&self.context_grammars);
+        let active_context = Arc::clone(&self.active_context);
+        let context_changed = Arc::clone(&self.context_changed);
+        let do_not_disturb = Arc::clone(&self.do_not_disturb);
+        let state = Arc::clone(&self.state);
+        let emitter = Arc::clone(&self.emitter);
+        let pre_trigger = Arc::clone(&self.pre_trigger);
+        let min_wake_energy_rms = Arc::clone(&self.min_wake_energy_rms);
+        let energy_gate = self.energy_gate.clone();
+        let sapi_stats = Arc::clone(&self.sapi_stats);
+        let echo = self.echo.clone();
+        let number_input = Arc::clone(&self.number_input);
+        let number_input_changed = Arc::clone(&self.number_input_changed);
+        // Constraints set before the thread starts still need to be loaded
+        constraints_changed.store(true, Ordering::SeqCst);
+        let (shutdown_sender, shutdown_receiver) = unbounded();
+        *self.shutdown_sender.lock().unwrap() = Some(shutdown_sender);
         
         // Start wake word detection in a separate thread
-        thread::spawn(move || {
+        let listener = thread::spawn(move || {
             println!("🎙️ Started listening for wake words with SAPI...");
             
             // Get the recognizer from the Arc<Mutex>
@@ -63,7 +547,7 @@ impl WakeWordDetector {
             let recognizer = match recognizer_guard.as_ref() {
                 Some(recognizer) => recognizer,
                 None => {
-                    eprintln!("❌ Recognizer not available");
+                    report_error(&state, &emitter, SpeechError::BackendUnavailable("SAPI recognizer not available".to_string()));
                     let mut guard = is_listening_for_wake_word.lock().unwrap();
                     *guard = false;
                     return;
@@ -74,7 +558,8 @@ impl WakeWordDetector {
             let ctx = match SyncContext::new(recognizer) {
                 Ok(ctx) => ctx,
                 Err(e) => {
-                    eprintln!("❌ Failed to create recognition context: {:?}", e);
+                    sapi_stats.lock().unwrap().record_error(e.code().0);
+                    report_error(&state, &emitter, SpeechError::BackendUnavailable(format!("Failed to create recognition context: {:?}", e)));
                     let mut guard = is_listening_for_wake_word.lock().unwrap();
                     *guard = false;
                     return;
@@ -84,12 +569,13 @@ impl WakeWordDetector {
             // Create a grammar with the wake word "Hey Jackson" - more precise matching
             let grammar = match ctx
                 .grammar_builder()
-                .add_rule(&Rule::text("Hey Jackson")) // Exact match
+                .add_rule(&Rule::text(WAKE_PHRASE)) // Exact match
                 .build()
             {
                 Ok(grammar) => grammar,
                 Err(e) => {
-                    eprintln!("❌ Failed to create grammar: {:?}", e);
+                    sapi_stats.lock().unwrap().record_error(e.code().0);
+                    report_error(&state, &emitter, SpeechError::Internal(format!("Failed to create grammar: {:?}", e)));
                     let mut guard = is_listening_for_wake_word.lock().unwrap();
                     *guard = false;
                     return;
@@ -98,7 +584,8 @@ impl WakeWordDetector {
             
             // Enable the grammar
             if let Err(e) = grammar.set_enabled(true) {
-                eprintln!("❌ Failed to enable grammar: {:?}", e);
+                sapi_stats.lock().unwrap().record_error(e.code().0);
+                report_error(&state, &emitter, SpeechError::Internal(format!("Failed to enable grammar: {:?}", e)));
                 let mut guard = is_listening_for_wake_word.lock().unwrap();
                 *guard = false;
                 return;
@@ -106,55 +593,485 @@ impl WakeWordDetector {
             
             println!("✅ SAPI recognition started successfully");
             
-            // Keep recognizing while listening
+            // Let the caller know the detector is actually listening now
+            set_state(&state, &emitter, DetectorState::Listening);
+            sapi_stats.lock().unwrap().start(Instant::now());
+            let _ = ready_sender.send(());
+            let mut consecutive_errors = 0;
+            
+            // Grammar for the currently active constraint phrases, if any. It is
+            // only held to keep the grammar loaded while the constraints apply.
+            let mut _constraint_grammar: Option<Grammar> = None;
+            // sapi_lite has no dictation or rule weights, so vocabulary phrases
+            // get a grammar of their own for the length of a speech session
+            let mut _vocabulary_grammar: Option<Grammar> = None;
+            let mut _context_grammar: Option<Grammar> = None;
+            let mut _number_grammar: Option<Grammar> = None;
+            
+            // Keep recognizing while listening, until stop_listening signals or
+            // the detector is dropped
             while {
                 let guard = is_listening_for_wake_word.lock().unwrap();
                 *guard
-            } {
+            } && matches!(shutdown_receiver.try_recv(), Err(TryRecvError::Empty)) {
+                // Swap the constraint grammar if the phrase list changed
+                if constraints_changed.swap(false, Ordering::SeqCst) {
+                    let phrases = constraints.lock().unwrap().clone();
+                    // Drop the old grammar first so it's unloaded from the context
+                    _constraint_grammar = None;
+                    if !phrases.is_empty() {
+                        match build_phrase_grammar(&ctx, &phrases, |index| SemanticValue::Int(index as i32)) {
+                            Ok(grammar) => {
+                                println!("🔒 Recognition constrained to {} phrases", phrases.len());
+                                _constraint_grammar = Some(grammar);
+                            }
+                            Err(e) => eprintln!("❌ Failed to create constraint grammar: {:?}", e),
+                        }
+                    } else {
+                        println!("🔓 Recognition constraints cleared");
+                    }
+                }
+                
+                if vocabulary_changed.swap(false, Ordering::SeqCst) {
+                    _vocabulary_grammar = None;
+                    let phrases = vocabulary.lock().unwrap().clone();
+                    if session_grammars_active.load(Ordering::SeqCst) && !phrases.is_empty() {
+                        let tag = |_| SemanticValue::String(Cow::Borrowed(VOCABULARY_TAG));
+                        match build_phrase_grammar(&ctx, &phrases, tag) {
+                            Ok(grammar) => _vocabulary_grammar = Some(grammar),
+                            Err(e) => eprintln!("❌ Failed to create vocabulary grammar: {:?}", e),
+                        }
+                    }
+                }
+                
+                if context_changed.swap(false, Ordering::SeqCst) {
+                    _context_grammar = None;
+                    let context = active_context.lock().unwrap().clone();
+                    let phrases = context
+                        .and_then(|context| context_grammars.lock().unwrap().get(&context).cloned())
+                        .unwrap_or_default();
+                    if session_grammars_active.load(Ordering::SeqCst) && !phrases.is_empty() {
+                        let tag = |_| SemanticValue::String(Cow::Borrowed(CONTEXT_TAG));
+                        match build_phrase_grammar(&ctx, &phrases, tag) {
+                            Ok(grammar) => _context_grammar = Some(grammar),
+                            Err(e) => eprintln!("❌ Failed to create context grammar: {:?}", e),
+                        }
+                    }
+                }
+                
+                if number_input_changed.swap(false, Ordering::SeqCst) {
+                    _number_grammar = None;
+                    if number_input.lock().unwrap().is_some() {
+                        match build_number_grammar(&ctx) {
+                            Ok(grammar) => _number_grammar = Some(grammar),
+                            Err(e) => eprintln!("❌ Failed to create number grammar: {:?}", e),
+                        }
+                    }
+                }
+                
+                // Nothing loud enough to be a wake word; SAPI keeps any result
+                // until the gate opens again. Sessions are never gated, and
+                // neither is a paused microphone, whose results are drained
+                // and dropped instead of surfacing after the pause.
+                let paused = session.as_ref().is_some_and(|session| session.microphone_paused());
+                if !session_grammars_active.load(Ordering::SeqCst) && !paused && !energy_gate.is_open() {
+                    thread::sleep(RECOGNIZE_SLICE);
+                    continue;
+                }
+                
                 // Try to recognize the wake word with a timeout
-                match ctx.recognize(Duration::from_millis(500)) {
+                let outcome = ctx.recognize(RECOGNIZE_SLICE);
+                if outcome.is_ok() && consecutive_errors > 0 {
+                    consecutive_errors = 0;
+                    set_state(&state, &emitter, DetectorState::Listening);
+                }
+                match outcome {
+                    Ok(Some(_)) if paused => {
+                        // Muted: not even the wake word counts
+                        println!("🔇 Ignored a phrase while the microphone is paused");
+                        sapi_stats.lock().unwrap().record_phrase(false, None);
+                    }
                     Ok(Some(phrase)) => {
                         let text = phrase.text.to_string_lossy();
                         println!("🔊 Recognized: \"{}\"", text);
+                        // Whether anything used the phrase, for the statistics
+                        let mut accepted = false;
+                        let mut confidence = None;
                         
                         // Check if "Hey Jackson" was recognized
-                        if text.trim().eq_ignore_ascii_case("hey jackson") {
-                            println!("🎯 Wake word detected with high confidence!");
-                            callback(0); // Index 0 for "Hey Jackson"
+                        if text.trim().eq_ignore_ascii_case(WAKE_PHRASE) {
+                            let threshold = *min_wake_energy_rms.lock().unwrap();
+                            let energy = wake_energy(&pre_trigger);
+                            let output_playing = session.as_ref().is_some_and(|session| session.output_playing());
+                            if do_not_disturb.load(Ordering::SeqCst) {
+                                println!("🔕 Wake word ignored (do not disturb)");
+                            } else if echo.is_suppressing(output_playing) {
+                                println!("🔁 Wake word ignored (playback echo)");
+                            } else if energy.is_some_and(|rms| rms < threshold) {
+                                let rms = energy.unwrap_or_default();
+                                println!("🔇 Wake word rejected, energy {:.4} below {:.4}", rms, threshold);
+                                let payload = WakeWordRejectedPayload { rms, threshold };
+                                emit(&emitter, "wake-word-rejected-low-energy", payload);
+                            } else {
+                                println!("🎯 Wake word detected with high confidence!");
+                                accepted = true;
+                                confidence = Some(1.0);
+                                if let Some(session) = &session {
+                                    session.open();
+                                }
+                                callback(WakeWordDetection {
+                                    keyword_index: 0, // Index 0 for "Hey Jackson"
+                                    phrase: text.trim().to_string(),
+                                    confidence: 1.0,
+                                    audio_energy_rms: energy.unwrap_or_default(),
+                                });
+                            }
+                        } else if has_tag(&phrase, NUMBER_TAG) {
+                            let callback = number_input.lock().unwrap().clone();
+                            match (callback, normalize::dictated_number(&text)) {
+                                (Some(callback), Some(number)) => {
+                                    println!("🔢 Dictated number: {}", number);
+                                    accepted = true;
+                                    callback(number);
+                                }
+                                _ => println!("🔢 Couldn't read \"{}\" as a number", text),
+                            }
+                        } else if let Some(index) = constraint_index(&phrase) {
+                            println!("🔒 Matched constraint {}: \"{}\"", index, text);
+                            accepted = session.is_some();
+                            if let Some(session) = &session {
+                                session.push_result(RecognitionResult {
+                                    text: text.trim().to_string(),
+                                    raw_text: text.trim().to_string(),
+                                    constraint_index: Some(index),
+                                    confidence: None,
+                                    source: "sapi".to_string(),
+                                    entities: Vec::new(),
+                                    fallbacks: 0,
+                                    alternatives: Vec::new(),
+                                });
+                            }
+                        } else if has_tag(&phrase, VOCABULARY_TAG) || has_tag(&phrase, CONTEXT_TAG) {
+                            println!("📖 Matched session phrase: \"{}\"", text);
+                            accepted = session.is_some();
+                            if let Some(session) = &session {
+                                session.push_result(RecognitionResult {
+                                    text: text.trim().to_string(),
+                                    raw_text: text.trim().to_string(),
+                                    constraint_index: None,
+                                    confidence: None,
+                                    source: "sapi".to_string(),
+                                    entities: Vec::new(),
+                                    fallbacks: 0,
+                                    alternatives: Vec::new(),
+                                });
+                            }
                         }
+                        sapi_stats.lock().unwrap().record_phrase(accepted, confidence);
                     }
                     Ok(None) => {
                         // No recognition, continue listening
                     }
                     Err(e) => {
-                        eprintln!("⚠️ Recognition error: {:?}", e);
-                        // Continue listening despite errors
+                        consecutive_errors += 1;
+                        sapi_stats.lock().unwrap().record_error(e.code().0);
+                        if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
+                            // Persistent failure, stop instead of spinning
+                            report_error(&state, &emitter, SpeechError::Internal(format!("Recognition keeps failing: {:?}", e)));
+                            sapi_stats.lock().unwrap().stop();
+                            *is_listening_for_wake_word.lock().unwrap() = false;
+                            return;
+                        }
+                        // Usually the input device dropping out; keep listening.
+                        // The streak is reported once, and cleared by the next success.
+                        if consecutive_errors == 1 {
+                            report_error(&state, &emitter, SpeechError::AudioUnavailable(format!("{:?}", e)));
+                        } else {
+                            eprintln!("❌ Recognition failed again ({} in a row): {:?}", consecutive_errors, e);
+                        }
+                        let delay = recognize_error_delay(consecutive_errors);
+                        if !matches!(shutdown_receiver.recv_timeout(delay), Err(RecvTimeoutError::Timeout)) {
+                            break;
+                        }
                     }
                 }
             }
             
+            sapi_stats.lock().unwrap().stop();
+            set_state(&state, &emitter, DetectorState::Idle);
             println!("🛑 SAPI wake word recognition stopped.");
         });
+        *self.listener.lock().unwrap() = Some(listener);
+        
+        ready_receiver
     }
     
     pub fn stop_listening(&self) {
-        // Stop wake word detection
+        self.request_stop();
+        self.stop_capture();
+        self.join_listener();
+        
+        println!("🛑 Stopped listening.");
+    }
+    
+    // Tell the recognition thread to stop, without waiting for it
+    pub fn request_stop(&self) {
         let mut guard = self.is_listening_for_wake_word.lock().unwrap();
         *guard = false;
         drop(guard);
-        
-        // Also stop the audio capture if it exists
-        if let Ok(mut capture_guard) = self.audio_capture.lock() {
-            if let Some(ref mut capture) = capture_guard.as_mut() {
-                capture.stop_capture();
+        if let Some(sender) = self.shutdown_sender.lock().unwrap().take() {
+            let _ = sender.send(());
+        }
+        self.wake_callback.lock().unwrap().take();
+        self.stop_number_input();
+    }
+    
+    // Close the capture that runs alongside SAPI, if it's running
+    pub fn stop_capture(&self) {
+        if let Some(mut pipeline) = self.pipeline.lock().unwrap().take() {
+            pipeline.stop();
+        }
+    }
+    
+    // Wait up to `timeout` for the recognition thread after request_stop.
+    // False if it's still running; it's kept so a later stop can join it.
+    pub fn join_listener_within(&self, timeout: Duration) -> bool {
+        let mut listener = self.listener.lock().unwrap();
+        let Some(handle) = listener.as_ref() else {
+            return true;
+        };
+        if handle.thread().id() == thread::current().id() {
+            return false;
+        }
+        let deadline = Instant::now() + timeout;
+        while !handle.is_finished() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        if !handle.is_finished() {
+            return false;
+        }
+        if listener.take().is_some_and(|handle| handle.join().is_err()) {
+            eprintln!("❌ Wake word recognition thread panicked");
+        }
+        true
+    }
+    
+    // Act as if the wake word was heard, without SAPI, for driving the
+    // pipeline from tests
+    #[cfg(debug_assertions)]
+    pub fn simulate_wake_word(&self, keyword_index: usize) -> Result<()> {
+        let callback = self
+            .wake_callback
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Not listening for the wake word"))?;
+        println!("🧪 Simulating wake word {}", keyword_index);
+        if self.session.as_ref().is_some_and(|session| session.microphone_paused()) {
+            println!("🔇 Ignored the wake word while the microphone is paused");
+            return Ok(());
+        }
+        if let Some(session) = &self.session {
+            session.open();
+        }
+        callback(WakeWordDetection {
+            keyword_index,
+            phrase: WAKE_PHRASE.to_string(),
+            confidence: 1.0,
+            audio_energy_rms: 0.0,
+        });
+        Ok(())
+    }
+    
+    // Deliver `text` to the session as if it had been recognized
+    #[cfg(debug_assertions)]
+    pub fn simulate_speech(&self, text: &str) -> Result<()> {
+        if self.wake_callback.lock().unwrap().is_none() {
+            return Err(anyhow::anyhow!("Not listening"));
+        }
+        let session = self.session.as_ref().ok_or_else(|| anyhow::anyhow!("No session handle"))?;
+        println!("🧪 Simulating speech \"{}\"", text);
+        session.push_result(RecognitionResult {
+            text: text.to_string(),
+            raw_text: text.to_string(),
+            constraint_index: None,
+            confidence: None,
+            source: "simulated".to_string(),
+            entities: Vec::new(),
+            fallbacks: 0,
+            alternatives: Vec::new(),
+        });
+        Ok(())
+    }
+    
+    // Wait for the recognition thread to notice the shutdown, which takes up
+    // to one recognize() slice. Called from the thread itself (by a callback)
+    // it can't be waited for, so it's left to finish on its own.
+    fn join_listener(&self) {
+        let Some(listener) = self.listener.lock().unwrap().take() else {
+            return;
+        };
+        if listener.thread().id() == thread::current().id() {
+            return;
+        }
+        if listener.join().is_err() {
+            eprintln!("❌ Wake word recognition thread panicked");
+        }
+    }
+}
+
+// Calls `attempt` until it succeeds or has failed max_attempts times (at
+// least once), sleeping base_delay * 2^n after the nth failure
+fn retry_with_backoff<T>(
+    max_attempts: u32,
+    base_delay: Duration,
+    mut sleep: impl FnMut(Duration),
+    mut attempt: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let max_attempts = max_attempts.max(1);
+    let mut failures = 0;
+    loop {
+        match attempt() {
+            Ok(value) => return Ok(value),
+            Err(e) if failures + 1 < max_attempts => {
+                let delay = base_delay.saturating_mul(2u32.saturating_pow(failures));
+                failures += 1;
+                eprintln!(
+                    "⚠️ Wake word detector attempt {}/{} failed, retrying in {} ms: {}",
+                    failures,
+                    max_attempts,
+                    delay.as_millis(),
+                    e
+                );
+                sleep(delay);
             }
-            *capture_guard = None;
+            Err(e) => return Err(e),
         }
-        
-        println!("🛑 Stopped listening.");
     }
 }
 
+// Sends an event to the app; the detector doesn't depend on the Tauri runtime type
+type EventEmitter = Box<dyn Fn(&str, serde_json::Value) + Send>;
+
+type NumberCallback = Arc<dyn Fn(String) + Send + Sync>;
+
+type WakeCallback = Arc<dyn Fn(WakeWordDetection) + Send + Sync>;
+
+fn emit(emitter: &Mutex<Option<EventEmitter>>, event: &str, payload: impl Serialize) {
+    if let Some(emit) = emitter.lock().unwrap().as_ref() {
+        emit(event, serde_json::to_value(payload).unwrap_or_default());
+    }
+}
+
+fn set_state(state: &Mutex<DetectorState>, emitter: &Mutex<Option<EventEmitter>>, new_state: DetectorState) {
+    let mut guard = state.lock().unwrap();
+    if *guard == new_state {
+        return;
+    }
+    *guard = new_state;
+    drop(guard);
+    
+    emit(emitter, "wake-word-state-changed", new_state);
+}
+
+// Emit a "speech-error" event and move to Retrying or Error depending on
+// whether the failure is recoverable
+fn report_error(state: &Mutex<DetectorState>, emitter: &Mutex<Option<EventEmitter>>, error: SpeechError) {
+    eprintln!("❌ {}", error);
+    let next_state = if error.recoverable() {
+        DetectorState::Retrying
+    } else {
+        DetectorState::Error
+    };
+    
+    emit(emitter, "speech-error", error.payload());
+    set_state(state, emitter, next_state);
+}
+
+// RMS of the audio just before a detection, or None if nothing was captured.
+fn wake_energy(pre_trigger: &Mutex<PreTriggerBuffer>) -> Option<f32> {
+    let samples = pre_trigger.lock().unwrap().recent(WAKE_ENERGY_WINDOW);
+    if samples.is_empty() {
+        None
+    } else {
+        Some(audio::rms(&samples))
+    }
+}
+
+// Build a grammar matching exactly one of the given phrases, tagging each
+// alternative (by index) so the match can be mapped back
+fn build_phrase_grammar<'a>(
+    ctx: &SyncContext,
+    phrases: &'a [String],
+    tag: impl Fn(usize) -> SemanticValue<Cow<'a, str>>,
+) -> Result<Grammar> {
+    let texts: Vec<Rule> = phrases.iter().map(|phrase| Rule::text(phrase.as_str())).collect();
+    let tagged: Vec<Rule> = texts
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| Rule::semantic(tag(index), rule))
+        .collect();
+    let options: Vec<&Rule> = tagged.iter().collect();
+    let choice = Rule::choice(options);
+    
+    let grammar = ctx
+        .grammar_builder()
+        .add_rule(&choice)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to build phrase grammar: {:?}", e))?;
+    grammar
+        .set_enabled(true)
+        .map_err(|e| anyhow::anyhow!("Failed to enable phrase grammar: {:?}", e))?;
+    Ok(grammar)
+}
+
+// How long to wait after the nth recognition error in a row
+fn recognize_error_delay(consecutive_errors: u32) -> Duration {
+    let doublings = consecutive_errors.saturating_sub(1).min(16);
+    RECOGNIZE_ERROR_DELAY.saturating_mul(1 << doublings).min(MAX_RECOGNIZE_ERROR_DELAY)
+}
+
+// Grammar for dictated numbers: an optional "negative", number words, and
+// optionally "point" followed by more number words
+fn build_number_grammar(ctx: &SyncContext) -> Result<Grammar> {
+    let arena = RuleArena::new();
+    let words: Vec<&Rule> = normalize::dictation_words().into_iter().map(|word| arena.text(word)).collect();
+    let word = arena.choice(words);
+    let digits = arena.repeat(1..=MAX_DICTATED_WORDS, word);
+    let fraction = arena.sequence(vec![arena.text("point"), digits]);
+    let number = arena.sequence(vec![
+        arena.repeat(..=1, arena.text("negative")),
+        digits,
+        arena.repeat(..=1, fraction),
+    ]);
+    let tagged = arena.semantic(NUMBER_TAG, number);
+    
+    let grammar = ctx
+        .grammar_builder()
+        .add_rule(tagged)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to build number grammar: {:?}", e))?;
+    grammar
+        .set_enabled(true)
+        .map_err(|e| anyhow::anyhow!("Failed to enable number grammar: {:?}", e))?;
+    Ok(grammar)
+}
+
+// Extract the constraint index tagged onto a recognized phrase
+fn constraint_index(phrase: &Phrase) -> Option<usize> {
+    phrase.semantics.iter().find_map(|tree| match tree.value {
+        SemanticValue::Int(index) if index >= 0 => Some(index as usize),
+        _ => None,
+    })
+}
+
+fn has_tag(phrase: &Phrase, expected: &str) -> bool {
+    phrase
+        .semantics
+        .iter()
+        .any(|tree| matches!(&tree.value, SemanticValue::String(tag) if tag == expected))
+}
+
 // Make WakeWordDetector thread-safe
 unsafe impl Send for WakeWordDetector {}
 unsafe impl Sync for WakeWordDetector {}
@@ -162,8 +1079,48 @@ unsafe impl Sync for WakeWordDetector {}
 // Finalize SAPI when the program exits
 impl Drop for WakeWordDetector {
     fn drop(&mut self) {
+        self.stop_listening();
+        // The recognizer has to be released before SAPI goes away
+        self.recognizer.lock().unwrap().take();
         println!("🔧 Finalizing SAPI...");
         sapi_lite::finalize();
         println!("✅ SAPI finalized successfully");
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_with_doubling_delays() {
+        let mut delays = Vec::new();
+        let mut calls = 0;
+        let result = retry_with_backoff(3, Duration::from_millis(500), |delay| delays.push(delay), || {
+            calls += 1;
+            if calls < 3 { Err(anyhow::anyhow!("device busy")) } else { Ok(calls) }
+        });
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(delays, [Duration::from_millis(500), Duration::from_millis(1000)]);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_attempt() {
+        let mut delays = Vec::new();
+        let mut calls = 0;
+        let result: Result<()> = retry_with_backoff(3, Duration::from_millis(500), |delay| delays.push(delay), || {
+            calls += 1;
+            Err(anyhow::anyhow!("device busy"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+        assert_eq!(delays.len(), 2);
+    }
+
+    #[test]
+    fn recognize_errors_back_off_up_to_a_cap() {
+        let delays: Vec<u64> = (1..=8).map(|errors| recognize_error_delay(errors).as_millis() as u64).collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1_600, 3_200, 5_000, 5_000]);
+        assert_eq!(recognize_error_delay(u32::MAX), MAX_RECOGNIZE_ERROR_DELAY);
+    }
+}
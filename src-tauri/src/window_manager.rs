@@ -0,0 +1,692 @@
+// window_manager.rs - Sizing, placing, showing and hiding the main window. The
+// geometry is worked out by pure functions over work areas, so it can be
+// tested without a real window.
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{LogicalSize, Monitor, PhysicalPosition, PhysicalSize, Runtime, WebviewWindow};
+
+use crate::config::{AppConfig, MonitorPlacement, WindowAnchor, WindowConfig, WindowMode, WindowSize};
+
+// The part of a monitor not covered by the taskbar, in physical pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorkArea {
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+}
+
+impl WorkArea {
+    fn of(monitor: &Monitor) -> Self {
+        let area = monitor.work_area();
+        Self {
+            position: area.position,
+            size: area.size,
+            scale_factor: monitor.scale_factor(),
+        }
+    }
+
+    pub fn logical_size(&self) -> WindowSize {
+        let size = self.size.to_logical::<f64>(self.scale_factor);
+        WindowSize { width: size.width, height: size.height }
+    }
+}
+
+// The last work area monitor information was read for, used while it can't
+// be (some RDP sessions, just after the display wakes)
+pub type WorkAreaCache = Arc<Mutex<Option<WorkArea>>>;
+
+// Told whether a cached work area was available when monitor information wasn't
+pub type MonitorUnavailableHook = Arc<dyn Fn(bool) + Send + Sync>;
+
+// Until when Moved events are our own set_position calls rather than the
+// user dragging the window
+pub type ProgrammaticMoves = Arc<Mutex<Option<Instant>>>;
+
+// Moved events can arrive a little after set_position returns
+const PROGRAMMATIC_MOVE_GRACE: Duration = Duration::from_millis(250);
+
+// Told whether a display picked by index or name was missing, each time it's
+// looked up
+pub type DisplayMissingHook = Arc<dyn Fn(&MonitorPlacement, bool) + Send + Sync>;
+
+// A snapshot of the window settings, applied to the main window
+pub struct WindowManager<R: Runtime> {
+    window: WebviewWindow<R>,
+    work_area_cache: WorkAreaCache,
+    on_monitor_unavailable: MonitorUnavailableHook,
+    on_display_missing: DisplayMissingHook,
+    programmatic_moves: ProgrammaticMoves,
+    limits: WindowConfig,
+    anchor: WindowAnchor,
+    placement: MonitorPlacement,
+    remembered_size: Option<WindowSize>,
+    user_position: Option<PhysicalPosition<i32>>,
+    always_on_top: bool,
+    show_without_focus: bool,
+}
+
+impl<R: Runtime> WindowManager<R> {
+    pub fn new(
+        window: WebviewWindow<R>,
+        config: &AppConfig,
+        work_area_cache: WorkAreaCache,
+        on_monitor_unavailable: MonitorUnavailableHook,
+        on_display_missing: DisplayMissingHook,
+        programmatic_moves: ProgrammaticMoves,
+    ) -> Self {
+        Self {
+            window,
+            work_area_cache,
+            on_monitor_unavailable,
+            on_display_missing,
+            programmatic_moves,
+            limits: config.window,
+            anchor: config.window_anchor,
+            placement: config.window_monitor.clone(),
+            remembered_size: config.window_size,
+            user_position: config.window_position.map(|position| PhysicalPosition::new(position.x, position.y)),
+            always_on_top: config.always_on_top,
+            show_without_focus: config.show_without_focus,
+        }
+    }
+
+    // Place by the tray instead: on the primary monitor, whose taskbar has
+    // the notification area, and ignoring where the window was dragged
+    pub fn at_tray(mut self) -> Self {
+        self.anchor = WindowAnchor::Tray;
+        self.placement = MonitorPlacement::Primary;
+        self.user_position = None;
+        self
+    }
+
+    pub fn window(&self) -> &WebviewWindow<R> {
+        &self.window
+    }
+
+    pub fn limits(&self) -> &WindowConfig {
+        &self.limits
+    }
+
+    pub fn is_visible(&self) -> bool {
+        matches!(self.window.is_visible(), Ok(true))
+    }
+
+    // Current logical size of the client area
+    pub fn size(&self) -> Option<WindowSize> {
+        let size = self.window.inner_size().ok()?;
+        let size = size.to_logical::<f64>(self.window.scale_factor().ok()?);
+        Some(WindowSize { width: size.width, height: size.height })
+    }
+
+    // The chosen monitor's work area, or the last one that could be read.
+    // Monitors are enumerated every time, since they change when displays are
+    // plugged in or out.
+    fn work_area(&self) -> Option<WorkArea> {
+        match self.placement_monitor() {
+            Some(monitor) => {
+                let work_area = WorkArea::of(&monitor);
+                *self.work_area_cache.lock().unwrap() = Some(work_area);
+                Some(work_area)
+            }
+            None => self.cached_work_area(),
+        }
+    }
+
+    fn cached_work_area(&self) -> Option<WorkArea> {
+        let cached = *self.work_area_cache.lock().unwrap();
+        (self.on_monitor_unavailable)(cached.is_some());
+        cached
+    }
+
+    // Keep a size between the minimum and the work area it will be shown on,
+    // less the margins
+    pub fn clamp(&self, size: WindowSize) -> WindowSize {
+        clamp_window_size(size, self.work_area().map(|area| area.logical_size()), &self.limits)
+    }
+
+    // The remembered size (or the default one), clamped to fit
+    pub fn restored_size(&self) -> WindowSize {
+        self.clamp(self.remembered_size.unwrap_or(self.limits.default_size()))
+    }
+
+    // Where a window of `size` sits at the anchor on the chosen monitor, or
+    // where the user dragged it. A cached work area can belong to a monitor
+    // that's since been unplugged, so a position off every connected monitor
+    // is worked out again on the primary one.
+    pub fn anchored_position(&self, size: WindowSize) -> Result<PhysicalPosition<i32>, String> {
+        if let Some(position) = self.user_position {
+            return Ok(position);
+        }
+        let work_area = self.work_area().ok_or_else(|| "Failed to get monitor information".to_string())?;
+        let size = LogicalSize::new(size.width, size.height);
+        let origin = anchored_origin(work_area, size, self.anchor, self.limits.margin);
+        let primary = self.window.primary_monitor().ok().flatten().map(|monitor| WorkArea::of(&monitor));
+        let physical_size = size.to_physical::<u32>(work_area.scale_factor);
+        Ok(match fallback_work_area(origin, physical_size, &self.connected_work_areas(), primary) {
+            Some(primary) => anchored_origin(primary, size, self.anchor, self.limits.margin),
+            None => origin,
+        })
+    }
+
+    fn connected_work_areas(&self) -> Vec<WorkArea> {
+        self.window
+            .available_monitors()
+            .map(|monitors| monitors.iter().map(WorkArea::of).collect())
+            .unwrap_or_default()
+    }
+
+    // Size of a window mode on the chosen monitor, and where it goes if not
+    // at the anchor: the overlay covers the whole work area
+    pub fn mode_geometry(&self, mode: WindowMode) -> Result<(WindowSize, Option<PhysicalPosition<i32>>), String> {
+        match mode {
+            WindowMode::Compact => Ok((self.clamp(self.limits.compact), None)),
+            WindowMode::Expanded => Ok((self.clamp(self.limits.expanded), None)),
+            WindowMode::FullscreenOverlay => {
+                let work_area = self.work_area().ok_or_else(|| "Failed to get monitor information".to_string())?;
+                Ok((work_area.logical_size(), Some(work_area.position)))
+            }
+        }
+    }
+
+    // The work area of the monitor the window is on, for the overlay, or the
+    // chosen monitor's if that can't be read
+    pub fn current_work_area(&self) -> Option<WorkArea> {
+        match self.window.current_monitor() {
+            Ok(Some(monitor)) => Some(WorkArea::of(&monitor)),
+            _ => self.work_area(),
+        }
+    }
+
+    // Set the size and move to the anchor for it. The size is applied even
+    // when the position can't be worked out.
+    pub fn apply_size(&self, size: WindowSize) -> tauri::Result<()> {
+        let position = self.anchored_position(size);
+        self.window.set_size(LogicalSize::new(size.width, size.height))?;
+        match position {
+            Ok(position) => self.move_to(position, size)?,
+            Err(e) => eprintln!("⚠️ Window resized but not repositioned: {}", e),
+        }
+        Ok(())
+    }
+
+    // Change only the size, leaving the window where it is unless it would
+    // then hang off the work area
+    pub fn resize_in_place(&self, size: WindowSize) -> tauri::Result<()> {
+        self.window.set_size(LogicalSize::new(size.width, size.height))?;
+        let position = self.window.outer_position()?;
+        self.move_to(position, size)
+    }
+
+    // Every programmatic move goes through here, so the window never hangs
+    // off the work area after a resize, a resolution change or a display
+    // being unplugged
+    pub fn move_to(&self, position: PhysicalPosition<i32>, size: WindowSize) -> tauri::Result<()> {
+        let scale_factor = self.window.scale_factor()?;
+        let size = LogicalSize::new(size.width, size.height).to_physical::<u32>(scale_factor);
+        let position = match self.work_area_for(position, size) {
+            Some(work_area) => clamp_inside_work_area(position, size, work_area),
+            None => position,
+        };
+        if self.window.outer_position().ok() == Some(position) {
+            return Ok(());
+        }
+        self.set_position(position)
+    }
+
+    fn set_position(&self, position: PhysicalPosition<i32>) -> tauri::Result<()> {
+        *self.programmatic_moves.lock().unwrap() = Some(Instant::now() + PROGRAMMATIC_MOVE_GRACE);
+        self.window.set_position(position)
+    }
+
+    // Pull the window back onto a monitor if less than half of it is on
+    // one. Returns whether it had to move.
+    pub fn ensure_visible(&self) -> tauri::Result<bool> {
+        let position = self.window.outer_position()?;
+        let size = self.window.outer_size()?;
+        let clamped = self.keep_on_screen(position, size);
+        if clamped == position {
+            return Ok(false);
+        }
+        self.set_position(clamped)?;
+        Ok(true)
+    }
+
+    // A window the user dragged may hang off the edge, as long as at least
+    // half of it is on screen
+    fn keep_on_screen(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) -> PhysicalPosition<i32> {
+        match self.work_area_for(position, size) {
+            Some(work_area) => clamp_to_monitor(position, size, work_area),
+            None => position,
+        }
+    }
+
+    // The work area the window mostly overlaps, or the primary one if it's on none
+    fn work_area_for(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) -> Option<WorkArea> {
+        if let Some(work_area) = most_overlapped(position, size, &self.connected_work_areas()) {
+            return Some(work_area);
+        }
+        match self.window.primary_monitor() {
+            Ok(Some(monitor)) => Some(WorkArea::of(&monitor)),
+            _ => self.cached_work_area(),
+        }
+    }
+
+    // Show the window at `size`, clamped to fit, at the anchor
+    pub fn show_at_anchor(&self, size: WindowSize) -> WindowSize {
+        self.window.set_max_size(None::<LogicalSize<f64>>)
+            .unwrap_or_else(|e| eprintln!("Failed to remove max size: {:?}", e));
+        let size = self.clamp(size);
+        self.apply_size(size)
+            .unwrap_or_else(|e| eprintln!("Failed to size window: {:?}", e));
+        self.present();
+        size
+    }
+
+    // Show with the always-on-top setting, focusing the window unless it's on
+    // top and set to appear without taking focus
+    pub fn present(&self) {
+        self.window.set_always_on_top(self.always_on_top)
+            .unwrap_or_else(|e| eprintln!("Failed to set always on top: {:?}", e));
+        self.window.show().unwrap_or_else(|e| eprintln!("Failed to show window: {:?}", e));
+        if !(self.always_on_top && self.show_without_focus) {
+            self.window.set_focus().unwrap_or_else(|e| eprintln!("Failed to focus window: {:?}", e));
+        }
+    }
+
+    // Hide the window, returning the size it had so the next show can restore it
+    pub fn hide(&self) -> Option<WindowSize> {
+        let size = self.size();
+        self.window.hide().unwrap_or_else(|e| eprintln!("Failed to hide window: {:?}", e));
+        size
+    }
+
+    // The monitor the placement picks, falling back to the primary one
+    fn placement_monitor(&self) -> Option<Monitor> {
+        let window = &self.window;
+        let chosen = match &self.placement {
+            MonitorPlacement::Primary => None,
+            MonitorPlacement::Cursor => window.cursor_position().ok().and_then(|cursor| {
+                window.available_monitors().ok()?.into_iter().find(|monitor| {
+                    monitor_contains(*monitor.position(), *monitor.size(), cursor.x, cursor.y)
+                })
+            }),
+            placement => match window.available_monitors() {
+                Ok(monitors) => {
+                    let names: Vec<Option<String>> = monitors.iter().map(|monitor| monitor.name().cloned()).collect();
+                    let chosen = chosen_display(&names, placement).map(|index| monitors[index].clone());
+                    (self.on_display_missing)(placement, chosen.is_none());
+                    chosen
+                }
+                Err(_) => None,
+            },
+        };
+        chosen.or_else(|| window.primary_monitor().ok().flatten())
+    }
+}
+
+// Which of the enumerated monitors an index or name placement picks; None for
+// the other placements or if it isn't connected. Names are compared ignoring
+// case, like Windows does.
+fn chosen_display(names: &[Option<String>], placement: &MonitorPlacement) -> Option<usize> {
+    match placement {
+        MonitorPlacement::Index(index) => (*index < names.len()).then_some(*index),
+        MonitorPlacement::Name(name) => names
+            .iter()
+            .position(|candidate| candidate.as_deref().is_some_and(|candidate| candidate.eq_ignore_ascii_case(name))),
+        MonitorPlacement::Primary | MonitorPlacement::Cursor => None,
+    }
+}
+
+fn monitor_contains(position: PhysicalPosition<i32>, size: PhysicalSize<u32>, x: f64, y: f64) -> bool {
+    let left = position.x as f64;
+    let top = position.y as f64;
+    x >= left && x < left + size.width as f64 && y >= top && y < top + size.height as f64
+}
+
+// A window with less than half its area inside is moved fully inside, or to
+// the top left corner if it's bigger than the work area
+pub fn clamp_to_monitor(position: PhysicalPosition<i32>, size: PhysicalSize<u32>, work_area: WorkArea) -> PhysicalPosition<i32> {
+    let area = size.width as u64 * size.height as u64;
+    if area == 0 || overlap(position, size, work_area) * 2 >= area {
+        return position;
+    }
+    clamp_inside_work_area(position, size, work_area)
+}
+
+// Move a window of `size` at `position` the least distance that puts all of
+// it inside the work area; one bigger than the work area goes to its top left
+pub fn clamp_inside_work_area(position: PhysicalPosition<i32>, size: PhysicalSize<u32>, work_area: WorkArea) -> PhysicalPosition<i32> {
+    let clamp_axis = |start: i32, size: u32, area_start: i32, area_extent: u32| {
+        let max = area_start as i64 + area_extent as i64 - size as i64;
+        (start as i64).min(max).max(area_start as i64) as i32
+    };
+    PhysicalPosition::new(
+        clamp_axis(position.x, size.width, work_area.position.x, work_area.size.width),
+        clamp_axis(position.y, size.height, work_area.position.y, work_area.size.height),
+    )
+}
+
+// Physical pixels of the window inside the work area
+fn overlap(position: PhysicalPosition<i32>, size: PhysicalSize<u32>, work_area: WorkArea) -> u64 {
+    let axis = |start: i32, size: u32, area_start: i32, area_extent: u32| {
+        let from = (start as i64).max(area_start as i64);
+        let to = (start as i64 + size as i64).min(area_start as i64 + area_extent as i64);
+        (to - from).max(0) as u64
+    };
+    axis(position.x, size.width, work_area.position.x, work_area.size.width)
+        * axis(position.y, size.height, work_area.position.y, work_area.size.height)
+}
+
+// The work area sharing the most pixels with the window; None if it's on none of them
+fn most_overlapped(position: PhysicalPosition<i32>, size: PhysicalSize<u32>, work_areas: &[WorkArea]) -> Option<WorkArea> {
+    work_areas
+        .iter()
+        .map(|&work_area| (overlap(position, size, work_area), work_area))
+        .filter(|(overlap, _)| *overlap > 0)
+        .max_by_key(|(overlap, _)| *overlap)
+        .map(|(_, work_area)| work_area)
+}
+
+// The primary work area if a window at `position` wouldn't be on any of the
+// connected ones; None if it's fine where it is, or if monitors couldn't be
+// enumerated and there's nothing to check against
+fn fallback_work_area(
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    connected: &[WorkArea],
+    primary: Option<WorkArea>,
+) -> Option<WorkArea> {
+    if connected.is_empty() || most_overlapped(position, size, connected).is_some() {
+        return None;
+    }
+    primary
+}
+
+// Keep a size between the minimum and the work area less the margins on
+// either side. Where the minimum doesn't fit inside the margins it still has
+// to fit the work area: the window shrinks rather than hangs off screen.
+// Values that aren't numbers (from a hand-edited file) fall back to the
+// default size.
+pub fn clamp_window_size(size: WindowSize, work_area: Option<WindowSize>, limits: &WindowConfig) -> WindowSize {
+    if !size.width.is_finite() || !size.height.is_finite() {
+        return limits.default_size();
+    }
+    let Some(work_area) = work_area else {
+        return WindowSize { width: size.width.max(limits.min_width), height: size.height.max(limits.min_height) };
+    };
+    let margins = 2.0 * limits.margin as f64;
+    let axis = |size: f64, min: f64, extent: f64| size.min(extent - margins).max(min.min(extent));
+    WindowSize {
+        width: axis(size.width, limits.min_width, work_area.width),
+        height: axis(size.height, limits.min_height, work_area.height),
+    }
+}
+
+// The size resize_window applies for a requested size, or None if it's within
+// 10px of the current size. There are no maximums, just the configured minimum.
+pub fn resize_target(current: PhysicalSize<u32>, width: f64, height: f64, min: WindowSize) -> Option<(f64, f64)> {
+    let new_width = width.max(min.width);
+    let new_height = height.max(min.height);
+    let changed = (current.width as f64 - new_width).abs() > 10.0 || (current.height as f64 - new_height).abs() > 10.0;
+    changed.then_some((new_width, new_height))
+}
+
+// The work area is in physical pixels, while window sizes are set in logical
+// ones, so the size and margin are scaled by the monitor's factor first
+pub fn anchored_origin(work_area: WorkArea, size: LogicalSize<f64>, anchor: WindowAnchor, margin: u32) -> PhysicalPosition<i32> {
+    let size = size.to_physical::<u32>(work_area.scale_factor);
+    let margin = (margin as f64 * work_area.scale_factor).round() as u32;
+    let (horizontal, vertical) = anchor_alignment(anchor);
+    let x = align(work_area.position.x, work_area.size.width, size.width, horizontal, margin);
+    let y = align(work_area.position.y, work_area.size.height, size.height, vertical, margin);
+    PhysicalPosition::new(x, y)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Align {
+    Start,
+    Center,
+    End,
+}
+
+// Horizontal and vertical alignment of an anchor
+fn anchor_alignment(anchor: WindowAnchor) -> (Align, Align) {
+    match anchor {
+        WindowAnchor::TopCenter => (Align::Center, Align::Start),
+        WindowAnchor::BottomCenter => (Align::Center, Align::End),
+        WindowAnchor::TopLeft => (Align::Start, Align::Start),
+        WindowAnchor::TopRight => (Align::End, Align::Start),
+        WindowAnchor::BottomLeft => (Align::Start, Align::End),
+        WindowAnchor::BottomRight | WindowAnchor::Tray => (Align::End, Align::End),
+        WindowAnchor::Center => (Align::Center, Align::Center),
+    }
+}
+
+// Where a window of `size` starts along one axis of the work area
+fn align(start: i32, extent: u32, size: u32, align: Align, margin: u32) -> i32 {
+    let position = match align {
+        Align::Start => start as i64 + margin as i64,
+        Align::Center => return centered(start, extent, size),
+        Align::End => start as i64 + extent as i64 - size as i64 - margin as i64,
+    };
+    position.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+// Position that centers a window along one axis of the work area. Monitors to
+// the left of or above the primary one have negative positions, so this is
+// done in i64 and clamped instead of risking overflow in i32.
+fn centered(start: i32, extent: u32, size: u32) -> i32 {
+    let position = start as i64 + (extent as i64 - size as i64) / 2;
+    position.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+// Cubic ease-out: fast at first, settling gently on the target
+pub fn ease_out(progress: f64) -> f64 {
+    1.0 - (1.0 - progress.clamp(0.0, 1.0)).powi(3)
+}
+
+pub fn lerp(from: f64, to: f64, amount: f64) -> f64 {
+    from + (to - from) * amount
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn cursor_on_secondary_monitor_is_found() {
+        // Secondary monitor to the left of a 1920x1080 primary
+        let secondary = (PhysicalPosition::new(-1280, 0), PhysicalSize::new(1280, 1024));
+        assert!(monitor_contains(secondary.0, secondary.1, -640.5, 500.0));
+        assert!(!monitor_contains(secondary.0, secondary.1, 0.0, 500.0));
+        assert!(!monitor_contains(secondary.0, secondary.1, -640.0, 1024.0));
+    }
+
+    #[test]
+    fn displays_are_chosen_by_index_or_name() {
+        let names = [Some(r"\\.\DISPLAY1".to_string()), None, Some(r"\\.\DISPLAY3".to_string())];
+        assert_eq!(chosen_display(&names, &MonitorPlacement::Index(1)), Some(1));
+        assert_eq!(chosen_display(&names, &MonitorPlacement::Index(3)), None);
+        assert_eq!(chosen_display(&names, &MonitorPlacement::Name(r"\\.\display3".to_string())), Some(2));
+        // Unplugged
+        assert_eq!(chosen_display(&names, &MonitorPlacement::Name(r"\\.\DISPLAY2".to_string())), None);
+        assert_eq!(chosen_display(&names, &MonitorPlacement::Primary), None);
+    }
+
+    #[test]
+    fn bottom_and_right_anchors_account_for_the_window_size() {
+        // 1920x1040 work area, 480x320 window, 50px margin
+        assert_eq!(align(0, 1920, 480, Align::End, 50), 1390);
+        assert_eq!(align(0, 1040, 320, Align::End, 50), 670);
+        assert_eq!(align(-1280, 1024, 320, Align::Start, 50), -1230);
+        assert_eq!(align(0, 1040, 320, Align::Center, 50), 360);
+    }
+
+    #[test]
+    fn window_is_centered_at_any_scale_factor() {
+        // A 480x320 (logical) window on a 2560x1400 work area left of the primary
+        let size = LogicalSize::new(480.0, 320.0);
+
+        for scale_factor in [1.0, 1.25, 1.5, 2.0] {
+            let work_area = WorkArea {
+                position: PhysicalPosition::new(-2560, 0),
+                size: PhysicalSize::new(2560, 1400),
+                scale_factor,
+            };
+            let origin = anchored_origin(work_area, size, WindowAnchor::TopCenter, 50);
+            let physical_width = (480.0 * scale_factor) as i32;
+            // Equal space either side of the window, up to rounding
+            let left_gap = origin.x - work_area.position.x;
+            let right_gap = work_area.position.x + 2560 - (origin.x + physical_width);
+            assert!((left_gap - right_gap).abs() <= 1, "scale {}: {} vs {}", scale_factor, left_gap, right_gap);
+            assert_eq!(origin.y, (50.0 * scale_factor).round() as i32);
+
+            let origin = anchored_origin(work_area, size, WindowAnchor::BottomRight, 50);
+            let margin = (50.0 * scale_factor).round() as i32;
+            assert_eq!(origin.x + physical_width + margin, 0);
+            assert_eq!(origin.y + (320.0 * scale_factor) as i32 + margin, 1400);
+            assert_eq!(anchored_origin(work_area, size, WindowAnchor::Tray, 50), origin);
+        }
+    }
+
+    #[test]
+    fn ease_out_starts_fast_and_ends_on_target() {
+        assert_eq!(ease_out(0.0), 0.0);
+        assert_eq!(ease_out(1.0), 1.0);
+        assert_eq!(ease_out(2.0), 1.0);
+        assert!(ease_out(0.5) > 0.5);
+        assert!(ease_out(0.25) < ease_out(0.5));
+    }
+
+    #[test]
+    fn absurd_window_sizes_are_clamped() {
+        let work_area = Some(WindowSize { width: 1920.0, height: 1040.0 });
+        let limits = WindowConfig::default();
+        let clamp = |width, height| clamp_window_size(WindowSize { width, height }, work_area, &limits);
+        assert_eq!(clamp(5.0, 5.0), WindowSize { width: 350.0, height: 200.0 });
+        // Inside the 50px margins
+        assert_eq!(clamp(20000.0, 20000.0), WindowSize { width: 1820.0, height: 940.0 });
+        assert_eq!(clamp(640.0, 400.0), WindowSize { width: 640.0, height: 400.0 });
+        assert_eq!(clamp(f64::NAN, 400.0), limits.default_size());
+    }
+
+    #[test]
+    fn sizes_shrink_to_fit_narrow_monitors() {
+        let limits = WindowConfig::default();
+        // A 1366x768 laptop with the taskbar at the bottom
+        let laptop = Some(WindowSize { width: 1366.0, height: 728.0 });
+        let clamp = |width, height| clamp_window_size(WindowSize { width, height }, laptop, &limits);
+        assert_eq!(clamp(1920.0, 600.0), WindowSize { width: 1266.0, height: 600.0 });
+        assert_eq!(clamp(1300.0, 700.0), WindowSize { width: 1266.0, height: 628.0 });
+        // A minimum bigger than the space inside the margins still fits the screen
+        let tiny = Some(WindowSize { width: 380.0, height: 180.0 });
+        assert_eq!(clamp_window_size(WindowSize { width: 400.0, height: 300.0 }, tiny, &limits), WindowSize { width: 350.0, height: 180.0 });
+        // Without monitor information only the minimum applies
+        assert_eq!(clamp_window_size(WindowSize { width: 5000.0, height: 10.0 }, None, &limits), WindowSize { width: 5000.0, height: 200.0 });
+    }
+
+    #[test]
+    fn windows_at_any_anchor_end_up_fully_inside() {
+        let laptop = WorkArea {
+            position: PhysicalPosition::new(0, 0),
+            size: PhysicalSize::new(1366, 728),
+            scale_factor: 1.0,
+        };
+        let size = PhysicalSize::new(600, 400);
+        let inside = |position: PhysicalPosition<i32>| {
+            let position = clamp_inside_work_area(position, size, laptop);
+            assert!(position.x >= 0 && position.y >= 0, "{:?}", position);
+            assert!(position.x + 600 <= 1366 && position.y + 400 <= 728, "{:?}", position);
+            position
+        };
+        // Hanging off each edge by a little is pulled in the least distance
+        assert_eq!(inside(PhysicalPosition::new(-20, 100)), PhysicalPosition::new(0, 100));
+        assert_eq!(inside(PhysicalPosition::new(800, 100)), PhysicalPosition::new(766, 100));
+        assert_eq!(inside(PhysicalPosition::new(300, -5)), PhysicalPosition::new(300, 0));
+        assert_eq!(inside(PhysicalPosition::new(300, 500)), PhysicalPosition::new(300, 328));
+        for anchor in [WindowAnchor::TopLeft, WindowAnchor::TopRight, WindowAnchor::BottomLeft, WindowAnchor::BottomRight, WindowAnchor::Center] {
+            // A margin too big for the window still leaves it on screen
+            inside(anchored_origin(laptop, LogicalSize::new(600.0, 400.0), anchor, 400));
+        }
+        // Bigger than the work area: the top left corner stays visible
+        let huge = PhysicalSize::new(2000, 1000);
+        assert_eq!(clamp_inside_work_area(PhysicalPosition::new(-100, 50), huge, laptop), PhysicalPosition::new(0, 0));
+    }
+
+    #[test]
+    fn windows_mostly_off_screen_are_pulled_back() {
+        let primary = WorkArea {
+            position: PhysicalPosition::new(0, 0),
+            size: PhysicalSize::new(1920, 1040),
+            scale_factor: 1.0,
+        };
+        let size = PhysicalSize::new(400, 300);
+        // Half on screen is enough
+        let half = PhysicalPosition::new(1720, 100);
+        assert_eq!(clamp_to_monitor(half, size, primary), half);
+        // Where an unplugged monitor on the right used to be
+        assert_eq!(clamp_to_monitor(PhysicalPosition::new(2500, 900), size, primary), PhysicalPosition::new(1520, 740));
+        assert_eq!(clamp_to_monitor(PhysicalPosition::new(-390, -200), size, primary), PhysicalPosition::new(0, 0));
+        // Too big to fit: its top left corner stays on screen
+        let huge = PhysicalSize::new(4000, 3000);
+        assert_eq!(clamp_to_monitor(PhysicalPosition::new(-3000, 0), huge, primary), PhysicalPosition::new(0, 0));
+
+        let secondary = WorkArea { position: PhysicalPosition::new(-1280, 0), ..primary };
+        let areas = [primary, secondary];
+        assert_eq!(most_overlapped(PhysicalPosition::new(-300, 0), size, &areas), Some(secondary));
+        assert_eq!(most_overlapped(PhysicalPosition::new(5000, 0), size, &areas), None);
+    }
+
+    #[test]
+    fn positions_on_unplugged_monitors_fall_back_to_the_primary() {
+        let primary = WorkArea {
+            position: PhysicalPosition::new(0, 0),
+            size: PhysicalSize::new(1920, 1040),
+            scale_factor: 1.0,
+        };
+        let left = WorkArea { position: PhysicalPosition::new(-2560, 0), size: PhysicalSize::new(2560, 1400), ..primary };
+        let above = WorkArea { position: PhysicalPosition::new(0, -1080), ..primary };
+        let size = PhysicalSize::new(480, 320);
+        // Top center of the left monitor
+        let on_left = PhysicalPosition::new(-1520, 50);
+
+        assert_eq!(fallback_work_area(on_left, size, &[primary, left], Some(primary)), None);
+        assert_eq!(fallback_work_area(on_left, size, &[primary], Some(primary)), Some(primary));
+        assert_eq!(fallback_work_area(on_left, size, &[primary, above], Some(primary)), Some(primary));
+        // Straddling the edge counts as on screen
+        assert_eq!(fallback_work_area(PhysicalPosition::new(-240, 50), size, &[primary], Some(primary)), None);
+        assert_eq!(fallback_work_area(on_left, size, &[], Some(primary)), None);
+        assert_eq!(fallback_work_area(on_left, size, &[above], None), None);
+    }
+
+    #[test]
+    fn work_area_size_is_logical() {
+        let work_area = WorkArea {
+            position: PhysicalPosition::new(0, 0),
+            size: PhysicalSize::new(3840, 2080),
+            scale_factor: 2.0,
+        };
+        assert_eq!(work_area.logical_size(), WindowSize { width: 1920.0, height: 1040.0 });
+    }
+
+    proptest! {
+        #[test]
+        fn centered_stays_near_work_area(
+            work_x in -32768i32..=32767,
+            work_width in 0u32..=32767,
+            window_width in 0u32..=32767,
+        ) {
+            let x = centered(work_x, work_width, window_width);
+            prop_assert!(x >= work_x - window_width as i32);
+            prop_assert!(x <= work_x + work_width as i32);
+        }
+
+        #[test]
+        fn centered_does_not_overflow(work_x: i32, work_width: u32, window_width: u32) {
+            let x = centered(work_x, work_width, window_width) as i64;
+            prop_assert!(x >= work_x as i64 - window_width as i64);
+            prop_assert!(x <= work_x as i64 + work_width as i64);
+        }
+    }
+}
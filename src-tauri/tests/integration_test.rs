@@ -0,0 +1,94 @@
+// integration_test.rs - Drive the app's commands through Tauri's mock runtime
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use app_lib::config::{AppConfig, WindowConfig};
+use serde_json::{json, Value};
+use tauri::ipc::{CallbackFn, InvokeBody};
+use tauri::test::{get_ipc_response, mock_builder, mock_context, noop_assets, MockRuntime, INVOKE_KEY};
+use tauri::webview::InvokeRequest;
+use tauri::{App, Listener, PhysicalSize, WebviewWindow, WebviewWindowBuilder};
+
+// The full app state and command handler, without a wake word detector.
+// Fades are off: the mock window always reports itself visible, and showing
+// and hiding would otherwise finish on a timer after the command returns.
+fn create_app() -> (App<MockRuntime>, WebviewWindow<MockRuntime>) {
+    let app = mock_builder()
+        .invoke_handler(app_lib::invoke_handler())
+        .build(mock_context(noop_assets()))
+        .expect("failed to build test app");
+    let config = AppConfig { fade_ms: 0, ..AppConfig::default() };
+    app_lib::manage_state(app.handle(), config, None);
+    let window = WebviewWindowBuilder::new(&app, "main", Default::default())
+        .build()
+        .expect("failed to create main window");
+    (app, window)
+}
+
+fn invoke(window: &WebviewWindow<MockRuntime>, cmd: &str, args: Value) -> Result<Value, Value> {
+    get_ipc_response(
+        window,
+        InvokeRequest {
+            cmd: cmd.into(),
+            callback: CallbackFn(0),
+            error: CallbackFn(1),
+            url: "http://tauri.localhost".parse().unwrap(),
+            body: InvokeBody::Json(args),
+            headers: Default::default(),
+            invoke_key: INVOKE_KEY.to_string(),
+        },
+    )
+    .map(|body| body.deserialize::<Value>().unwrap())
+}
+
+// Set a flag whenever `event` is emitted
+fn watch(app: &App<MockRuntime>, event: &str) -> Arc<AtomicBool> {
+    let seen = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&seen);
+    app.listen(event.to_string(), move |_| flag.store(true, Ordering::SeqCst));
+    seen
+}
+
+#[tokio::test]
+async fn show_window_emits_window_shown() {
+    let (app, window) = create_app();
+    let shown = watch(&app, "window-shown");
+
+    invoke(&window, "show_window", json!({})).expect("show_window failed");
+
+    assert!(shown.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn hide_window_emits_window_hidden() {
+    let (app, window) = create_app();
+    let hidden = watch(&app, "window-hidden");
+
+    invoke(&window, "hide_window", json!({})).expect("hide_window failed");
+
+    assert!(hidden.load(Ordering::SeqCst));
+}
+
+// The mock window always reports 0x0 and has no monitor, so the threshold
+// resize_window applies is checked directly
+#[test]
+fn resize_window_ignores_changes_under_10px() {
+    let current = PhysicalSize::new(480, 320);
+    let min = WindowConfig::default().min_size();
+
+    assert_eq!(app_lib::resize_target(current, 485.0, 315.0, min), None);
+    assert_eq!(app_lib::resize_target(current, 490.0, 320.0, min), None);
+    assert_eq!(app_lib::resize_target(current, 500.0, 320.0, min), Some((500.0, 320.0)));
+    // Requests below the minimum are clamped before comparing
+    assert_eq!(app_lib::resize_target(PhysicalSize::new(350, 200), 100.0, 100.0, min), None);
+}
+
+#[tokio::test]
+async fn get_app_health_reports_sapi_not_initialized_without_detector() {
+    let (_app, window) = create_app();
+
+    let health = invoke(&window, "get_app_health", json!({})).expect("get_app_health failed");
+
+    assert_eq!(health["sapi_initialized"], json!(false));
+    assert_eq!(health["wake_word_state"], Value::Null);
+}
@@ -434,10 +434,14 @@ function App() {
       try {
         // Set up the event listener for wake word detection.
         unlisten = await listen("wake-word-detected", (event) => {
-          const payload = event.payload as { keyword_index: number };
-          const keyword_index = payload.keyword_index;
-          const keywords = ["Hey Jackson"];
-          const keyword = keywords[keyword_index] || "Unknown";
+          const payload = event.payload as {
+            keyword_index: number;
+            phrase: string;
+            confidence: number;
+            timestamp_ms: number;
+            audio_energy_rms: number;
+          };
+          const keyword = payload.phrase || "Unknown";
           
           setDetectionCount((prev) => prev + 1);
           setStatusMessage(`Listening for speech...`);
@@ -462,7 +466,16 @@ function App() {
           }
         });
         
-        // Start the backend wake word detection
+        // Listeners are attached; let the backend deliver queued events
+        await invoke("frontend_ready");
+        
+        // Start the backend wake word detection, unless it's turned off for startup
+        if (!(await invoke<boolean>("get_listen_on_startup"))) {
+          setStatusMessage("Not listening; start listening from the tray");
+          setInitializationError(false);
+          console.log("⏸️ Listening on startup is off");
+          return;
+        }
         await invoke("start_wake_word_detection");
         setStatusMessage('Listening for "Hey Jackson"...');
         setInitializationError(false);